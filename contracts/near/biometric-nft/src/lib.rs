@@ -0,0 +1,22 @@
+//! Biometric NFT contract build
+//!
+//! The contract and the modules it depends on live in `src/near-wasm`,
+//! whose own crate exports a different contract, so they are compiled here
+//! as a separate deployable crate.
+
+#[path = "../../../../src/near-wasm/src/biometric_contract.rs"]
+pub mod biometric_contract;
+#[path = "../../../../src/near-wasm/src/biometric_fusion.rs"]
+pub mod biometric_fusion;
+#[path = "../../../../src/near-wasm/src/emotion_palette.rs"]
+pub mod emotion_palette;
+#[path = "../../../../src/near-wasm/src/emotion_smoothing.rs"]
+pub mod emotion_smoothing;
+#[path = "../../../../src/near-wasm/src/emotion_taxonomy.rs"]
+pub mod emotion_taxonomy;
+#[path = "../../../../src/near-wasm/src/emotional_aggregation.rs"]
+pub mod emotional_aggregation;
+#[path = "../../../../src/near-wasm/src/interactive_advanced.rs"]
+pub mod interactive_advanced;
+
+pub use biometric_contract::BiometricNFTContract;
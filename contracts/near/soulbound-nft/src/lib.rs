@@ -9,6 +9,7 @@ use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+use emotion_model::{Sanitize, ValidationPolicy};
 mod metadata;
 
 /// This spec can be treated like a version of the standard.
@@ -46,16 +47,12 @@ pub struct BiometricData {
     pub verification_method: String, // "AI-Enhanced", "Manual", etc.
 }
 
-/// Emotion data from AI inference
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub struct EmotionData {
-    pub primary_emotion: String,     // "Happy", "Sad", "Focused", etc.
-    pub confidence: f64,             // AI confidence (0.0 - 1.0)
-    pub secondary_emotions: Vec<(String, f64)>, // Other emotions with scores
-    pub arousal: f64,                // Arousal level (-1.0 to 1.0)
-    pub valence: f64,                // Valence level (-1.0 to 1.0)
-}
+/// Emotion data from AI inference; shared with emotion-model, which owns
+/// its validation and conversions
+pub use emotion_model::SoulboundEmotionData as EmotionData;
+
+/// Soulbound records are permanent, so malformed emotion data is rejected
+const EMOTION_POLICY: ValidationPolicy = ValidationPolicy::Reject;
 
 /// Historical emotion record
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -127,6 +124,9 @@ impl BiometricSoulboundNFT {
         quality_score: f64,
         biometric_hash: String,
     ) -> Token {
+        let emotion_data = emotion_data
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid emotion data: {}", e)));
         let token_id = format!("biometric_{}_{}", env::signer_account_id(), env::block_timestamp());
         
         // Validate biometric quality
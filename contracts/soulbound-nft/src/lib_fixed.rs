@@ -8,6 +8,7 @@ use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, near_bindgen, AccountId, Balance, PanicOnDefault, Promise, PromiseOrValue};
+use emotion_model::{Sanitize, ValidationPolicy};
 
 pub type TokenId = String;
 pub type Balance = U128;
@@ -43,16 +44,12 @@ pub struct BiometricData {
     pub verification_method: String,
 }
 
-/// Emotion data from AI inference
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub struct EmotionData {
-    pub primary_emotion: String,
-    pub confidence: f64,
-    pub secondary_emotions: Vec<(String, f64)>,
-    pub arousal: f64,
-    pub valence: f64,
-}
+/// Emotion data from AI inference; shared with emotion-model, which owns
+/// its validation and conversions
+pub use emotion_model::SoulboundEmotionData as EmotionData;
+
+/// Soulbound records are permanent, so malformed emotion data is rejected
+const EMOTION_POLICY: ValidationPolicy = ValidationPolicy::Reject;
 
 /// Historical emotion record
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -155,6 +152,9 @@ impl Contract {
         quality_score: f64,
         biometric_hash: String,
     ) -> Token {
+        let emotion_data = emotion_data
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid emotion data: {}", e)));
         // Validate biometric quality
         assert!(quality_score >= 0.7, "Biometric quality too low: {}", quality_score);
         
@@ -0,0 +1,332 @@
+//! Compatibility shims for emotion data stored in the older layouts
+//!
+//! Each [`LegacyLayout`] mirrors the field order and types of a type that
+//! predates [`Emotion`], so Borsh state and JSON payloads written with it can
+//! be read and written without depending on the crate that defined it.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{Emotion, EmotionError};
+
+/// Layouts of the emotion types that predate the shared model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegacyLayout {
+    /// `fractal_studio::EmotionalVector`: valence, arousal, dominance
+    VadVector,
+    /// `emotional::EmotionalVector`: valence, arousal, dominance, timestamp
+    TimedVadVector,
+    /// `dynamic_nft::EmotionalState`: VAD, confidence, timestamp
+    VadState,
+    /// `interactive_advanced::DetailedEmotionalState`
+    DetailedState,
+    /// Soulbound contracts' `EmotionData`: f64 values, arousal in -1..1
+    SoulboundEmotionData,
+}
+
+/// Extended dimensions of the detailed state, estimated from VAD when the
+/// source did not measure them
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedDimensions {
+    pub engagement: f32,
+    pub focus: f32,
+    pub stress: f32,
+    pub relaxation: f32,
+    pub intensity: f32,
+}
+
+impl ExtendedDimensions {
+    pub fn estimate(emotion: &Emotion) -> Self {
+        let positivity = (emotion.valence + 1.0) / 2.0;
+        let intensity = ((emotion.valence / 2.0).powi(2)
+            + (emotion.arousal - 0.5).powi(2)
+            + (emotion.dominance - 0.5).powi(2))
+        .sqrt()
+            / 0.866;
+        Self {
+            engagement: emotion.arousal,
+            focus: emotion.dominance,
+            stress: emotion.arousal * (1.0 - positivity),
+            relaxation: (1.0 - emotion.arousal) * positivity,
+            intensity: intensity.min(1.0),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+struct VadVector {
+    valence: f32,
+    arousal: f32,
+    dominance: f32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+struct TimedVadVector {
+    valence: f32,
+    arousal: f32,
+    dominance: f32,
+    timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+struct VadState {
+    valence: f32,
+    arousal: f32,
+    dominance: f32,
+    confidence: f32,
+    timestamp: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+struct DetailedState {
+    valence: f32,
+    arousal: f32,
+    dominance: f32,
+    engagement: f32,
+    focus: f32,
+    stress: f32,
+    relaxation: f32,
+    confidence: f32,
+    primary_emotion: String,
+    intensity: f32,
+}
+
+/// Emotion data as the soulbound contracts store it; they use this type
+/// directly as their `EmotionData`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SoulboundEmotionData {
+    pub primary_emotion: String,
+    pub confidence: f64,
+    pub secondary_emotions: Vec<(String, f64)>,
+    /// Bipolar arousal, -1.0 to 1.0
+    pub arousal: f64,
+    pub valence: f64,
+}
+
+impl From<VadVector> for Emotion {
+    fn from(v: VadVector) -> Self {
+        Emotion::new(v.valence, v.arousal, v.dominance)
+    }
+}
+
+impl From<TimedVadVector> for Emotion {
+    fn from(v: TimedVadVector) -> Self {
+        Emotion::new(v.valence, v.arousal, v.dominance).with_timestamp(v.timestamp)
+    }
+}
+
+impl From<VadState> for Emotion {
+    fn from(s: VadState) -> Self {
+        Emotion::new(s.valence, s.arousal, s.dominance)
+            .with_confidence(s.confidence)
+            .with_timestamp(s.timestamp)
+    }
+}
+
+impl From<DetailedState> for Emotion {
+    fn from(s: DetailedState) -> Self {
+        Emotion::new(s.valence, s.arousal, s.dominance)
+            .with_confidence(s.confidence)
+            .with_label(s.primary_emotion)
+    }
+}
+
+impl From<SoulboundEmotionData> for Emotion {
+    fn from(d: SoulboundEmotionData) -> Self {
+        Emotion::new(
+            d.valence as f32,
+            Emotion::arousal_from_bipolar(d.arousal as f32),
+            0.5,
+        )
+        .with_confidence(d.confidence as f32)
+        .with_label(d.primary_emotion)
+    }
+}
+
+impl From<&Emotion> for VadVector {
+    fn from(e: &Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+        }
+    }
+}
+
+impl From<&Emotion> for TimedVadVector {
+    fn from(e: &Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            timestamp: e.timestamp,
+        }
+    }
+}
+
+impl From<&Emotion> for VadState {
+    fn from(e: &Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            confidence: e.confidence,
+            timestamp: e.timestamp,
+        }
+    }
+}
+
+impl From<&Emotion> for DetailedState {
+    fn from(e: &Emotion) -> Self {
+        let extended = ExtendedDimensions::estimate(e);
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            engagement: extended.engagement,
+            focus: extended.focus,
+            stress: extended.stress,
+            relaxation: extended.relaxation,
+            confidence: e.confidence,
+            primary_emotion: e.label.clone().unwrap_or_default(),
+            intensity: extended.intensity,
+        }
+    }
+}
+
+impl From<&Emotion> for SoulboundEmotionData {
+    fn from(e: &Emotion) -> Self {
+        Self {
+            primary_emotion: e.label.clone().unwrap_or_default(),
+            confidence: e.confidence as f64,
+            secondary_emotions: Vec::new(),
+            arousal: e.bipolar_arousal() as f64,
+            valence: e.valence as f64,
+        }
+    }
+}
+
+impl From<Emotion> for SoulboundEmotionData {
+    fn from(e: Emotion) -> Self {
+        Self::from(&e)
+    }
+}
+
+fn decode_borsh<T: BorshDeserialize + Into<Emotion>>(
+    bytes: &[u8],
+) -> Result<Emotion, EmotionError> {
+    T::try_from_slice(bytes)
+        .map(Into::into)
+        .map_err(|e| EmotionError::Decode(e.to_string()))
+}
+
+fn decode_json<T: for<'de> Deserialize<'de> + Into<Emotion>>(
+    json: &str,
+) -> Result<Emotion, EmotionError> {
+    serde_json::from_str::<T>(json)
+        .map(Into::into)
+        .map_err(|e| EmotionError::Decode(e.to_string()))
+}
+
+/// Read Borsh bytes written with a legacy layout
+pub fn from_borsh(layout: LegacyLayout, bytes: &[u8]) -> Result<Emotion, EmotionError> {
+    match layout {
+        LegacyLayout::VadVector => decode_borsh::<VadVector>(bytes),
+        LegacyLayout::TimedVadVector => decode_borsh::<TimedVadVector>(bytes),
+        LegacyLayout::VadState => decode_borsh::<VadState>(bytes),
+        LegacyLayout::DetailedState => decode_borsh::<DetailedState>(bytes),
+        LegacyLayout::SoulboundEmotionData => decode_borsh::<SoulboundEmotionData>(bytes),
+    }
+}
+
+/// Read a JSON payload written with a legacy layout
+pub fn from_json(layout: LegacyLayout, json: &str) -> Result<Emotion, EmotionError> {
+    match layout {
+        LegacyLayout::VadVector => decode_json::<VadVector>(json),
+        LegacyLayout::TimedVadVector => decode_json::<TimedVadVector>(json),
+        LegacyLayout::VadState => decode_json::<VadState>(json),
+        LegacyLayout::DetailedState => decode_json::<DetailedState>(json),
+        LegacyLayout::SoulboundEmotionData => decode_json::<SoulboundEmotionData>(json),
+    }
+}
+
+/// Encode an emotion as Borsh in a legacy layout
+pub fn to_borsh(layout: LegacyLayout, emotion: &Emotion) -> Vec<u8> {
+    let encoded = match layout {
+        LegacyLayout::VadVector => borsh::to_vec(&VadVector::from(emotion)),
+        LegacyLayout::TimedVadVector => borsh::to_vec(&TimedVadVector::from(emotion)),
+        LegacyLayout::VadState => borsh::to_vec(&VadState::from(emotion)),
+        LegacyLayout::DetailedState => borsh::to_vec(&DetailedState::from(emotion)),
+        LegacyLayout::SoulboundEmotionData => borsh::to_vec(&SoulboundEmotionData::from(emotion)),
+    };
+    encoded.expect("Emotion serialization failed")
+}
+
+/// Encode an emotion as JSON in a legacy layout
+pub fn to_json(layout: LegacyLayout, emotion: &Emotion) -> String {
+    let encoded = match layout {
+        LegacyLayout::VadVector => serde_json::to_string(&VadVector::from(emotion)),
+        LegacyLayout::TimedVadVector => serde_json::to_string(&TimedVadVector::from(emotion)),
+        LegacyLayout::VadState => serde_json::to_string(&VadState::from(emotion)),
+        LegacyLayout::DetailedState => serde_json::to_string(&DetailedState::from(emotion)),
+        LegacyLayout::SoulboundEmotionData => {
+            serde_json::to_string(&SoulboundEmotionData::from(emotion))
+        }
+    };
+    encoded.expect("Emotion serialization failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soulbound_borsh_round_trip() {
+        let stored = SoulboundEmotionData {
+            primary_emotion: "Focused".to_string(),
+            confidence: 0.9,
+            secondary_emotions: vec![("Calm".to_string(), 0.4)],
+            arousal: 0.5,
+            valence: -0.2,
+        };
+        let bytes = borsh::to_vec(&stored).unwrap();
+        let emotion = from_borsh(LegacyLayout::SoulboundEmotionData, &bytes).unwrap();
+        assert_eq!(emotion.arousal, 0.75);
+        assert_eq!(emotion.label.as_deref(), Some("Focused"));
+        assert!(emotion.validate().is_ok());
+
+        let written = to_borsh(LegacyLayout::SoulboundEmotionData, &emotion);
+        let restored = from_borsh(LegacyLayout::SoulboundEmotionData, &written).unwrap();
+        assert_eq!(restored, emotion);
+    }
+
+    #[test]
+    fn test_every_layout_round_trips_vad() {
+        let emotion = Emotion::new(0.25, 0.75, 0.5)
+            .with_confidence(0.5)
+            .with_timestamp(42)
+            .with_label("Joy");
+        for layout in [
+            LegacyLayout::VadVector,
+            LegacyLayout::TimedVadVector,
+            LegacyLayout::VadState,
+            LegacyLayout::DetailedState,
+            LegacyLayout::SoulboundEmotionData,
+        ] {
+            let from_bytes = from_borsh(layout, &to_borsh(layout, &emotion)).unwrap();
+            let from_text = from_json(layout, &to_json(layout, &emotion)).unwrap();
+            assert_eq!(from_bytes, from_text, "{:?}", layout);
+            assert_eq!(from_bytes.valence, 0.25, "{:?}", layout);
+            assert_eq!(from_bytes.arousal, 0.75, "{:?}", layout);
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            from_borsh(LegacyLayout::VadState, &[1, 2, 3]),
+            Err(EmotionError::Decode(_))
+        ));
+        assert!(from_json(LegacyLayout::VadVector, r#"{"valence":0.1}"#).is_err());
+    }
+}
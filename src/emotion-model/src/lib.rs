@@ -0,0 +1,281 @@
+//! Emotion model - Shared emotional state for studios, NFTs and contracts
+//!
+//! One canonical valence/arousal/dominance type with range validation.
+//! Crate-specific emotion types convert to and from [`Emotion`] through
+//! `From` impls, and [`compat`] reads data stored in the older layouts.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub mod compat;
+pub mod validation;
+
+pub use compat::SoulboundEmotionData;
+pub use validation::{Sanitize, ValidationPolicy};
+
+/// Valid valence range (displeasure to pleasure)
+pub const VALENCE_RANGE: (f32, f32) = (-1.0, 1.0);
+/// Valid arousal range (calm to excited)
+pub const AROUSAL_RANGE: (f32, f32) = (0.0, 1.0);
+/// Valid dominance range (submissive to in control)
+pub const DOMINANCE_RANGE: (f32, f32) = (0.0, 1.0);
+/// Valid confidence range
+pub const CONFIDENCE_RANGE: (f32, f32) = (0.0, 1.0);
+/// Arousal range of the soulbound contracts' bipolar scale
+pub const BIPOLAR_AROUSAL_RANGE: (f32, f32) = (-1.0, 1.0);
+
+/// Canonical emotional state
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Emotion {
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
+    /// Nanoseconds since the Unix epoch, 0 when unknown
+    #[serde(default)]
+    pub timestamp: u64,
+    /// Named emotion, when the source provided one
+    #[serde(
+        default,
+        alias = "primary_emotion",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub label: Option<String>,
+}
+
+/// Why an emotion failed validation
+#[derive(Clone, Debug, PartialEq)]
+pub enum EmotionError {
+    NonFinite {
+        field: &'static str,
+    },
+    OutOfRange {
+        field: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    InvalidLabel {
+        field: &'static str,
+        reason: &'static str,
+    },
+    Decode(String),
+}
+
+impl fmt::Display for EmotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmotionError::NonFinite { field } => write!(f, "{} is not a finite number", field),
+            EmotionError::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => {
+                write!(f, "{} {} is outside {}..={}", field, value, min, max)
+            }
+            EmotionError::InvalidLabel { field, reason } => {
+                write!(f, "{} is invalid: {}", field, reason)
+            }
+            EmotionError::Decode(message) => write!(f, "cannot decode emotion: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EmotionError {}
+
+fn full_confidence() -> f32 {
+    1.0
+}
+
+impl Emotion {
+    /// Emotion with full confidence and no timestamp or label
+    pub fn new(valence: f32, arousal: f32, dominance: f32) -> Self {
+        Self {
+            valence,
+            arousal,
+            dominance,
+            confidence: 1.0,
+            timestamp: 0,
+            label: None,
+        }
+    }
+
+    /// Neutral state at the centre of the VAD space
+    pub fn neutral() -> Self {
+        Self::new(0.0, 0.5, 0.5)
+    }
+
+    /// Build an emotion, rejecting values outside the valid ranges
+    pub fn try_new(
+        valence: f32,
+        arousal: f32,
+        dominance: f32,
+        confidence: f32,
+        timestamp: u64,
+    ) -> Result<Self, EmotionError> {
+        let emotion = Self {
+            valence,
+            arousal,
+            dominance,
+            confidence,
+            timestamp,
+            label: None,
+        };
+        emotion.validate()?;
+        Ok(emotion)
+    }
+
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Check every dimension is finite and within its range
+    pub fn validate(&self) -> Result<(), EmotionError> {
+        check("valence", self.valence, VALENCE_RANGE)?;
+        check("arousal", self.arousal, AROUSAL_RANGE)?;
+        check("dominance", self.dominance, DOMINANCE_RANGE)?;
+        check("confidence", self.confidence, CONFIDENCE_RANGE)
+    }
+
+    /// Clamp every dimension into range; non-finite values become neutral
+    /// and zero the confidence
+    pub fn clamped(mut self) -> Self {
+        let mut lost = false;
+        let mut clamp = |value: f32, (min, max): (f32, f32), neutral: f32| {
+            if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                lost = true;
+                neutral
+            }
+        };
+        self.valence = clamp(self.valence, VALENCE_RANGE, 0.0);
+        self.arousal = clamp(self.arousal, AROUSAL_RANGE, 0.5);
+        self.dominance = clamp(self.dominance, DOMINANCE_RANGE, 0.5);
+        self.confidence = clamp(self.confidence, CONFIDENCE_RANGE, 0.0);
+        if lost {
+            self.confidence = 0.0;
+        }
+        self
+    }
+
+    /// Arousal on the -1..1 scale used by the soulbound contracts
+    pub fn bipolar_arousal(&self) -> f32 {
+        self.arousal * 2.0 - 1.0
+    }
+
+    /// Convert -1..1 arousal to the canonical 0..1 scale
+    pub fn arousal_from_bipolar(arousal: f32) -> f32 {
+        (arousal + 1.0) / 2.0
+    }
+}
+
+impl Default for Emotion {
+    fn default() -> Self {
+        Self::neutral()
+    }
+}
+
+/// Euclidean distance between two `(valence, arousal, dominance)` points,
+/// with valence rescaled onto the 0..1 range of the other axes and the
+/// dominance term scaled by `dominance_weight`
+pub fn vad_distance(a: (f32, f32, f32), b: (f32, f32, f32), dominance_weight: f32) -> f32 {
+    let dv = (a.0 - b.0) / 2.0;
+    let da = a.1 - b.1;
+    let dd = a.2 - b.2;
+    (dv * dv + da * da + dominance_weight * dd * dd).sqrt()
+}
+
+fn check(field: &'static str, value: f32, (min, max): (f32, f32)) -> Result<(), EmotionError> {
+    if !value.is_finite() {
+        return Err(EmotionError::NonFinite { field });
+    }
+    if value < min || value > max {
+        return Err(EmotionError::OutOfRange {
+            field,
+            value,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vad_distance_rescales_valence() {
+        let low = (-1.0, 0.0, 0.0);
+        let high = (1.0, 1.0, 1.0);
+        assert_eq!(vad_distance(low, low, 1.0), 0.0);
+        assert!((vad_distance(low, high, 1.0) - 3f32.sqrt()).abs() < 1e-6);
+        assert!((vad_distance((-1.0, 0.5, 0.0), (1.0, 0.5, 1.0), 0.0) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_validate_ranges() {
+        assert!(Emotion::neutral().validate().is_ok());
+        assert_eq!(
+            Emotion::new(0.0, 1.5, 0.5).validate(),
+            Err(EmotionError::OutOfRange {
+                field: "arousal",
+                value: 1.5,
+                min: 0.0,
+                max: 1.0
+            })
+        );
+        assert_eq!(
+            Emotion::try_new(f32::NAN, 0.5, 0.5, 1.0, 0),
+            Err(EmotionError::NonFinite { field: "valence" })
+        );
+    }
+
+    #[test]
+    fn test_clamped() {
+        let emotion = Emotion::new(-3.0, 2.0, -1.0).with_confidence(1.5).clamped();
+        assert_eq!(
+            (emotion.valence, emotion.arousal, emotion.dominance),
+            (-1.0, 1.0, 0.0)
+        );
+        assert_eq!(emotion.confidence, 1.0);
+
+        let broken = Emotion::new(0.4, f32::INFINITY, 0.5).clamped();
+        assert_eq!(broken.arousal, 0.5);
+        assert_eq!(broken.confidence, 0.0);
+        assert!(broken.validate().is_ok());
+    }
+
+    #[test]
+    fn test_bipolar_arousal() {
+        let emotion = Emotion::new(0.0, 0.25, 0.5);
+        assert_eq!(emotion.bipolar_arousal(), -0.5);
+        assert_eq!(Emotion::arousal_from_bipolar(-0.5), 0.25);
+    }
+
+    #[test]
+    fn test_json_defaults_and_aliases() {
+        let emotion: Emotion = serde_json::from_str(
+            r#"{"valence":0.2,"arousal":0.7,"dominance":0.4,"primary_emotion":"Joy"}"#,
+        )
+        .unwrap();
+        assert_eq!(emotion.confidence, 1.0);
+        assert_eq!(emotion.timestamp, 0);
+        assert_eq!(emotion.label.as_deref(), Some("Joy"));
+    }
+}
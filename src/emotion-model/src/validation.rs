@@ -0,0 +1,246 @@
+//! Validation and sanitization of emotional data before it is persisted
+//!
+//! Non-finite values and malformed labels are always rejected; finite values
+//! outside their range are either rejected or clamped depending on the
+//! [`ValidationPolicy`] chosen by the entry point.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Emotion, EmotionError, SoulboundEmotionData, AROUSAL_RANGE, BIPOLAR_AROUSAL_RANGE,
+    CONFIDENCE_RANGE, DOMINANCE_RANGE, VALENCE_RANGE,
+};
+
+/// Longest emotion label accepted on-chain, in bytes
+pub const MAX_LABEL_LEN: usize = 64;
+/// Most secondary emotions accepted alongside a primary one
+pub const MAX_SECONDARY_EMOTIONS: usize = 16;
+
+/// How out-of-range finite values are handled
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+pub enum ValidationPolicy {
+    /// Fail on any value outside its range
+    Reject,
+    /// Clamp finite values into range
+    Clamp,
+}
+
+/// Types that can be checked and normalized before being stored
+pub trait Sanitize: Sized {
+    fn sanitize(self, policy: ValidationPolicy) -> Result<Self, EmotionError>;
+}
+
+/// Check one value against its range under the given policy
+pub fn sanitize_f32(
+    field: &'static str,
+    value: f32,
+    (min, max): (f32, f32),
+    policy: ValidationPolicy,
+) -> Result<f32, EmotionError> {
+    if !value.is_finite() {
+        return Err(EmotionError::NonFinite { field });
+    }
+    if value >= min && value <= max {
+        return Ok(value);
+    }
+    match policy {
+        ValidationPolicy::Clamp => Ok(value.clamp(min, max)),
+        ValidationPolicy::Reject => Err(EmotionError::OutOfRange {
+            field,
+            value,
+            min,
+            max,
+        }),
+    }
+}
+
+/// `sanitize_f32` for the f64 fields used by the soulbound contracts
+pub fn sanitize_f64(
+    field: &'static str,
+    value: f64,
+    (min, max): (f32, f32),
+    policy: ValidationPolicy,
+) -> Result<f64, EmotionError> {
+    if !value.is_finite() {
+        return Err(EmotionError::NonFinite { field });
+    }
+    let (min64, max64) = (min as f64, max as f64);
+    if value >= min64 && value <= max64 {
+        return Ok(value);
+    }
+    match policy {
+        ValidationPolicy::Clamp => Ok(value.clamp(min64, max64)),
+        ValidationPolicy::Reject => Err(EmotionError::OutOfRange {
+            field,
+            value: value as f32,
+            min,
+            max,
+        }),
+    }
+}
+
+/// Reject labels that are too long or contain control characters
+pub fn validate_label(field: &'static str, label: &str) -> Result<(), EmotionError> {
+    if label.len() > MAX_LABEL_LEN {
+        return Err(EmotionError::InvalidLabel {
+            field,
+            reason: "too long",
+        });
+    }
+    if label.chars().any(char::is_control) {
+        return Err(EmotionError::InvalidLabel {
+            field,
+            reason: "contains control characters",
+        });
+    }
+    Ok(())
+}
+
+impl Sanitize for Emotion {
+    fn sanitize(self, policy: ValidationPolicy) -> Result<Self, EmotionError> {
+        if let Some(label) = &self.label {
+            validate_label("label", label)?;
+        }
+        Ok(Self {
+            valence: sanitize_f32("valence", self.valence, VALENCE_RANGE, policy)?,
+            arousal: sanitize_f32("arousal", self.arousal, AROUSAL_RANGE, policy)?,
+            dominance: sanitize_f32("dominance", self.dominance, DOMINANCE_RANGE, policy)?,
+            confidence: sanitize_f32("confidence", self.confidence, CONFIDENCE_RANGE, policy)?,
+            ..self
+        })
+    }
+}
+
+impl Sanitize for SoulboundEmotionData {
+    fn sanitize(self, policy: ValidationPolicy) -> Result<Self, EmotionError> {
+        validate_label("primary_emotion", &self.primary_emotion)?;
+        if self.secondary_emotions.len() > MAX_SECONDARY_EMOTIONS {
+            return Err(EmotionError::InvalidLabel {
+                field: "secondary_emotions",
+                reason: "too many entries",
+            });
+        }
+        let secondary_emotions = self
+            .secondary_emotions
+            .into_iter()
+            .map(|(label, score)| {
+                validate_label("secondary_emotions", &label)?;
+                let score = sanitize_f64("secondary_emotions", score, CONFIDENCE_RANGE, policy)?;
+                Ok((label, score))
+            })
+            .collect::<Result<Vec<_>, EmotionError>>()?;
+
+        Ok(Self {
+            primary_emotion: self.primary_emotion,
+            confidence: sanitize_f64("confidence", self.confidence, CONFIDENCE_RANGE, policy)?,
+            secondary_emotions,
+            arousal: sanitize_f64("arousal", self.arousal, BIPOLAR_AROUSAL_RANGE, policy)?,
+            valence: sanitize_f64("valence", self.valence, VALENCE_RANGE, policy)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn in_range(value: f32, (min, max): (f32, f32)) -> bool {
+        value.is_finite() && value >= min && value <= max
+    }
+
+    #[test]
+    fn test_policies() {
+        let emotion = Emotion::new(1.2, 0.5, 0.5);
+        assert_eq!(
+            emotion
+                .clone()
+                .sanitize(ValidationPolicy::Reject)
+                .unwrap_err()
+                .to_string(),
+            "valence 1.2 is outside -1..=1"
+        );
+        assert_eq!(
+            emotion.sanitize(ValidationPolicy::Clamp).unwrap().valence,
+            1.0
+        );
+
+        let nan = Emotion::new(0.0, 0.5, 0.5).with_confidence(f32::NAN);
+        assert_eq!(
+            nan.sanitize(ValidationPolicy::Clamp),
+            Err(EmotionError::NonFinite {
+                field: "confidence"
+            })
+        );
+    }
+
+    #[test]
+    fn test_soulbound_data() {
+        let data = SoulboundEmotionData {
+            primary_emotion: "Focused".to_string(),
+            confidence: 0.9,
+            secondary_emotions: vec![("Calm".to_string(), 1.4)],
+            arousal: -0.5,
+            valence: 0.2,
+        };
+        assert!(data.clone().sanitize(ValidationPolicy::Reject).is_err());
+        let clean = data.sanitize(ValidationPolicy::Clamp).unwrap();
+        assert_eq!(clean.secondary_emotions[0].1, 1.0);
+        assert_eq!(clean.arousal, -0.5);
+
+        let crowded = SoulboundEmotionData {
+            secondary_emotions: vec![("Calm".to_string(), 0.1); MAX_SECONDARY_EMOTIONS + 1],
+            ..clean
+        };
+        assert!(crowded.sanitize(ValidationPolicy::Clamp).is_err());
+    }
+
+    #[test]
+    fn test_labels() {
+        assert!(validate_label("label", "Joy").is_ok());
+        assert!(validate_label("label", &"a".repeat(MAX_LABEL_LEN + 1)).is_err());
+        assert!(validate_label("label", "Joy\u{0}").is_err());
+    }
+
+    quickcheck! {
+        fn prop_clamp_is_valid_unless_non_finite(v: f32, a: f32, d: f32, c: f32) -> bool {
+            let emotion = Emotion::new(v, a, d).with_confidence(c);
+            let all_finite = [v, a, d, c].iter().all(|x| x.is_finite());
+            match emotion.sanitize(ValidationPolicy::Clamp) {
+                Ok(clean) => all_finite && clean.validate().is_ok(),
+                Err(EmotionError::NonFinite { .. }) => !all_finite,
+                Err(_) => false,
+            }
+        }
+
+        fn prop_reject_accepts_exactly_valid(v: f32, a: f32, d: f32, c: f32) -> bool {
+            let emotion = Emotion::new(v, a, d).with_confidence(c);
+            let valid = in_range(v, VALENCE_RANGE)
+                && in_range(a, AROUSAL_RANGE)
+                && in_range(d, DOMINANCE_RANGE)
+                && in_range(c, CONFIDENCE_RANGE);
+            match emotion.clone().sanitize(ValidationPolicy::Reject) {
+                Ok(clean) => valid && clean == emotion,
+                Err(_) => !valid,
+            }
+        }
+
+        fn prop_sanitize_is_idempotent(v: f32, a: f32, d: f32) -> bool {
+            match Emotion::new(v, a, d).sanitize(ValidationPolicy::Clamp) {
+                Ok(once) => once.clone().sanitize(ValidationPolicy::Reject) == Ok(once),
+                Err(_) => true,
+            }
+        }
+
+        fn prop_f64_clamp_is_in_range(value: f64) -> bool {
+            let wide = sanitize_f64("valence", value, VALENCE_RANGE, ValidationPolicy::Clamp);
+            match wide {
+                Ok(clean) => value.is_finite() && (-1.0..=1.0).contains(&clean),
+                Err(_) => !value.is_finite(),
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Adaptive learning - Online preference learning for interactive responses
+//!
+//! Each response action is an arm of a UCB1 bandit. An interaction's
+//! outcome is scored from dwell time, intensity and emotional impact, and
+//! the chosen arm's preference weight moves towards that reward by the
+//! learning rate. Selection favours high weights while still trying arms
+//! that have been pulled rarely.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+
+/// Dwell time at which the dwell component of the reward reaches one half
+const HALF_REWARD_DWELL_MS: f32 = 10_000.0;
+/// Share of the reward from dwell time, intensity and emotional impact
+const DWELL_SHARE: f32 = 0.4;
+const INTENSITY_SHARE: f32 = 0.3;
+const IMPACT_SHARE: f32 = 0.3;
+
+/// How the NFT can respond to an interaction
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum ResponseAction {
+    EnhanceResponse,
+    CalmResponse,
+    EnergizeResponse,
+    VaryPalette,
+    Maintain,
+}
+
+impl ResponseAction {
+    /// Every action, in preference-weight order
+    pub const ALL: [ResponseAction; 5] = [
+        ResponseAction::EnhanceResponse,
+        ResponseAction::CalmResponse,
+        ResponseAction::EnergizeResponse,
+        ResponseAction::VaryPalette,
+        ResponseAction::Maintain,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResponseAction::EnhanceResponse => "enhance_response",
+            ResponseAction::CalmResponse => "calm_response",
+            ResponseAction::EnergizeResponse => "energize_response",
+            ResponseAction::VaryPalette => "vary_palette",
+            ResponseAction::Maintain => "maintain",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.as_str() == name)
+    }
+}
+
+/// What a viewer did after a response
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InteractionOutcome {
+    pub dwell_ms: u64,
+    pub intensity: f32,
+    /// Valence shift scaled by its confidence, -1 to 1
+    pub emotional_impact: f32,
+}
+
+impl InteractionOutcome {
+    /// Read `dwell_ms` from an event's JSON data, defaulting to zero
+    pub fn dwell_from_data(data: &str) -> u64 {
+        serde_json::from_str::<serde_json::Value>(data)
+            .ok()
+            .and_then(|v| v.get("dwell_ms").and_then(|d| d.as_u64()))
+            .unwrap_or(0)
+    }
+
+    /// Reward between 0 and 1
+    pub fn reward(&self) -> f32 {
+        let dwell = self.dwell_ms as f32;
+        let dwell = dwell / (dwell + HALF_REWARD_DWELL_MS);
+        let intensity = self.intensity.clamp(0.0, 1.0);
+        let impact = (self.emotional_impact.clamp(-1.0, 1.0) + 1.0) / 2.0;
+        DWELL_SHARE * dwell + INTENSITY_SHARE * intensity + IMPACT_SHARE * impact
+    }
+}
+
+/// Pick the arm with the highest upper confidence bound; untried arms first
+pub fn select(weights: &[f32], pulls: &[u32], exploration: f32) -> usize {
+    if let Some(untried) = pulls.iter().position(|&n| n == 0) {
+        return untried;
+    }
+    let total: u32 = pulls.iter().sum();
+    let log_total = (total.max(1) as f32).ln();
+    let bound = |i: usize| weights[i] + exploration * (log_total / pulls[i] as f32).sqrt();
+    (0..weights.len().min(pulls.len()))
+        .max_by(|&a, &b| bound(a).total_cmp(&bound(b)))
+        .unwrap_or(0)
+}
+
+/// Move an arm's weight towards the observed reward
+pub fn update(weight: &mut f32, reward: f32, learning_rate: f32) {
+    *weight += learning_rate.clamp(0.0, 1.0) * (reward - *weight);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reward_components() {
+        let idle = InteractionOutcome::default();
+        assert!((idle.reward() - 0.15).abs() < 1e-6);
+
+        let engaged = InteractionOutcome {
+            dwell_ms: 10_000,
+            intensity: 1.0,
+            emotional_impact: 1.0,
+        };
+        assert!((engaged.reward() - 0.8).abs() < 1e-6);
+
+        assert_eq!(
+            InteractionOutcome::dwell_from_data(r#"{"dwell_ms": 2500}"#),
+            2500
+        );
+        assert_eq!(InteractionOutcome::dwell_from_data("not json"), 0);
+    }
+
+    #[test]
+    fn test_bandit_converges_on_best_arm() {
+        let rewards = [0.2, 0.3, 0.9, 0.4, 0.1];
+        let mut weights = vec![0.5; 5];
+        let mut pulls = vec![0u32; 5];
+        for _ in 0..200 {
+            let arm = select(&weights, &pulls, 0.3);
+            update(&mut weights[arm], rewards[arm], 0.2);
+            pulls[arm] += 1;
+        }
+        assert!(pulls.iter().all(|&n| n > 0));
+        let best = ResponseAction::ALL[select(&weights, &pulls, 0.0)];
+        assert_eq!(best, ResponseAction::EnergizeResponse);
+        assert!(pulls[2] > 100);
+    }
+
+    #[test]
+    fn test_action_names_round_trip() {
+        for action in ResponseAction::ALL {
+            assert_eq!(ResponseAction::from_name(action.as_str()), Some(action));
+            assert_eq!(ResponseAction::ALL[action.index()], action);
+        }
+        assert_eq!(ResponseAction::from_name("unknown"), None);
+    }
+}
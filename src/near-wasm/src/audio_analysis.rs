@@ -0,0 +1,616 @@
+//! Audio Analysis - Feature extraction for audio-reactive shaders
+//!
+//! Turns PCM audio into the `audio_*` uniforms expected by
+//! `WGSLShader::audio_reactive_template`: FFT band energies, RMS, spectral
+//! centroid, onset/beat detection and a running BPM estimate.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+use crate::wgsl_studio::{ShaderParams, UniformParam, UniformType};
+
+/// Analyzer configuration
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AudioAnalyzerConfig {
+    pub sample_rate: u32,
+    /// FFT size in samples, must be a power of two
+    pub frame_size: usize,
+    /// Samples between consecutive frames
+    pub hop_size: usize,
+    pub bass_range_hz: (f32, f32),
+    pub mid_range_hz: (f32, f32),
+    pub high_range_hz: (f32, f32),
+    /// Standard deviations above the mean spectral flux needed for an onset
+    pub onset_sensitivity: f32,
+    /// Minimum time between two onsets
+    pub min_onset_interval_ms: f32,
+    pub min_bpm: f32,
+    pub max_bpm: f32,
+    /// Per-frame decay of the automatic gain control peak
+    pub gain_decay: f32,
+}
+
+/// Features extracted from one analysis frame
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AudioFeatures {
+    pub time_ms: f64,
+    pub rms: f32,
+    /// Spectral centroid in Hz
+    pub spectral_centroid: f32,
+    /// Band levels normalized to 0.0 - 1.0
+    pub bass: f32,
+    pub mid: f32,
+    pub high: f32,
+    pub spectral_flux: f32,
+    pub onset: bool,
+    pub bpm: Option<f32>,
+}
+
+/// Decoded PCM audio
+#[derive(Clone, Debug)]
+pub struct WavData {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Mono samples in -1.0 to 1.0
+    pub samples: Vec<f32>,
+}
+
+/// Streaming audio feature extractor
+#[derive(Clone)]
+pub struct AudioAnalyzer {
+    config: AudioAnalyzerConfig,
+    window: Vec<f32>,
+    buffer: Vec<f32>,
+    previous_magnitudes: Vec<f32>,
+    flux_history: VecDeque<f32>,
+    envelope: VecDeque<f32>,
+    gain_peak: f32,
+    frames_processed: u64,
+    last_onset_frame: Option<u64>,
+    bpm: Option<f32>,
+}
+
+/// Frames of onset envelope kept for tempo estimation
+const ENVELOPE_FRAMES: usize = 512;
+/// Frames of spectral flux used for the adaptive onset threshold
+const FLUX_HISTORY_FRAMES: usize = 43;
+
+impl Default for AudioAnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44_100,
+            frame_size: 1024,
+            hop_size: 512,
+            bass_range_hz: (20.0, 250.0),
+            mid_range_hz: (250.0, 4_000.0),
+            high_range_hz: (4_000.0, 16_000.0),
+            onset_sensitivity: 1.5,
+            min_onset_interval_ms: 100.0,
+            min_bpm: 60.0,
+            max_bpm: 200.0,
+            gain_decay: 0.995,
+        }
+    }
+}
+
+impl AudioAnalyzer {
+    /// Create an analyzer for the given configuration
+    pub fn new(config: AudioAnalyzerConfig) -> Self {
+        assert!(
+            config.frame_size.is_power_of_two(),
+            "Frame size must be a power of two"
+        );
+        assert!(
+            config.hop_size > 0 && config.hop_size <= config.frame_size,
+            "Hop size must be between 1 and the frame size"
+        );
+
+        let window = (0..config.frame_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / config.frame_size as f32).cos())
+            .collect();
+        let bins = config.frame_size / 2 + 1;
+
+        Self {
+            window,
+            buffer: Vec::with_capacity(config.frame_size),
+            previous_magnitudes: vec![0.0; bins],
+            flux_history: VecDeque::with_capacity(FLUX_HISTORY_FRAMES),
+            envelope: VecDeque::with_capacity(ENVELOPE_FRAMES),
+            gain_peak: 0.0,
+            frames_processed: 0,
+            last_onset_frame: None,
+            bpm: None,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &AudioAnalyzerConfig {
+        &self.config
+    }
+
+    /// Current tempo estimate
+    pub fn bpm(&self) -> Option<f32> {
+        self.bpm
+    }
+
+    /// Feed PCM samples, returning features for every completed frame
+    pub fn push_samples(&mut self, samples: &[f32]) -> Vec<AudioFeatures> {
+        let mut features = Vec::new();
+        for sample in samples {
+            self.buffer.push(*sample);
+            if self.buffer.len() == self.config.frame_size {
+                let frame = self.buffer.clone();
+                features.push(self.process_frame(&frame));
+                self.buffer.drain(..self.config.hop_size);
+            }
+        }
+        features
+    }
+
+    /// Analyze a single frame of `frame_size` samples
+    pub fn process_frame(&mut self, frame: &[f32]) -> AudioFeatures {
+        assert_eq!(frame.len(), self.config.frame_size, "Frame size mismatch");
+
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+
+        let mut re: Vec<f32> = frame.iter().zip(&self.window).map(|(s, w)| s * w).collect();
+        let mut im = vec![0.0; frame.len()];
+        fft_in_place(&mut re, &mut im);
+
+        let bins = self.config.frame_size / 2 + 1;
+        let scale = 2.0 / self.config.frame_size as f32;
+        let magnitudes: Vec<f32> = (0..bins)
+            .map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt() * scale)
+            .collect();
+
+        let bin_hz = self.config.sample_rate as f32 / self.config.frame_size as f32;
+        let magnitude_sum: f32 = magnitudes.iter().sum();
+        let spectral_centroid = if magnitude_sum > f32::EPSILON {
+            magnitudes
+                .iter()
+                .enumerate()
+                .map(|(k, m)| k as f32 * bin_hz * m)
+                .sum::<f32>()
+                / magnitude_sum
+        } else {
+            0.0
+        };
+
+        let bass = band_level(&magnitudes, bin_hz, self.config.bass_range_hz);
+        let mid = band_level(&magnitudes, bin_hz, self.config.mid_range_hz);
+        let high = band_level(&magnitudes, bin_hz, self.config.high_range_hz);
+
+        // Shared automatic gain control keeps bands comparable to each other
+        self.gain_peak = (self.gain_peak * self.config.gain_decay)
+            .max(bass)
+            .max(mid)
+            .max(high);
+        let gain = if self.gain_peak > 1e-6 {
+            1.0 / self.gain_peak
+        } else {
+            0.0
+        };
+
+        let spectral_flux: f32 = magnitudes
+            .iter()
+            .zip(&self.previous_magnitudes)
+            .map(|(m, p)| (m - p).max(0.0))
+            .sum();
+        self.previous_magnitudes = magnitudes;
+
+        let onset = self.detect_onset(spectral_flux);
+
+        if self.envelope.len() == ENVELOPE_FRAMES {
+            self.envelope.pop_front();
+        }
+        self.envelope.push_back(spectral_flux);
+        if self.frames_processed.is_multiple_of(16) {
+            self.bpm = self.estimate_bpm();
+        }
+
+        let time_ms = self.frames_processed as f64 * self.config.hop_size as f64 * 1000.0
+            / self.config.sample_rate as f64;
+        self.frames_processed += 1;
+
+        AudioFeatures {
+            time_ms,
+            rms,
+            spectral_centroid,
+            bass: (bass * gain).clamp(0.0, 1.0),
+            mid: (mid * gain).clamp(0.0, 1.0),
+            high: (high * gain).clamp(0.0, 1.0),
+            spectral_flux,
+            onset,
+            bpm: self.bpm,
+        }
+    }
+
+    /// Adaptive-threshold onset detection on spectral flux
+    fn detect_onset(&mut self, flux: f32) -> bool {
+        let history_len = self.flux_history.len() as f32;
+        let onset = if history_len >= 4.0 {
+            let mean = self.flux_history.iter().sum::<f32>() / history_len;
+            let variance = self
+                .flux_history
+                .iter()
+                .map(|f| (f - mean).powi(2))
+                .sum::<f32>()
+                / history_len;
+            let threshold = mean + self.config.onset_sensitivity * variance.sqrt();
+
+            let frame_ms = self.config.hop_size as f32 * 1000.0 / self.config.sample_rate as f32;
+            let spaced = self.last_onset_frame.is_none_or(|last| {
+                (self.frames_processed - last) as f32 * frame_ms
+                    >= self.config.min_onset_interval_ms
+            });
+            flux > threshold && flux > 1e-4 && spaced
+        } else {
+            false
+        };
+
+        if onset {
+            self.last_onset_frame = Some(self.frames_processed);
+        }
+        if self.flux_history.len() == FLUX_HISTORY_FRAMES {
+            self.flux_history.pop_front();
+        }
+        self.flux_history.push_back(flux);
+        onset
+    }
+
+    /// Estimate tempo from the autocorrelation of the onset envelope
+    fn estimate_bpm(&self) -> Option<f32> {
+        let frames_per_second = self.config.sample_rate as f32 / self.config.hop_size as f32;
+        let min_lag = (60.0 * frames_per_second / self.config.max_bpm)
+            .floor()
+            .max(1.0) as usize;
+        let max_lag = (60.0 * frames_per_second / self.config.min_bpm).ceil() as usize;
+        if self.envelope.len() < max_lag * 2 {
+            return None;
+        }
+
+        let mean = self.envelope.iter().sum::<f32>() / self.envelope.len() as f32;
+        let centered: Vec<f32> = self.envelope.iter().map(|e| e - mean).collect();
+        let energy: f32 = centered.iter().map(|e| e * e).sum();
+        if energy <= f32::EPSILON {
+            return None;
+        }
+
+        let autocorrelation = |lag: usize| -> f32 {
+            centered
+                .iter()
+                .zip(&centered[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+        };
+
+        let scores: Vec<f32> = (min_lag - 1..=max_lag + 1).map(autocorrelation).collect();
+        let (best, best_score) = (1..scores.len() - 1)
+            .map(|i| (i, scores[i]))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))?;
+        if best_score / energy < 0.1 {
+            return None;
+        }
+
+        // Parabolic interpolation around the peak for sub-frame lag accuracy
+        let (left, right) = (scores[best - 1], scores[best + 1]);
+        let denominator = left - 2.0 * best_score + right;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        let lag = (best + min_lag - 1) as f32 + offset;
+        Some(60.0 * frames_per_second / lag)
+    }
+
+    /// Reset all running state
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config.clone());
+    }
+}
+
+impl AudioFeatures {
+    /// Uniform updates for audio-reactive shaders
+    pub fn to_uniforms(&self) -> Vec<UniformParam> {
+        let float = |name: &str, value: f32| UniformParam {
+            name: name.to_string(),
+            value_type: UniformType::Float,
+            value: vec![value],
+        };
+        vec![
+            float("audio_bass", self.bass),
+            float("audio_mid", self.mid),
+            float("audio_high", self.high),
+            float("audio_rms", self.rms),
+            float("audio_centroid", self.spectral_centroid),
+            float("audio_beat", if self.onset { 1.0 } else { 0.0 }),
+            float("audio_bpm", self.bpm.unwrap_or(0.0)),
+        ]
+    }
+
+    /// Write the audio uniforms into shader parameters, replacing previous values
+    pub fn apply_to_params(&self, params: &mut ShaderParams) {
+        for uniform in self.to_uniforms() {
+            match params
+                .custom_uniforms
+                .iter_mut()
+                .find(|u| u.name == uniform.name)
+            {
+                Some(existing) => existing.value = uniform.value,
+                None => params.custom_uniforms.push(uniform),
+            }
+        }
+    }
+}
+
+/// Parse a RIFF/WAVE file with 16-bit integer or 32-bit float PCM
+pub fn parse_wav(bytes: &[u8]) -> Result<WavData, String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a RIFF/WAVE file".to_string());
+    }
+
+    let read_u16 = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let read_u32 =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let chunk_id = &bytes[offset..offset + 4];
+        let chunk_len = read_u32(offset + 4) as usize;
+        let body = offset + 8;
+        // A hostile length can overflow usize on 32-bit targets
+        let declared_end = body
+            .checked_add(chunk_len)
+            .ok_or("WAV chunk length overflows")?;
+        let end = declared_end.min(bytes.len());
+
+        if chunk_id == b"fmt " {
+            if chunk_len < 16 || body + 16 > bytes.len() {
+                return Err("Truncated fmt chunk".to_string());
+            }
+            format = Some((
+                read_u16(body),
+                read_u16(body + 2),
+                read_u32(body + 4),
+                read_u16(body + 14),
+            ));
+        } else if chunk_id == b"data" {
+            let (audio_format, channels, sample_rate, bits) =
+                format.ok_or("data chunk before fmt chunk")?;
+            if channels == 0 {
+                return Err("WAV file has no channels".to_string());
+            }
+            let data = &bytes[body..end];
+            let interleaved: Vec<f32> = match (audio_format, bits) {
+                (1, 16) => data
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32_768.0)
+                    .collect(),
+                (3, 32) => data
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+                _ => {
+                    return Err(format!(
+                        "Unsupported WAV encoding (format {}, {} bits)",
+                        audio_format, bits
+                    ))
+                }
+            };
+            let samples = interleaved
+                .chunks_exact(channels as usize)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect();
+            return Ok(WavData {
+                sample_rate,
+                channels,
+                samples,
+            });
+        }
+        offset = match declared_end.checked_add(chunk_len % 2) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Err("WAV file has no data chunk".to_string())
+}
+
+/// Analyze a whole WAV file with the default configuration at its sample rate
+pub fn analyze_wav(bytes: &[u8]) -> Result<Vec<AudioFeatures>, String> {
+    let wav = parse_wav(bytes)?;
+    let mut analyzer = AudioAnalyzer::new(AudioAnalyzerConfig {
+        sample_rate: wav.sample_rate,
+        ..Default::default()
+    });
+    Ok(analyzer.push_samples(&wav.samples))
+}
+
+/// RMS level of the spectrum bins inside a frequency band
+fn band_level(magnitudes: &[f32], bin_hz: f32, (low, high): (f32, f32)) -> f32 {
+    let first = ((low / bin_hz).ceil() as usize).max(1);
+    let last = ((high / bin_hz).floor() as usize).min(magnitudes.len() - 1);
+    if first > last {
+        return 0.0;
+    }
+    let power: f32 = magnitudes[first..=last].iter().map(|m| m * m).sum();
+    (power / (last - first + 1) as f32).sqrt()
+}
+
+/// Iterative radix-2 Cooley-Tukey FFT
+fn fft_in_place(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode mono or stereo 16-bit PCM as a WAV fixture
+    fn wav_fixture(sample_rate: u32, channels: u16, samples: &[f32]) -> Vec<u8> {
+        let data_len = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * 32_767.0) as i16;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn sine(frequency: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| 0.8 * (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    /// Decaying noise bursts at a fixed tempo
+    fn click_track(bpm: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        let period = (60.0 / bpm * sample_rate as f32) as usize;
+        let mut seed: u32 = 12345;
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let noise = (seed >> 16) as f32 / 32_768.0 - 1.0;
+                let phase = i % period;
+                if phase < 2_000 {
+                    noise * (-(phase as f32) / 300.0).exp()
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wav_parsing_downmixes_stereo() {
+        let samples = vec![0.5, -0.5, 0.25, 0.25];
+        let wav = parse_wav(&wav_fixture(22_050, 2, &samples)).unwrap();
+        assert_eq!(wav.sample_rate, 22_050);
+        assert_eq!(wav.channels, 2);
+        assert_eq!(wav.samples.len(), 2);
+        assert!(wav.samples[0].abs() < 1e-3);
+        assert!((wav.samples[1] - 0.25).abs() < 1e-3);
+
+        assert!(parse_wav(b"not a wav file").is_err());
+    }
+
+    #[test]
+    fn test_truncated_and_oversized_chunks_are_errors() {
+        let wav = wav_fixture(22_050, 1, &[0.5; 4]);
+        assert!(parse_wav(&wav[..30]).is_err());
+
+        let mut oversized = wav[..12].to_vec();
+        oversized.extend_from_slice(b"LIST");
+        oversized.extend_from_slice(&u32::MAX.to_le_bytes());
+        oversized.extend_from_slice(&wav[12..]);
+        assert!(parse_wav(&oversized).is_err());
+    }
+
+    #[test]
+    fn test_band_energies_follow_frequency() {
+        let bass = analyze_wav(&wav_fixture(44_100, 1, &sine(100.0, 1.0, 44_100))).unwrap();
+        let last = bass.last().unwrap();
+        assert!(last.bass > 0.9);
+        assert!(last.mid < 0.1);
+        assert!(last.high < 0.1);
+        assert!((last.rms - 0.8 / 2f32.sqrt()).abs() < 0.02);
+
+        let high = analyze_wav(&wav_fixture(44_100, 1, &sine(8_000.0, 1.0, 44_100))).unwrap();
+        let last = high.last().unwrap();
+        assert!(last.high > 0.9);
+        assert!(last.bass < 0.1);
+        assert!((last.spectral_centroid - 8_000.0).abs() < 500.0);
+    }
+
+    #[test]
+    fn test_onsets_and_bpm_from_click_track() {
+        let features =
+            analyze_wav(&wav_fixture(44_100, 1, &click_track(120.0, 10.0, 44_100))).unwrap();
+
+        let onsets = features.iter().filter(|f| f.onset).count();
+        assert!((18..=22).contains(&onsets), "detected {} onsets", onsets);
+
+        let bpm = features.last().unwrap().bpm.expect("tempo estimate");
+        assert!((bpm - 120.0).abs() < 3.0, "estimated {} bpm", bpm);
+    }
+
+    #[test]
+    fn test_silence_produces_no_onsets() {
+        let features = analyze_wav(&wav_fixture(44_100, 1, &vec![0.0; 44_100])).unwrap();
+        assert!(features
+            .iter()
+            .all(|f| !f.onset && f.bass == 0.0 && f.rms == 0.0));
+        assert!(features.last().unwrap().bpm.is_none());
+    }
+
+    #[test]
+    fn test_apply_to_params_updates_in_place() {
+        let mut analyzer = AudioAnalyzer::new(AudioAnalyzerConfig::default());
+        let features = analyzer.push_samples(&sine(100.0, 0.1, 44_100));
+        let mut params = ShaderParams::default();
+
+        features[0].apply_to_params(&mut params);
+        features.last().unwrap().apply_to_params(&mut params);
+
+        assert_eq!(params.custom_uniforms.len(), 7);
+        let bass = params
+            .custom_uniforms
+            .iter()
+            .find(|u| u.name == "audio_bass")
+            .unwrap();
+        assert_eq!(bass.value, vec![features.last().unwrap().bass]);
+    }
+}
@@ -0,0 +1,1014 @@
+//! Biometric NFT Contract - Many biometric NFTs behind NEP-171 ownership
+//!
+//! Ownership, transfers, approvals and enumeration come from the standard
+//! `NonFungibleToken`; each token's biometric state is a `BiometricNFT`
+//! whose collections are prefixed by its token ID.
+
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::core::{
+    NonFungibleTokenCore, NonFungibleTokenResolver,
+};
+use near_contract_standards::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
+use near_contract_standards::non_fungible_token::metadata::TokenMetadata;
+use near_contract_standards::non_fungible_token::{NonFungibleToken, Token, TokenId};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, AccountId, PanicOnDefault, Promise, PromiseOrValue};
+use std::collections::HashMap;
+
+use crate::biometric_fusion::FusionConfig;
+use crate::emotion_smoothing::SmoothingConfig;
+use crate::emotional_aggregation::CollectiveMood;
+use crate::interactive_advanced::{
+    BiometricNFT, BiometricSnapshot, DetailedEmotionalState, EmotionalInteraction,
+    EmotionalResonance, InteractionType, InteractiveMetadata, PrivacySettings, ProfileStatus,
+    VisualState,
+};
+
+/// Interactions returned per page when no limit is given
+const DEFAULT_HISTORY_PAGE: u64 = 50;
+
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct BiometricNFTContract {
+    tokens: NonFungibleToken,
+    owner_id: AccountId,
+    biometrics_by_id: LookupMap<TokenId, BiometricNFT>,
+}
+
+#[near]
+impl BiometricNFTContract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        Self {
+            tokens: NonFungibleToken::new(
+                b"t".to_vec(),
+                owner_id.clone(),
+                Some(b"m".to_vec()),
+                Some(b"e".to_vec()),
+                Some(b"p".to_vec()),
+            ),
+            owner_id,
+            biometrics_by_id: LookupMap::new(b"b".to_vec()),
+        }
+    }
+
+    /// Mint a biometric NFT; the attached deposit must cover its storage
+    #[payable]
+    pub fn nft_mint(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: TokenMetadata,
+        metadata: InteractiveMetadata,
+    ) -> Token {
+        let initial_storage = env::storage_usage();
+
+        let token = self.tokens.internal_mint_with_refund(
+            token_id.clone(),
+            receiver_id.clone(),
+            Some(token_metadata),
+            None,
+        );
+        let nft = BiometricNFT::new(token_id.clone(), receiver_id, metadata);
+        self.biometrics_by_id.insert(&token_id, &nft);
+
+        charge_storage(initial_storage);
+        token
+    }
+
+    /// Register the caller's biometric profile for a token; the attached
+    /// deposit must cover its storage
+    #[payable]
+    pub fn register_profile(&mut self, token_id: TokenId, consent: bool) {
+        let initial_storage = env::storage_usage();
+        let mut nft = self.biometric(&token_id);
+        nft.register_profile(consent);
+        self.biometrics_by_id.insert(&token_id, &nft);
+        charge_storage(initial_storage);
+    }
+
+    /// Submit a resting reading for the caller's calibration, returning how
+    /// many more are needed; the attached deposit must cover its storage.
+    /// Completing a recalibration needs the token owner to authorize the
+    /// profile again.
+    #[payable]
+    pub fn submit_calibration(
+        &mut self,
+        token_id: TokenId,
+        reading: DetailedEmotionalState,
+    ) -> u32 {
+        let initial_storage = env::storage_usage();
+        let mut nft = self.biometric(&token_id);
+        let remaining = nft.submit_calibration(reading);
+        self.biometrics_by_id.insert(&token_id, &nft);
+        charge_storage(initial_storage);
+        remaining
+    }
+
+    /// Allow a registered profile to interact (token owner only)
+    pub fn authorize_profile(&mut self, token_id: TokenId, account_id: AccountId) {
+        let mut nft = self.biometric(&token_id);
+        nft.authorize_profile(account_id);
+        self.biometrics_by_id.insert(&token_id, &nft);
+    }
+
+    /// Remove a profile and its baseline (token owner only)
+    pub fn revoke_profile(&mut self, token_id: TokenId, account_id: AccountId) {
+        let mut nft = self.biometric(&token_id);
+        nft.revoke_profile(account_id);
+        self.biometrics_by_id.insert(&token_id, &nft);
+    }
+
+    pub fn get_profile_status(
+        &self,
+        token_id: TokenId,
+        account_id: AccountId,
+    ) -> Option<ProfileStatus> {
+        self.biometric(&token_id).profile_status(&account_id)
+    }
+
+    /// Interact with a token using real-time biometric data; the attached
+    /// deposit must cover the stored interaction
+    #[payable]
+    pub fn interact(
+        &mut self,
+        token_id: TokenId,
+        emotional_state: DetailedEmotionalState,
+        biometric_data: BiometricSnapshot,
+        interaction_type: InteractionType,
+    ) -> VisualState {
+        let initial_storage = env::storage_usage();
+        let mut nft = self.biometric(&token_id);
+        nft.interact_with_biometrics(emotional_state, biometric_data, interaction_type);
+        self.biometrics_by_id.insert(&token_id, &nft);
+        charge_storage(initial_storage);
+        nft.visual_state
+    }
+
+    /// Configure a token's smoothing (token owner only)
+    pub fn set_smoothing_config(&mut self, token_id: TokenId, config: SmoothingConfig) {
+        let mut nft = self.biometric(&token_id);
+        nft.set_smoothing_config(config);
+        self.biometrics_by_id.insert(&token_id, &nft);
+    }
+
+    pub fn get_visual_state(&self, token_id: TokenId) -> VisualState {
+        self.biometric(&token_id).visual_state
+    }
+
+    pub fn get_emotional_resonance(&self, token_id: TokenId) -> EmotionalResonance {
+        self.biometric(&token_id).emotional_resonance
+    }
+
+    pub fn get_interactive_metadata(&self, token_id: TokenId) -> InteractiveMetadata {
+        self.biometric(&token_id).metadata
+    }
+
+    pub fn get_collective_mood(&self, token_id: TokenId) -> CollectiveMood {
+        self.biometric(&token_id).get_collective_mood()
+    }
+
+    /// Interactions with a token, oldest first; private histories need a
+    /// signed call from the token owner or an authorized profile
+    pub fn get_interaction_history(
+        &self,
+        token_id: TokenId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<EmotionalInteraction> {
+        self.biometric(&token_id).interaction_history_page(
+            from_index.map_or(0, |i| i.0 as u64),
+            limit.unwrap_or(DEFAULT_HISTORY_PAGE),
+        )
+    }
+
+    /// Change a token's sensor quality gating and weights (token owner only)
+    pub fn set_fusion_config(&mut self, token_id: TokenId, config: FusionConfig) {
+        let mut nft = self.biometric(&token_id);
+        nft.set_fusion_config(config);
+        self.biometrics_by_id.insert(&token_id, &nft);
+    }
+
+    pub fn get_privacy_settings(&self, token_id: TokenId) -> PrivacySettings {
+        self.biometric(&token_id).privacy
+    }
+
+    /// Change a token's privacy settings (token owner only); stored history
+    /// is redacted on read until `redact_interaction_history` rewrites it
+    pub fn set_privacy_settings(&mut self, token_id: TokenId, privacy: PrivacySettings) {
+        let mut nft = self.biometric(&token_id);
+        nft.set_privacy_settings(privacy);
+        self.biometrics_by_id.insert(&token_id, &nft);
+    }
+
+    /// Rewrite one page of a token's stored history to match its privacy
+    /// settings (token owner only), returning how many interactions remain;
+    /// the attached deposit must cover any storage added
+    #[payable]
+    pub fn redact_interaction_history(&mut self, token_id: TokenId, limit: Option<u64>) -> u64 {
+        let initial_storage = env::storage_usage();
+        let mut nft = self.biometric(&token_id);
+        let remaining = nft.redact_history(limit.unwrap_or(DEFAULT_HISTORY_PAGE));
+        self.biometrics_by_id.insert(&token_id, &nft);
+        charge_storage(initial_storage);
+        remaining
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+}
+
+impl BiometricNFTContract {
+    /// Load a token's biometric state with its owner taken from NEP-171,
+    /// which transfers keep current
+    fn biometric(&self, token_id: &TokenId) -> BiometricNFT {
+        let mut nft = self
+            .biometrics_by_id
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        if let Some(owner) = self.tokens.owner_by_id.get(token_id) {
+            nft.owner = owner;
+        }
+        nft
+    }
+}
+
+/// Require the attached deposit to cover storage added since
+/// `initial_storage`, refunding any excess
+fn charge_storage(initial_storage: u64) {
+    let storage_used = env::storage_usage().saturating_sub(initial_storage);
+    let required_deposit = env::storage_byte_cost().saturating_mul(storage_used as u128);
+    let attached = env::attached_deposit();
+    assert!(
+        attached >= required_deposit,
+        "Not enough deposit for storage"
+    );
+    if attached > required_deposit {
+        let _ = Promise::new(env::predecessor_account_id())
+            .transfer(attached.saturating_sub(required_deposit));
+    }
+}
+
+#[near]
+impl NonFungibleTokenCore for BiometricNFTContract {
+    #[payable]
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        self.tokens
+            .nft_transfer(receiver_id, token_id, approval_id, memo)
+    }
+
+    #[payable]
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        self.tokens
+            .nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+    }
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        self.tokens.nft_token(token_id)
+    }
+}
+
+#[near]
+impl NonFungibleTokenResolver for BiometricNFTContract {
+    #[private]
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        self.tokens.nft_resolve_transfer(
+            previous_owner_id,
+            receiver_id,
+            token_id,
+            approved_account_ids,
+        )
+    }
+}
+
+#[near]
+impl NonFungibleTokenApproval for BiometricNFTContract {
+    #[payable]
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.tokens.nft_approve(token_id, account_id, msg)
+    }
+
+    #[payable]
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        self.tokens.nft_revoke(token_id, account_id)
+    }
+
+    #[payable]
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        self.tokens.nft_revoke_all(token_id)
+    }
+
+    fn nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: AccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.tokens
+            .nft_is_approved(token_id, approved_account_id, approval_id)
+    }
+}
+
+#[near]
+impl NonFungibleTokenEnumeration for BiometricNFTContract {
+    fn nft_total_supply(&self) -> U128 {
+        self.tokens.nft_total_supply()
+    }
+
+    fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        self.tokens.nft_tokens(from_index, limit)
+    }
+
+    fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
+        self.tokens.nft_supply_for_owner(account_id)
+    }
+
+    fn nft_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        self.tokens
+            .nft_tokens_for_owner(account_id, from_index, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biometric_fusion::Modality;
+    use crate::interactive_advanced::{EEGData, GSRData, InteractionRules, CALIBRATION_READINGS};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, NearToken};
+
+    const MINT_DEPOSIT: NearToken = NearToken::from_millinear(100);
+
+    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(accounts(0))
+            .signer_account_id(predecessor_account_id.clone())
+            .predecessor_account_id(predecessor_account_id);
+        builder
+    }
+
+    fn interactive_metadata() -> InteractiveMetadata {
+        InteractiveMetadata {
+            title: "Alpha Bloom".to_string(),
+            description: "Responds to the viewer's mood".to_string(),
+            artist: accounts(0),
+            created_at: 0,
+            base_ipfs_cid: "QmBase".to_string(),
+            interaction_rules: InteractionRules {
+                valence_affects_color: true,
+                arousal_affects_speed: true,
+                dominance_affects_detail: true,
+                meditation_affects_morphing: true,
+                stress_affects_complexity: false,
+                sensitivity: 1.0,
+            },
+        }
+    }
+
+    fn token_metadata(title: &str) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: Some(1),
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn state(valence: f32, arousal: f32) -> DetailedEmotionalState {
+        DetailedEmotionalState {
+            valence,
+            arousal,
+            dominance: 0.5,
+            engagement: 0.7,
+            focus: 0.6,
+            stress: 0.2,
+            relaxation: 0.5,
+            confidence: 0.9,
+            primary_emotion: "joy".to_string(),
+            intensity: 0.8,
+        }
+    }
+
+    fn snapshot(meditation: f32) -> BiometricSnapshot {
+        BiometricSnapshot {
+            eeg_data: Some(EEGData {
+                alpha: 0.4,
+                beta: 0.3,
+                theta: 0.2,
+                delta: 0.1,
+                gamma: 0.1,
+                frontal_asymmetry: 0.1,
+                attention: 0.6,
+                meditation,
+            }),
+            heart_rate: None,
+            gsr: None,
+            facial_data: None,
+            quality_score: 0.9,
+            data_cid: "QmRecording".to_string(),
+        }
+    }
+
+    /// Register, calibrate at a resting state and authorize `account` on
+    /// `token_id`, leaving the context set to `account` with a storage
+    /// deposit attached
+    fn enroll(
+        contract: &mut BiometricNFTContract,
+        context: &mut VMContextBuilder,
+        token_id: &str,
+        owner: AccountId,
+        account: AccountId,
+        rest: DetailedEmotionalState,
+    ) {
+        context
+            .predecessor_account_id(account.clone())
+            .attached_deposit(MINT_DEPOSIT);
+        testing_env!(context.build());
+        contract.register_profile(token_id.to_string(), true);
+        for _ in 0..CALIBRATION_READINGS {
+            contract.submit_calibration(token_id.to_string(), rest.clone());
+        }
+
+        context.predecessor_account_id(owner);
+        testing_env!(context.build());
+        contract.authorize_profile(token_id.to_string(), account.clone());
+
+        context.predecessor_account_id(account);
+        testing_env!(context.build());
+    }
+
+    /// Contract with "alpha" owned by accounts(1) and "beta" by accounts(2)
+    fn setup() -> (BiometricNFTContract, VMContextBuilder) {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        let mut contract = BiometricNFTContract::new(accounts(0));
+
+        context.attached_deposit(MINT_DEPOSIT);
+        testing_env!(context.build());
+        contract.nft_mint(
+            "alpha".to_string(),
+            accounts(1),
+            token_metadata("Alpha"),
+            interactive_metadata(),
+        );
+        contract.nft_mint(
+            "beta".to_string(),
+            accounts(2),
+            token_metadata("Beta"),
+            interactive_metadata(),
+        );
+        (contract, context)
+    }
+
+    #[test]
+    fn test_mint_many_tokens() {
+        let (contract, _) = setup();
+        assert_eq!(contract.nft_total_supply(), U128(2));
+        assert_eq!(
+            contract.nft_token("alpha".to_string()).unwrap().owner_id,
+            accounts(1)
+        );
+        assert_eq!(contract.nft_supply_for_owner(accounts(2)), U128(1));
+        assert_eq!(
+            contract.get_interactive_metadata("beta".to_string()).title,
+            "Alpha Bloom"
+        );
+    }
+
+    #[test]
+    fn test_interact_with_biometrics_modulates_one_token() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+
+        let before = contract.get_visual_state("alpha".to_string());
+        let after = contract.interact(
+            "alpha".to_string(),
+            state(0.8, 0.9),
+            snapshot(0.7),
+            InteractionType::Meditation,
+        );
+        assert!(after.animation_speed > before.animation_speed);
+        assert_eq!(after.morphing_rate, 0.7);
+
+        // The history is private, so read it as the token owner
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let history = contract.get_interaction_history("alpha".to_string(), None, None);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].user, accounts(3));
+        assert_eq!(
+            history[0].state_before.animation_speed,
+            before.animation_speed
+        );
+        assert!(
+            contract
+                .get_emotional_resonance("alpha".to_string())
+                .resonance_level
+                > 0.0
+        );
+
+        // The other token's prefixed collections are untouched
+        assert_eq!(
+            contract
+                .get_emotional_resonance("beta".to_string())
+                .resonance_level,
+            0.0
+        );
+        assert_eq!(
+            contract
+                .get_visual_state("beta".to_string())
+                .animation_speed,
+            before.animation_speed
+        );
+    }
+
+    #[test]
+    fn test_interact_with_biometrics_clamps_out_of_range_readings() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+
+        contract.interact(
+            "alpha".to_string(),
+            state(1.7, 0.5),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let history = contract.get_interaction_history("alpha".to_string(), None, Some(10));
+        assert_eq!(history[0].emotional_state.valence, 1.0);
+    }
+
+    #[test]
+    fn test_transfer_moves_owner_only_rights() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_yoctonear(1));
+        testing_env!(context.build());
+        contract.nft_transfer(accounts(3), "alpha".to_string(), None, None);
+
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.set_smoothing_config("alpha".to_string(), SmoothingConfig::default());
+        assert_eq!(
+            contract.nft_token("alpha".to_string()).unwrap().owner_id,
+            accounts(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can configure smoothing")]
+    fn test_previous_owner_loses_rights() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(NearToken::from_yoctonear(1));
+        testing_env!(context.build());
+        contract.nft_transfer(accounts(3), "alpha".to_string(), None, None);
+
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.set_smoothing_config("alpha".to_string(), SmoothingConfig::default());
+    }
+
+    fn privacy(store: bool, share: bool, analytics: bool, anonymize: bool) -> PrivacySettings {
+        PrivacySettings {
+            store_biometric_data: store,
+            share_interaction_history: share,
+            allow_emotional_analytics: analytics,
+            anonymize_data: anonymize,
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Interaction history is private")]
+    fn test_private_history_hidden_from_other_accounts() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+
+        // Neither the owner nor an authorized profile
+        context.predecessor_account_id(accounts(4));
+        testing_env!(context.build());
+        contract.get_interaction_history("alpha".to_string(), None, None);
+    }
+
+    #[test]
+    fn test_privacy_settings_redact_new_and_stored_interactions() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+
+        // The owner hides raw readings and viewer accounts, and shares the rest
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_privacy_settings("alpha".to_string(), privacy(false, true, false, true));
+
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(4),
+            state(0.0, 0.5),
+        );
+        contract.interact(
+            "alpha".to_string(),
+            state(0.6, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+
+        let history = contract.get_interaction_history("alpha".to_string(), None, None);
+        assert_eq!(history.len(), 2);
+        for interaction in &history {
+            assert!(interaction.biometric_data.eeg_data.is_none());
+            assert!(interaction.biometric_data.data_cid.starts_with("sha256:"));
+            assert!(interaction.anonymized);
+            assert_eq!(interaction.user.as_str().len(), 64);
+        }
+        assert_ne!(history[0].user, history[1].user);
+        assert_ne!(history[0].user, accounts(3));
+
+        // The owner rewrites stored history a page at a time; a second pass
+        // leaves random IDs and hashes as they were
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        assert_eq!(
+            contract.redact_interaction_history("alpha".to_string(), Some(1)),
+            1
+        );
+        assert_eq!(
+            contract.redact_interaction_history("alpha".to_string(), None),
+            0
+        );
+        let stored = contract.get_interaction_history("alpha".to_string(), None, None);
+        contract.set_privacy_settings("alpha".to_string(), privacy(false, true, false, true));
+        contract.redact_interaction_history("alpha".to_string(), None);
+        let again = contract.get_interaction_history("alpha".to_string(), None, None);
+        for (first, second) in stored.iter().zip(&again) {
+            assert_eq!(first.user, second.user);
+            assert_eq!(
+                first.biometric_data.data_cid,
+                second.biometric_data.data_cid
+            );
+        }
+
+        // Analytics were switched off, so the collective mood was cleared
+        assert_eq!(
+            contract
+                .get_collective_mood("alpha".to_string())
+                .contributing_viewers,
+            0
+        );
+    }
+
+    #[test]
+    fn test_anonymizing_restarts_collective_mood() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.set_privacy_settings("alpha".to_string(), privacy(true, false, true, true));
+        assert_eq!(
+            contract
+                .get_collective_mood("alpha".to_string())
+                .contributing_viewers,
+            0
+        );
+
+        // The same viewer now counts once, under a random ID
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+        assert_eq!(
+            contract
+                .get_collective_mood("alpha".to_string())
+                .contributing_viewers,
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can change privacy settings")]
+    fn test_only_owner_changes_privacy() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.set_privacy_settings("alpha".to_string(), privacy(true, true, true, false));
+    }
+
+    #[test]
+    fn test_interaction_is_read_relative_to_baseline() {
+        let (mut contract, mut context) = setup();
+        // An excitable viewer whose resting arousal is 0.8
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.1, 0.8),
+        );
+        let status = contract
+            .get_profile_status("alpha".to_string(), accounts(3))
+            .unwrap();
+        assert!(status.authorized && status.calibrated);
+
+        // Their resting state reads as neutral, so speed is the neutral 1.0
+        let visual = contract.interact(
+            "alpha".to_string(),
+            state(0.1, 0.8),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+        assert!((visual.animation_speed - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_calibration_counts_down() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(MINT_DEPOSIT);
+        testing_env!(context.build());
+        contract.register_profile("alpha".to_string(), true);
+        let remaining: Vec<u32> = (0..CALIBRATION_READINGS)
+            .map(|_| contract.submit_calibration("alpha".to_string(), state(0.0, 0.5)))
+            .collect();
+        assert_eq!(remaining, vec![4, 3, 2, 1, 0]);
+        let status = contract
+            .get_profile_status("alpha".to_string(), accounts(3))
+            .unwrap();
+        assert!(status.calibrated && !status.authorized);
+    }
+
+    #[test]
+    #[should_panic(expected = "Profile not authorized")]
+    fn test_recalibration_needs_reauthorization() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        for _ in 0..CALIBRATION_READINGS {
+            contract.submit_calibration("alpha".to_string(), state(0.9, 0.9));
+        }
+        let status = contract
+            .get_profile_status("alpha".to_string(), accounts(3))
+            .unwrap();
+        assert!(status.calibrated && !status.authorized);
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Profile not registered")]
+    fn test_unregistered_viewer_cannot_interact() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough deposit for storage")]
+    fn test_interaction_requires_storage_deposit() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        context.attached_deposit(NearToken::from_yoctonear(0));
+        testing_env!(context.build());
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Profile not registered")]
+    fn test_revoked_profile_cannot_interact() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        contract.revoke_profile("alpha".to_string(), accounts(3));
+        assert!(contract
+            .get_profile_status("alpha".to_string(), accounts(3))
+            .is_none());
+
+        context.predecessor_account_id(accounts(3));
+        testing_env!(context.build());
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            snapshot(0.5),
+            InteractionType::View,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Consent is required")]
+    fn test_registration_requires_consent() {
+        let (mut contract, mut context) = setup();
+        context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(MINT_DEPOSIT);
+        testing_env!(context.build());
+        contract.register_profile("alpha".to_string(), false);
+    }
+
+    #[test]
+    fn test_interaction_reports_contributing_sensors() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        let mut biometrics = snapshot(0.6);
+        biometrics.gsr = Some(GSRData {
+            conductance: 5.0,
+            arousal_level: 0.4,
+        });
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            biometrics,
+            InteractionType::BiofeedbackLoop,
+        );
+
+        context.predecessor_account_id(accounts(1));
+        testing_env!(context.build());
+        let history = contract.get_interaction_history("alpha".to_string(), None, None);
+        let modalities: Vec<Modality> = history[0]
+            .contributions
+            .iter()
+            .map(|c| c.modality)
+            .collect();
+        assert_eq!(modalities, vec![Modality::Eeg, Modality::Gsr]);
+        // Stress now comes from the sensors, not the reported 0.2: EEG beta
+        // over alpha+beta at weight 1.0 and GSR arousal at weight 0.6
+        let expected = (0.3 / 0.7 + 0.4 * 0.6) / 1.6;
+        assert!((history[0].emotional_state.stress - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Biometric data rejected")]
+    fn test_low_quality_snapshot_is_rejected() {
+        let (mut contract, mut context) = setup();
+        enroll(
+            &mut contract,
+            &mut context,
+            "alpha",
+            accounts(1),
+            accounts(3),
+            state(0.0, 0.5),
+        );
+        contract.interact(
+            "alpha".to_string(),
+            state(0.2, 0.4),
+            BiometricSnapshot {
+                quality_score: 0.1,
+                ..snapshot(0.5)
+            },
+            InteractionType::View,
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! Biometric fusion - Quality-weighted fusion of EEG, heart, skin and face
+//!
+//! Each modality present in a snapshot gets a plausibility score from its
+//! own readings, which is multiplied by the modality's reliability to weight
+//! its estimates of stress, relaxation and engagement. Snapshots below the
+//! quality threshold, or whose sensors are all unusable, are rejected rather
+//! than guessed from. Snapshots without any sensor data are rejected too,
+//! unless the config allows falling back to the viewer's self-report.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::interactive_advanced::{
+    BiometricSnapshot, DetailedEmotionalState, EEGData, FacialData, GSRData, HeartRateData,
+};
+
+/// Heart rates outside this range are treated as sensor faults
+const PLAUSIBLE_BPM: (u32, u32) = (30, 220);
+/// HRV (RMSSD, ms) treated as fully relaxed
+const RELAXED_HRV_MS: f32 = 100.0;
+/// Skin conductance (microsiemens) above which GSR is implausible
+const MAX_CONDUCTANCE: f32 = 100.0;
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Modality {
+    Eeg,
+    HeartRate,
+    Gsr,
+    Facial,
+}
+
+/// Quality thresholds and per-modality reliability
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FusionConfig {
+    /// Snapshots with a lower `quality_score` are rejected
+    pub min_quality: f32,
+    /// Modalities with a lower plausibility score are left out
+    pub min_modality_quality: f32,
+    pub eeg_reliability: f32,
+    pub heart_rate_reliability: f32,
+    pub gsr_reliability: f32,
+    pub facial_reliability: f32,
+    /// Keep self-reported values for snapshots with no sensor data at all
+    #[serde(default)]
+    pub allow_self_report: bool,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            min_quality: 0.3,
+            min_modality_quality: 0.5,
+            eeg_reliability: 1.0,
+            heart_rate_reliability: 0.8,
+            gsr_reliability: 0.6,
+            facial_reliability: 0.7,
+            allow_self_report: false,
+        }
+    }
+}
+
+/// How much one modality contributed to the fused estimate
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ModalityContribution {
+    pub modality: Modality,
+    /// Plausibility of this modality's readings (0.0 to 1.0)
+    pub quality: f32,
+    /// Share of the fused estimate; shares sum to 1
+    pub weight: f32,
+}
+
+/// Signals derived from every usable sensor
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FusedBiometrics {
+    pub stress: f32,
+    pub relaxation: f32,
+    pub engagement: f32,
+    /// Snapshot quality times the weighted modality quality
+    pub quality: f32,
+    pub contributions: Vec<ModalityContribution>,
+}
+
+/// One modality's estimate before weighting
+struct Estimate {
+    modality: Modality,
+    quality: f32,
+    reliability: f32,
+    stress: f32,
+    relaxation: f32,
+    engagement: f32,
+}
+
+impl FusionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            self.min_quality,
+            self.min_modality_quality,
+            self.eeg_reliability,
+            self.heart_rate_reliability,
+            self.gsr_reliability,
+            self.facial_reliability,
+        ];
+        if values.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            return Err("Fusion thresholds and reliabilities must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Fuse a snapshot's modalities, or explain why it cannot be used
+///
+/// With `allow_self_report`, a snapshot with no sensor data at all falls
+/// back to the `reported` stress, relaxation and engagement, with no
+/// contributions and zero quality.
+pub fn fuse(
+    snapshot: &BiometricSnapshot,
+    reported: &DetailedEmotionalState,
+    config: &FusionConfig,
+) -> Result<FusedBiometrics, String> {
+    let has_sensors = snapshot.eeg_data.is_some()
+        || snapshot.heart_rate.is_some()
+        || snapshot.gsr.is_some()
+        || snapshot.facial_data.is_some();
+    if !has_sensors {
+        if !config.allow_self_report {
+            return Err("Snapshot has no sensor data".to_string());
+        }
+        return Ok(FusedBiometrics {
+            stress: reported.stress,
+            relaxation: reported.relaxation,
+            engagement: reported.engagement,
+            quality: 0.0,
+            contributions: Vec::new(),
+        });
+    }
+    if !snapshot.quality_score.is_finite() || snapshot.quality_score < config.min_quality {
+        return Err(format!(
+            "Snapshot quality {} is below the minimum {}",
+            snapshot.quality_score, config.min_quality
+        ));
+    }
+
+    let estimates: Vec<Estimate> = [
+        snapshot.eeg_data.as_ref().map(|d| from_eeg(d, config)),
+        snapshot
+            .heart_rate
+            .as_ref()
+            .map(|d| from_heart_rate(d, config)),
+        snapshot.gsr.as_ref().map(|d| from_gsr(d, config)),
+        snapshot
+            .facial_data
+            .as_ref()
+            .map(|d| from_facial(d, config)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|e| e.quality > 0.0 && e.quality >= config.min_modality_quality && e.reliability > 0.0)
+    .collect();
+    let total: f32 = estimates.iter().map(|e| e.quality * e.reliability).sum();
+    if estimates.is_empty() || total <= 0.0 {
+        return Err("No usable sensor data".to_string());
+    }
+
+    let weighted = |f: fn(&Estimate) -> f32| {
+        estimates
+            .iter()
+            .map(|e| f(e) * e.quality * e.reliability)
+            .sum::<f32>()
+            / total
+    };
+    Ok(FusedBiometrics {
+        stress: weighted(|e| e.stress).clamp(0.0, 1.0),
+        relaxation: weighted(|e| e.relaxation).clamp(0.0, 1.0),
+        engagement: weighted(|e| e.engagement).clamp(0.0, 1.0),
+        quality: snapshot.quality_score.min(1.0) * weighted(|e| e.quality),
+        contributions: estimates
+            .iter()
+            .map(|e| ModalityContribution {
+                modality: e.modality,
+                quality: e.quality,
+                weight: e.quality * e.reliability / total,
+            })
+            .collect(),
+    })
+}
+
+/// Beta over alpha+beta tracks stress; attention and meditation come
+/// straight from the headset
+fn from_eeg(eeg: &EEGData, config: &FusionConfig) -> Estimate {
+    let bands = [eeg.alpha, eeg.beta, eeg.theta, eeg.delta, eeg.gamma];
+    let plausible = bands.iter().all(|b| b.is_finite() && *b >= 0.0)
+        && bands.iter().sum::<f32>() > 0.0
+        && (0.0..=1.0).contains(&eeg.attention)
+        && (0.0..=1.0).contains(&eeg.meditation);
+    let alert = eeg.alpha + eeg.beta;
+    Estimate {
+        modality: Modality::Eeg,
+        quality: if plausible { 1.0 } else { 0.0 },
+        reliability: config.eeg_reliability,
+        stress: if alert > 0.0 { eeg.beta / alert } else { 0.5 },
+        relaxation: eeg.meditation,
+        engagement: eeg.attention,
+    }
+}
+
+/// High HRV means relaxed; heart rate above resting means engaged
+fn from_heart_rate(heart: &HeartRateData, config: &FusionConfig) -> Estimate {
+    let (min_bpm, max_bpm) = PLAUSIBLE_BPM;
+    let plausible = (min_bpm..=max_bpm).contains(&heart.bpm)
+        && heart.hrv.is_finite()
+        && heart.hrv >= 0.0
+        && heart.stress_index.is_finite();
+    Estimate {
+        modality: Modality::HeartRate,
+        quality: if plausible { 1.0 } else { 0.0 },
+        reliability: config.heart_rate_reliability,
+        stress: heart.stress_index.clamp(0.0, 1.0),
+        relaxation: (heart.hrv / RELAXED_HRV_MS).clamp(0.0, 1.0),
+        engagement: ((heart.bpm as f32 - 60.0) / 60.0).clamp(0.0, 1.0),
+    }
+}
+
+/// Skin conductance rises with arousal, whatever its cause
+fn from_gsr(gsr: &GSRData, config: &FusionConfig) -> Estimate {
+    let plausible = gsr.conductance.is_finite()
+        && gsr.conductance > 0.0
+        && gsr.conductance <= MAX_CONDUCTANCE
+        && gsr.arousal_level.is_finite();
+    let arousal = gsr.arousal_level.clamp(0.0, 1.0);
+    Estimate {
+        modality: Modality::Gsr,
+        quality: if plausible { 1.0 } else { 0.0 },
+        reliability: config.gsr_reliability,
+        stress: arousal,
+        relaxation: 1.0 - arousal,
+        engagement: arousal,
+    }
+}
+
+/// Expression probabilities should sum to one; the further off they are,
+/// the less the classifier is trusted
+fn from_facial(face: &FacialData, config: &FusionConfig) -> Estimate {
+    let scores = [
+        face.happiness,
+        face.sadness,
+        face.anger,
+        face.surprise,
+        face.fear,
+        face.disgust,
+        face.neutral,
+    ];
+    let quality = if scores.iter().all(|s| s.is_finite() && *s >= 0.0) {
+        (1.0 - (scores.iter().sum::<f32>() - 1.0).abs()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    Estimate {
+        modality: Modality::Facial,
+        quality,
+        reliability: config.facial_reliability,
+        stress: (face.anger + face.fear + face.disgust).clamp(0.0, 1.0),
+        relaxation: (face.neutral + face.happiness * 0.5).clamp(0.0, 1.0),
+        engagement: (1.0 - face.neutral).clamp(0.0, 1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emotion_model::Emotion;
+
+    fn eeg() -> EEGData {
+        EEGData {
+            alpha: 0.6,
+            beta: 0.2,
+            theta: 0.3,
+            delta: 0.1,
+            gamma: 0.1,
+            frontal_asymmetry: 0.0,
+            attention: 0.4,
+            meditation: 0.8,
+        }
+    }
+
+    fn heart(bpm: u32) -> HeartRateData {
+        HeartRateData {
+            bpm,
+            hrv: 80.0,
+            stress_index: 0.2,
+        }
+    }
+
+    fn reported() -> DetailedEmotionalState {
+        DetailedEmotionalState {
+            stress: 0.3,
+            relaxation: 0.6,
+            engagement: 0.7,
+            ..DetailedEmotionalState::from(Emotion::new(0.2, 0.5, 0.5))
+        }
+    }
+
+    fn snapshot(quality_score: f32) -> BiometricSnapshot {
+        BiometricSnapshot {
+            eeg_data: Some(eeg()),
+            heart_rate: Some(heart(72)),
+            gsr: None,
+            facial_data: None,
+            quality_score,
+            data_cid: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_fuses_available_modalities_by_reliability() {
+        let fused = fuse(&snapshot(0.9), &reported(), &FusionConfig::default()).unwrap();
+        let modalities: Vec<Modality> = fused.contributions.iter().map(|c| c.modality).collect();
+        assert_eq!(modalities, vec![Modality::Eeg, Modality::HeartRate]);
+
+        // EEG has weight 1.0 and heart rate 0.8
+        let eeg_share = fused.contributions[0].weight;
+        assert!((eeg_share - 1.0 / 1.8).abs() < 1e-6);
+        let expected_relaxation = (0.8 * 1.0 + 0.8 * 0.8) / 1.8;
+        assert!((fused.relaxation - expected_relaxation).abs() < 1e-6);
+        assert!((fused.stress - (0.25 + 0.2 * 0.8) / 1.8).abs() < 1e-6);
+        assert!((fused.quality - 0.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_implausible_modality_is_left_out() {
+        let mut faulty = snapshot(0.9);
+        faulty.heart_rate = Some(heart(400));
+        let fused = fuse(&faulty, &reported(), &FusionConfig::default()).unwrap();
+        assert_eq!(fused.contributions.len(), 1);
+        assert_eq!(fused.contributions[0].modality, Modality::Eeg);
+        assert!((fused.relaxation - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rejects_low_quality_and_unusable_snapshots() {
+        let config = FusionConfig::default();
+        assert!(fuse(&snapshot(0.1), &reported(), &config)
+            .unwrap_err()
+            .contains("below the minimum"));
+
+        let mut unusable = snapshot(0.9);
+        unusable.eeg_data = None;
+        unusable.heart_rate = Some(heart(400));
+        assert_eq!(
+            fuse(&unusable, &reported(), &config).unwrap_err(),
+            "No usable sensor data"
+        );
+
+        let invalid = FusionConfig {
+            min_quality: 1.5,
+            ..FusionConfig::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_self_report_without_sensors_needs_opt_in() {
+        let mut empty = snapshot(0.0);
+        empty.eeg_data = None;
+        empty.heart_rate = None;
+        assert!(fuse(&empty, &reported(), &FusionConfig::default()).is_err());
+
+        let config = FusionConfig {
+            allow_self_report: true,
+            ..FusionConfig::default()
+        };
+        let fused = fuse(&empty, &reported(), &config).unwrap();
+        assert_eq!(
+            (fused.stress, fused.relaxation, fused.engagement),
+            (0.3, 0.6, 0.7)
+        );
+        assert!(fused.contributions.is_empty());
+    }
+
+    #[test]
+    fn test_facial_estimates_stay_in_range() {
+        let face = FacialData {
+            happiness: 0.0,
+            sadness: 0.0,
+            anger: 0.6,
+            surprise: 0.0,
+            fear: 0.5,
+            disgust: 0.4,
+            neutral: 0.0,
+        };
+        let estimate = from_facial(&face, &FusionConfig::default());
+        assert_eq!(estimate.stress, 1.0);
+        assert!((0.0..=1.0).contains(&estimate.relaxation));
+    }
+}
@@ -0,0 +1,343 @@
+//! Community metrics - Unique users, engagement rates and trending detection
+//!
+//! Unique users are counted exactly while the audience is small and with a
+//! HyperLogLog sketch once it grows. Interactions are bucketed by hour for
+//! windowed rates, and trending compares a fast-decaying velocity against a
+//! slow-decaying baseline so it switches off again when activity dies down.
+
+use std::collections::VecDeque;
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Timestamp};
+
+const NANOS_PER_HOUR: u64 = 3_600 * 1_000_000_000;
+/// Distinct users tracked exactly before switching to the sketch
+pub const EXACT_USER_LIMIT: usize = 256;
+/// HyperLogLog precision: 2^10 registers, about 3% standard error
+const HLL_PRECISION: u8 = 10;
+/// Hourly buckets kept for windowed rates (one week)
+const MAX_HOURLY_BUCKETS: usize = 168;
+/// Half-life of the velocity signal
+const VELOCITY_HALF_LIFE_NS: u64 = NANOS_PER_HOUR;
+/// Half-life of the baseline signal
+const BASELINE_HALF_LIFE_NS: u64 = 24 * NANOS_PER_HOUR;
+/// Velocity must exceed the baseline by this factor to trend
+const TRENDING_RATIO: f32 = 2.0;
+/// Baseline floor in interactions per hour, so a brand-new token needs a
+/// real burst rather than a single interaction to trend
+const MIN_BASELINE_RATE: f32 = 1.0;
+
+/// Supported HyperLogLog precisions
+const HLL_PRECISION_RANGE: (u8, u8) = (4, 16);
+
+/// HyperLogLog cardinality sketch over 64-bit hashes
+#[derive(BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde", try_from = "SketchParts")]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+/// Stored form of a `HyperLogLog`, checked before it is used
+#[derive(BorshDeserialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct SketchParts {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl TryFrom<SketchParts> for HyperLogLog {
+    type Error = String;
+
+    fn try_from(parts: SketchParts) -> Result<Self, Self::Error> {
+        let (min, max) = HLL_PRECISION_RANGE;
+        if parts.precision < min || parts.precision > max {
+            return Err(format!("Unsupported sketch precision {}", parts.precision));
+        }
+        if parts.registers.len() != 1 << parts.precision {
+            return Err("Sketch register count does not match its precision".to_string());
+        }
+        Ok(Self {
+            precision: parts.precision,
+            registers: parts.registers,
+        })
+    }
+}
+
+impl BorshDeserialize for HyperLogLog {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Self::try_from(SketchParts::deserialize_reader(reader)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(HLL_PRECISION_RANGE.0, HLL_PRECISION_RANGE.1);
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    pub fn insert(&mut self, hash: u64) {
+        let p = self.precision as u32;
+        let index = (hash >> (64 - p)) as usize;
+        // Rank of the first set bit in the remaining 64 - p bits
+        let rank = ((hash << p).leading_zeros() + 1).min(64 - p + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        // Linear counting is more accurate while many registers are empty
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+/// Distinct-user counter: an exact set of account hashes, replaced by a
+/// sketch past `EXACT_USER_LIMIT`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UniqueUsers {
+    /// Sorted account hashes; exact up to 64-bit hash collisions
+    exact: Vec<u64>,
+    sketch: Option<HyperLogLog>,
+}
+
+impl UniqueUsers {
+    /// Record a user, returning whether they are new; once the sketch is in
+    /// use, whether the estimate grew
+    pub fn insert(&mut self, user: &AccountId) -> bool {
+        let hash = hash_account(user);
+        if let Some(sketch) = &mut self.sketch {
+            let before = sketch.estimate();
+            sketch.insert(hash);
+            return sketch.estimate() > before;
+        }
+        match self.exact.binary_search(&hash) {
+            Ok(_) => false,
+            Err(position) => {
+                self.exact.insert(position, hash);
+                if self.exact.len() > EXACT_USER_LIMIT {
+                    let mut sketch = HyperLogLog::new(HLL_PRECISION);
+                    self.exact.drain(..).for_each(|h| sketch.insert(h));
+                    self.sketch = Some(sketch);
+                }
+                true
+            }
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        match &self.sketch {
+            Some(sketch) => sketch.estimate().round() as u32,
+            None => self.exact.len() as u32,
+        }
+    }
+
+    /// Whether the count is exact rather than estimated
+    pub fn is_exact(&self) -> bool {
+        self.sketch.is_none()
+    }
+}
+
+/// Exponentially decayed event count
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+struct DecayedCount {
+    value: f32,
+    updated: Timestamp,
+}
+
+impl DecayedCount {
+    fn at(&self, now: Timestamp, half_life_ns: u64) -> f32 {
+        let elapsed = now.saturating_sub(self.updated) as f32;
+        self.value * 0.5f32.powf(elapsed / half_life_ns as f32)
+    }
+
+    fn add(&mut self, now: Timestamp, half_life_ns: u64) {
+        self.value = self.at(now, half_life_ns) + 1.0;
+        self.updated = self.updated.max(now);
+    }
+
+    /// Steady-state events per hour implied by the decayed count
+    fn rate_per_hour(&self, now: Timestamp, half_life_ns: u64) -> f32 {
+        let half_life_hours = half_life_ns as f32 / NANOS_PER_HOUR as f32;
+        self.at(now, half_life_ns) * std::f32::consts::LN_2 / half_life_hours
+    }
+}
+
+/// Unique users, hourly interaction buckets and trending signals
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommunityTracker {
+    pub users: UniqueUsers,
+    /// (hour start, interactions), oldest first
+    hourly: VecDeque<(Timestamp, u32)>,
+    velocity: DecayedCount,
+    baseline: DecayedCount,
+}
+
+impl CommunityTracker {
+    /// Record one interaction, returning whether the user is new
+    pub fn record(&mut self, user: &AccountId, now: Timestamp) -> bool {
+        let hour = now - now % NANOS_PER_HOUR;
+        match self
+            .hourly
+            .iter_mut()
+            .rev()
+            .find(|(start, _)| *start == hour)
+        {
+            Some((_, count)) => *count += 1,
+            None => {
+                let position = self.hourly.partition_point(|(start, _)| *start < hour);
+                self.hourly.insert(position, (hour, 1));
+                while self.hourly.len() > MAX_HOURLY_BUCKETS {
+                    self.hourly.pop_front();
+                }
+            }
+        }
+        self.velocity.add(now, VELOCITY_HALF_LIFE_NS);
+        self.baseline.add(now, BASELINE_HALF_LIFE_NS);
+        self.users.insert(user)
+    }
+
+    /// Interactions in the hourly buckets overlapping the last `window_ns`
+    pub fn interactions_in(&self, window_ns: u64, now: Timestamp) -> u32 {
+        let since = now.saturating_sub(window_ns);
+        self.hourly
+            .iter()
+            .filter(|(start, _)| start + NANOS_PER_HOUR > since && *start <= now)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Average interactions per hour over the last `window_ns`
+    pub fn rate_per_hour(&self, window_ns: u64, now: Timestamp) -> f32 {
+        let hours = (window_ns as f32 / NANOS_PER_HOUR as f32).max(1.0);
+        self.interactions_in(window_ns, now) as f32 / hours
+    }
+
+    /// Current velocity divided by the baseline rate
+    pub fn trending_score(&self, now: Timestamp) -> f32 {
+        let velocity = self.velocity.rate_per_hour(now, VELOCITY_HALF_LIFE_NS);
+        let baseline = self.baseline.rate_per_hour(now, BASELINE_HALF_LIFE_NS);
+        velocity / baseline.max(MIN_BASELINE_RATE)
+    }
+
+    pub fn is_trending(&self, now: Timestamp) -> bool {
+        self.trending_score(now) >= TRENDING_RATIO
+    }
+}
+
+/// Stable 64-bit hash of an account id (FNV-1a with a final avalanche),
+/// identical on every platform so sketches survive upgrades
+fn hash_account(account: &AccountId) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in account.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    // splitmix64 finalizer spreads FNV's weak high bits
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(i: usize) -> AccountId {
+        format!("user{}.testnet", i).parse().unwrap()
+    }
+
+    #[test]
+    fn test_repeat_users_count_once() {
+        let mut users = UniqueUsers::default();
+        assert!(users.insert(&account(1)));
+        assert!(!users.insert(&account(1)));
+        assert!(users.insert(&account(2)));
+        assert_eq!(users.count(), 2);
+        assert!(users.is_exact());
+    }
+
+    #[test]
+    fn test_sketch_estimate_for_large_audiences() {
+        let mut users = UniqueUsers::default();
+        for i in 0..5_000 {
+            users.insert(&account(i));
+            users.insert(&account(i));
+        }
+        assert!(!users.is_exact());
+        let error = (users.count() as f32 - 5_000.0).abs() / 5_000.0;
+        assert!(error < 0.1, "estimate {}", users.count());
+    }
+
+    #[test]
+    fn test_malformed_sketches_are_rejected() {
+        use near_sdk::{borsh, serde_json};
+        let short = r#"{"precision":10,"registers":[0,0]}"#;
+        let zero = r#"{"precision":0,"registers":[0]}"#;
+        assert!(serde_json::from_str::<HyperLogLog>(short).is_err());
+        assert!(serde_json::from_str::<HyperLogLog>(zero).is_err());
+
+        let sketch = HyperLogLog::new(4);
+        let json = serde_json::to_string(&sketch).unwrap();
+        assert!(serde_json::from_str::<HyperLogLog>(&json).is_ok());
+        let bytes = borsh::to_vec(&sketch).unwrap();
+        assert!(borsh::from_slice::<HyperLogLog>(&bytes).is_ok());
+        assert!(borsh::from_slice::<HyperLogLog>(&borsh::to_vec(&(0u8, vec![0u8])).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_windowed_rates() {
+        let mut tracker = CommunityTracker::default();
+        let user = account(1);
+        for minute in 0..30 {
+            tracker.record(&user, minute * 60 * 1_000_000_000);
+        }
+        tracker.record(&user, 3 * NANOS_PER_HOUR);
+
+        let now = 3 * NANOS_PER_HOUR + 1;
+        assert_eq!(tracker.interactions_in(NANOS_PER_HOUR, now), 1);
+        assert_eq!(tracker.interactions_in(4 * NANOS_PER_HOUR, now), 31);
+        assert_eq!(tracker.rate_per_hour(4 * NANOS_PER_HOUR, now), 31.0 / 4.0);
+        assert_eq!(tracker.users.count(), 1);
+    }
+
+    #[test]
+    fn test_trending_rises_and_decays() {
+        let mut tracker = CommunityTracker::default();
+        // A steady trickle of one interaction an hour for two days
+        for hour in 0..48 {
+            tracker.record(&account(hour as usize), hour * NANOS_PER_HOUR);
+        }
+        let calm = 48 * NANOS_PER_HOUR;
+        assert!(!tracker.is_trending(calm));
+
+        // A burst of 20 interactions in ten minutes
+        for i in 0..20 {
+            tracker.record(&account(100 + i as usize), calm + i * 30 * 1_000_000_000);
+        }
+        let burst = calm + 10 * 60 * 1_000_000_000;
+        assert!(tracker.is_trending(burst));
+
+        // Six quiet hours later the burst has decayed away
+        assert!(!tracker.is_trending(burst + 6 * NANOS_PER_HOUR));
+    }
+}
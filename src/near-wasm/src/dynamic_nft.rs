@@ -11,6 +11,13 @@ use near_sdk::{
     env, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, PromiseOrValue,
 };
 use std::collections::HashMap;
+use emotion_model::validation::{sanitize_f32, Sanitize, ValidationPolicy};
+use emotion_model::{Emotion, EmotionError, AROUSAL_RANGE, CONFIDENCE_RANGE, DOMINANCE_RANGE, VALENCE_RANGE};
+
+use crate::emotion_smoothing::{EmotionSmoother, SmoothingConfig};
+use crate::emotional_similarity::{
+    self, SimilarityMatch, SimilarityQuery, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
 
 /// NEP-177 Token Metadata
 /// https://nomicon.io/Standards/Tokens/NonFungibleToken/Metadata
@@ -40,6 +47,8 @@ pub struct DynamicMetadata {
     pub last_interaction: u64,
     pub complexity_score: f32,
     pub ipfs_history: Vec<String>, // Historical IPFS CIDs
+    pub emotion_history: Vec<EmotionalState>, // Recent states, oldest first
+    pub emotional_category: String, // Category after hysteresis
 }
 
 /// Emotional state using VAD model
@@ -53,6 +62,45 @@ pub struct EmotionalState {
     pub timestamp: u64,
 }
 
+impl From<EmotionalState> for Emotion {
+    fn from(s: EmotionalState) -> Self {
+        Emotion::new(s.valence, s.arousal, s.dominance)
+            .with_confidence(s.confidence)
+            .with_timestamp(s.timestamp)
+    }
+}
+
+/// Sensor and model output drifts slightly out of range, so clamp it
+const EMOTION_POLICY: ValidationPolicy = ValidationPolicy::Clamp;
+/// Emotional states kept per token for trajectory similarity
+const EMOTION_HISTORY_LEN: usize = 32;
+/// Longest query trajectory accepted by `scan_similar_tokens`
+const MAX_QUERY_TRAJECTORY: usize = 64;
+
+impl Sanitize for EmotionalState {
+    fn sanitize(self, policy: ValidationPolicy) -> Result<Self, EmotionError> {
+        Ok(Self {
+            valence: sanitize_f32("valence", self.valence, VALENCE_RANGE, policy)?,
+            arousal: sanitize_f32("arousal", self.arousal, AROUSAL_RANGE, policy)?,
+            dominance: sanitize_f32("dominance", self.dominance, DOMINANCE_RANGE, policy)?,
+            confidence: sanitize_f32("confidence", self.confidence, CONFIDENCE_RANGE, policy)?,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+impl From<Emotion> for EmotionalState {
+    fn from(e: Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            confidence: e.confidence,
+            timestamp: e.timestamp,
+        }
+    }
+}
+
 /// Token struct following NEP-171
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Token {
@@ -63,6 +111,27 @@ pub struct Token {
     pub dynamic_metadata: DynamicMetadata,
 }
 
+/// Dynamic metadata as stored before emotion history and hysteresis
+/// categories were tracked
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DynamicMetadataV1 {
+    pub emotional_state: EmotionalState,
+    pub interaction_count: u64,
+    pub last_interaction: u64,
+    pub complexity_score: f32,
+    pub ipfs_history: Vec<String>,
+}
+
+/// Token as stored by `DynamicNFTV1`
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenV1 {
+    pub owner_id: AccountId,
+    pub approved_account_ids: HashMap<AccountId, u64>,
+    pub next_approval_id: u64,
+    pub metadata: TokenMetadata,
+    pub dynamic_metadata: DynamicMetadataV1,
+}
+
 /// Contract metadata following NEP-177
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -76,6 +145,27 @@ pub struct NFTContractMetadata {
     pub reference_hash: Option<Base64VecU8>, // Base64-encoded sha256 hash of JSON
 }
 
+/// Matches from one slice of tokens, ranked within that slice only
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SimilarityScan {
+    pub matches: Vec<SimilarityMatch>,
+    /// Tokens scanned in this slice
+    pub scanned: u64,
+    /// Token index where the next slice starts, `None` after the last one
+    pub next_index: Option<u64>,
+}
+
+/// Contract state as deployed before per-token smoothers were stored
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DynamicNFTV1 {
+    pub owner_id: AccountId,
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<String>>,
+    pub tokens_by_id: UnorderedMap<String, TokenV1>,
+    pub token_metadata_by_id: UnorderedMap<String, TokenMetadata>,
+    pub metadata: LazyOption<NFTContractMetadata>,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct DynamicNFT {
@@ -84,6 +174,7 @@ pub struct DynamicNFT {
     pub tokens_by_id: UnorderedMap<String, Token>,
     pub token_metadata_by_id: UnorderedMap<String, TokenMetadata>,
     pub metadata: LazyOption<NFTContractMetadata>,
+    pub smoothers_by_id: LookupMap<String, EmotionSmoother>,
 }
 
 #[near_bindgen]
@@ -96,10 +187,58 @@ impl DynamicNFT {
             tokens_per_owner: LookupMap::new(b"t"),
             tokens_by_id: UnorderedMap::new(b"i"),
             token_metadata_by_id: UnorderedMap::new(b"m"),
+            smoothers_by_id: LookupMap::new(b"s"),
             metadata: LazyOption::new(b"d", Some(&metadata)),
         }
     }
 
+    /// Upgrade state written by `DynamicNFTV1`
+    ///
+    /// Every token is rewritten in the current layout, starting its history
+    /// from the stored state, and gets a smoother primed with that state.
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let mut old: DynamicNFTV1 =
+            env::state_read().unwrap_or_else(|| env::panic_str("No contract state to migrate"));
+        let legacy: Vec<(String, TokenV1)> = old.tokens_by_id.iter().collect();
+        old.tokens_by_id.clear();
+        let mut tokens_by_id = UnorderedMap::new(b"i");
+        let mut smoothers_by_id = LookupMap::new(b"s");
+
+        for (token_id, token) in legacy {
+            let dynamic = token.dynamic_metadata;
+            let (smoother, category) =
+                primed_smoother(&dynamic.emotional_state, dynamic.last_interaction);
+            let upgraded = Token {
+                owner_id: token.owner_id,
+                approved_account_ids: token.approved_account_ids,
+                next_approval_id: token.next_approval_id,
+                metadata: token.metadata,
+                dynamic_metadata: DynamicMetadata {
+                    emotion_history: vec![dynamic.emotional_state.clone()],
+                    emotional_state: dynamic.emotional_state,
+                    interaction_count: dynamic.interaction_count,
+                    last_interaction: dynamic.last_interaction,
+                    complexity_score: dynamic.complexity_score,
+                    ipfs_history: dynamic.ipfs_history,
+                    emotional_category: category,
+                },
+            };
+            tokens_by_id.insert(&token_id, &upgraded);
+            smoothers_by_id.insert(&token_id, &smoother);
+        }
+
+        Self {
+            owner_id: old.owner_id,
+            tokens_per_owner: old.tokens_per_owner,
+            tokens_by_id,
+            token_metadata_by_id: old.token_metadata_by_id,
+            metadata: old.metadata,
+            smoothers_by_id,
+        }
+    }
+
     /// Mint new NFT with initial emotional state
     /// IPFS CID should be passed in metadata.reference
     #[payable]
@@ -110,18 +249,27 @@ impl DynamicNFT {
         token_metadata: TokenMetadata,
         initial_emotion: EmotionalState,
     ) -> Token {
+        let initial_emotion = initial_emotion
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid emotional state: {}", e)));
+
         // Validate deposit for storage
         let initial_storage = env::storage_usage();
 
+        // Prime the smoother so later updates ease in from the initial state
+        let (smoother, category) = primed_smoother(&initial_emotion, env::block_timestamp());
+
         // Create dynamic metadata
         let dynamic_metadata = DynamicMetadata {
-            emotional_state: initial_emotion,
+            emotional_state: initial_emotion.clone(),
             interaction_count: 0,
             last_interaction: env::block_timestamp(),
             complexity_score: 0.5,
             ipfs_history: vec![
                 token_metadata.reference.clone().unwrap_or_default()
             ],
+            emotion_history: vec![initial_emotion],
+            emotional_category: category,
         };
 
         // Create token
@@ -139,6 +287,8 @@ impl DynamicNFT {
             "Token already exists"
         );
 
+        self.smoothers_by_id.insert(&token_id, &smoother);
+
         // Update owner's token set
         let mut owner_tokens = self
             .tokens_per_owner
@@ -147,32 +297,26 @@ impl DynamicNFT {
         owner_tokens.insert(&token_id);
         self.tokens_per_owner.insert(&receiver_id, &owner_tokens);
 
-        // Refund excess storage deposit
-        let storage_used = env::storage_usage() - initial_storage;
-        let required_deposit = storage_used as u128 * env::storage_byte_cost();
-        let attached = env::attached_deposit();
-        
-        assert!(
-            attached >= required_deposit,
-            "Not enough deposit for storage"
-        );
-
-        if attached > required_deposit {
-            Promise::new(env::predecessor_account_id())
-                .transfer(attached - required_deposit);
-        }
+        charge_storage(initial_storage);
 
         token
     }
 
     /// Update emotional state and generate new IPFS metadata
     /// This is the "dynamic" part - NFT metadata changes based on interaction
+    ///
+    /// The caller pays for storage the update adds (history, IPFS CIDs and
+    /// the token's smoother); any excess deposit is refunded.
+    #[payable]
     pub fn update_emotional_state(
         &mut self,
         token_id: String,
         new_emotion: EmotionalState,
         new_ipfs_cid: Option<String>,
     ) {
+        let new_emotion = new_emotion
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid emotional state: {}", e)));
         let mut token = self.tokens_by_id.get(&token_id).expect("Token not found");
 
         // Only owner can update
@@ -181,8 +325,21 @@ impl DynamicNFT {
             token.owner_id,
             "Only owner can update emotional state"
         );
+        let initial_storage = env::storage_usage();
 
-        // Update emotional state
+        // Smooth the new state so noisy input doesn't jump the visuals
+        let mut smoother = self.smoother(&token_id, &token);
+        let smoothed = smoother.apply(&Emotion::from(new_emotion), env::block_timestamp());
+        self.smoothers_by_id.insert(&token_id, &smoother);
+        let new_emotion = EmotionalState::from(smoothed.emotion.clone());
+        token.dynamic_metadata.emotional_category = smoothed.label().to_string();
+
+        // Update emotional state, keeping a bounded history
+        let history = &mut token.dynamic_metadata.emotion_history;
+        history.push(new_emotion.clone());
+        if history.len() > EMOTION_HISTORY_LEN {
+            history.drain(..history.len() - EMOTION_HISTORY_LEN);
+        }
         token.dynamic_metadata.emotional_state = new_emotion;
         token.dynamic_metadata.interaction_count += 1;
         token.dynamic_metadata.last_interaction = env::block_timestamp();
@@ -195,6 +352,20 @@ impl DynamicNFT {
         }
 
         self.tokens_by_id.insert(&token_id, &token);
+        charge_storage(initial_storage);
+    }
+
+    /// Configure smoothing and category hysteresis for a token (owner only)
+    pub fn set_smoothing_config(&mut self, token_id: String, config: SmoothingConfig) {
+        let token = self.tokens_by_id.get(&token_id).expect("Token not found");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token.owner_id,
+            "Only owner can configure smoothing"
+        );
+        let mut smoother = self.smoother(&token_id, &token);
+        smoother.set_config(config);
+        self.smoothers_by_id.insert(&token_id, &smoother);
     }
 
     /// Calculate visual parameters from emotional state
@@ -226,6 +397,43 @@ impl DynamicNFT {
         token.dynamic_metadata
     }
 
+    /// Score `limit` tokens starting at `from_index` by how closely their
+    /// emotional state and history match the query, comparing trajectories
+    /// with dynamic time warping
+    ///
+    /// Matches are ranked within the scanned slice only, so gas stays
+    /// bounded as supply grows. Pass `next_index` back to scan the next
+    /// slice; a global ranking means merging the slices client-side.
+    pub fn scan_similar_tokens(
+        &self,
+        query: SimilarityQuery,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> SimilarityScan {
+        assert!(
+            query.trajectory.len() <= MAX_QUERY_TRAJECTORY,
+            "Query trajectory is too long"
+        );
+        let start = u64::try_from(from_index.unwrap_or(U128(0)).0)
+            .unwrap_or_else(|_| env::panic_str("from_index is out of range"));
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        let token_ids = self.tokens_by_id.keys_as_vector();
+        let tokens = self.tokens_by_id.values_as_vector();
+        let end = start.saturating_add(limit).min(token_ids.len());
+
+        let candidates = (start..end).filter_map(|index| {
+            let dynamic = tokens.get(index)?.dynamic_metadata;
+            let history = dynamic.emotion_history.into_iter().map(Emotion::from).collect();
+            Some((token_ids.get(index)?, Emotion::from(dynamic.emotional_state), history))
+        });
+        let page = emotional_similarity::rank(&query, candidates, 0, limit);
+        SimilarityScan {
+            matches: page.matches,
+            scanned: page.total,
+            next_index: (end < token_ids.len()).then_some(end),
+        }
+    }
+
     /// NEP-177: Get contract metadata
     pub fn nft_metadata(&self) -> NFTContractMetadata {
         self.metadata.get().unwrap()
@@ -267,10 +475,46 @@ impl DynamicNFT {
     }
 }
 
+impl DynamicNFT {
+    /// The token's stored smoother, or one primed with its current state
+    fn smoother(&self, token_id: &String, token: &Token) -> EmotionSmoother {
+        self.smoothers_by_id.get(token_id).unwrap_or_else(|| {
+            let dynamic = &token.dynamic_metadata;
+            primed_smoother(&dynamic.emotional_state, dynamic.last_interaction).0
+        })
+    }
+}
+
+/// A smoother that has already seen `state`, with the category it assigns
+fn primed_smoother(state: &EmotionalState, now: u64) -> (EmotionSmoother, String) {
+    let mut smoother = EmotionSmoother::default();
+    let smoothed = smoother.apply(&Emotion::from(state.clone()), now);
+    (smoother, smoothed.label().to_string())
+}
+
+/// Require a deposit covering storage added since `initial_storage`,
+/// refunding the rest to the caller
+fn charge_storage(initial_storage: u64) {
+    let storage_used = env::storage_usage().saturating_sub(initial_storage);
+    let required_deposit = storage_used as u128 * env::storage_byte_cost();
+    let attached = env::attached_deposit();
+
+    assert!(
+        attached >= required_deposit,
+        "Not enough deposit for storage"
+    );
+
+    if attached > required_deposit {
+        Promise::new(env::predecessor_account_id())
+            .transfer(attached - required_deposit);
+    }
+}
+
 // Unit tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::emotion_smoothing::SmoothingMethod;
     use near_sdk::test_utils::{accounts, VMContextBuilder};
     use near_sdk::testing_env;
 
@@ -283,12 +527,8 @@ mod tests {
         builder
     }
 
-    #[test]
-    fn test_mint_and_update() {
-        let mut context = get_context(accounts(0));
-        testing_env!(context.build());
-
-        let metadata = NFTContractMetadata {
+    fn contract_metadata() -> NFTContractMetadata {
+        NFTContractMetadata {
             spec: "nft-1.0.0".to_string(),
             name: "Dynamic Emotion NFT".to_string(),
             symbol: "DYNFT".to_string(),
@@ -296,24 +536,32 @@ mod tests {
             base_uri: Some("ipfs://".to_string()),
             reference: None,
             reference_hash: None,
-        };
-
-        let mut contract = DynamicNFT::new(accounts(0), metadata);
+        }
+    }
 
-        let token_metadata = TokenMetadata {
-            title: Some("Test NFT".to_string()),
-            description: Some("A dynamic test NFT".to_string()),
-            media: Some("ipfs://QmTest123".to_string()),
+    fn token_metadata(title: &str) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: None,
             media_hash: None,
             copies: Some(1),
-            issued_at: Some(1234567890),
+            issued_at: None,
             expires_at: None,
             starts_at: None,
             updated_at: None,
             extra: None,
-            reference: Some("ipfs://QmTestMetadata".to_string()),
+            reference: Some(format!("ipfs://{}", title)),
             reference_hash: None,
-        };
+        }
+    }
+
+    #[test]
+    fn test_mint_and_update() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = DynamicNFT::new(accounts(0), contract_metadata());
 
         let emotion = EmotionalState {
             valence: 0.5,
@@ -326,9 +574,213 @@ mod tests {
         context.attached_deposit(10_000_000_000_000_000_000_000); // 0.01 NEAR
         testing_env!(context.build());
 
-        contract.nft_mint("token1".to_string(), accounts(1), token_metadata, emotion);
+        contract.nft_mint("token1".to_string(), accounts(1), token_metadata("Test NFT"), emotion);
 
         let dynamic_meta = contract.get_dynamic_metadata("token1".to_string());
         assert_eq!(dynamic_meta.emotional_state.valence, 0.5);
+
+        // Out-of-range values are clamped before they are stored; an hour
+        // later the smoother has fully settled on the new state
+        context.predecessor_account_id(accounts(1));
+        context.block_timestamp(3_600_000_000_000);
+        testing_env!(context.build());
+        let drifted = EmotionalState {
+            valence: 1.3,
+            arousal: -0.1,
+            dominance: 0.6,
+            confidence: 0.9,
+            timestamp: 1234567891,
+        };
+        contract.update_emotional_state("token1".to_string(), drifted, None);
+
+        let dynamic_meta = contract.get_dynamic_metadata("token1".to_string());
+        assert_eq!(dynamic_meta.emotional_state.valence, 1.0);
+        assert_eq!(dynamic_meta.emotional_state.arousal, 0.0);
+    }
+
+    #[test]
+    fn test_update_is_smoothed() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = DynamicNFT::new(accounts(0), contract_metadata());
+
+        let calm = EmotionalState {
+            valence: 0.0,
+            arousal: 0.5,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 0,
+        };
+        context.attached_deposit(10_000_000_000_000_000_000_000);
+        testing_env!(context.build());
+        contract.nft_mint("token1".to_string(), accounts(0), token_metadata("Smooth"), calm);
+        assert_eq!(
+            contract.get_dynamic_metadata("token1".to_string()).emotional_category,
+            "Neutral"
+        );
+
+        // One second later a spike only moves part of the way
+        context.block_timestamp(1_000_000_000);
+        testing_env!(context.build());
+        let spike = EmotionalState {
+            valence: 1.0,
+            arousal: 0.5,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 1,
+        };
+        contract.update_emotional_state("token1".to_string(), spike.clone(), None);
+        let valence = contract
+            .get_dynamic_metadata("token1".to_string())
+            .emotional_state
+            .valence;
+        assert!(valence > 0.5 && valence < 0.7);
+
+        // With smoothing off the next update applies as-is
+        let config = SmoothingConfig {
+            method: SmoothingMethod::Off,
+            ..Default::default()
+        };
+        contract.set_smoothing_config("token1".to_string(), config);
+        contract.update_emotional_state("token1".to_string(), spike, None);
+        let dynamic_meta = contract.get_dynamic_metadata("token1".to_string());
+        assert_eq!(dynamic_meta.emotional_state.valence, 1.0);
+    }
+
+    #[test]
+    fn test_scan_similar_tokens() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = DynamicNFT::new(accounts(0), contract_metadata());
+
+        context.attached_deposit(10_000_000_000_000_000_000_000);
+        testing_env!(context.build());
+        for (token_id, valence, arousal) in [("joy", 0.8, 0.8), ("calm", 0.4, 0.2), ("gloom", -0.7, 0.3)] {
+            let emotion = EmotionalState {
+                valence,
+                arousal,
+                dominance: 0.5,
+                confidence: 0.9,
+                timestamp: 0,
+            };
+            contract.nft_mint(token_id.to_string(), accounts(1), token_metadata(token_id), emotion);
+        }
+
+        // Each call ranks one slice of tokens; gloom sits in the second
+        let query = SimilarityQuery::new(0.7, 0.7, 0.5);
+        let page = contract.scan_similar_tokens(query.clone(), None, Some(2));
+        let ids: Vec<&str> = page.matches.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["joy", "calm"]);
+        assert_eq!(page.next_index, Some(2));
+
+        let rest = contract.scan_similar_tokens(query, Some(U128(2)), Some(2));
+        assert_eq!(rest.matches[0].id, "gloom");
+        assert_eq!(rest.scanned, 1);
+        assert_eq!(rest.next_index, None);
+    }
+
+    #[test]
+    fn test_migrate_rewrites_tokens_in_the_current_layout() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let happy = EmotionalState {
+            valence: 0.8,
+            arousal: 0.8,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 0,
+        };
+        let mut tokens_by_id = UnorderedMap::new(b"i");
+        tokens_by_id.insert(
+            &"old".to_string(),
+            &TokenV1 {
+                owner_id: accounts(1),
+                approved_account_ids: HashMap::new(),
+                next_approval_id: 0,
+                metadata: token_metadata("old"),
+                dynamic_metadata: DynamicMetadataV1 {
+                    emotional_state: happy,
+                    interaction_count: 3,
+                    last_interaction: 0,
+                    complexity_score: 0.5,
+                    ipfs_history: vec!["ipfs://old".to_string()],
+                },
+            },
+        );
+        env::state_write(&DynamicNFTV1 {
+            owner_id: accounts(0),
+            tokens_per_owner: LookupMap::new(b"t"),
+            tokens_by_id,
+            token_metadata_by_id: UnorderedMap::new(b"m"),
+            metadata: LazyOption::new(b"d", Some(&contract_metadata())),
+        });
+
+        let mut contract = DynamicNFT::migrate();
+        let dynamic_meta = contract.get_dynamic_metadata("old".to_string());
+        assert_eq!(dynamic_meta.interaction_count, 3);
+        assert_eq!(dynamic_meta.emotion_history.len(), 1);
+        assert!(!dynamic_meta.emotional_category.is_empty());
+        assert_eq!(contract.nft_total_supply(), U128(1));
+
+        // The smoother starts from the stored state, so a reversal one
+        // second later only moves part of the way
+        context.predecessor_account_id(accounts(1));
+        context.block_timestamp(1_000_000_000);
+        context.attached_deposit(10_000_000_000_000_000_000_000);
+        testing_env!(context.build());
+        let gloomy = EmotionalState {
+            valence: -0.8,
+            arousal: 0.8,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 1,
+        };
+        contract.update_emotional_state("old".to_string(), gloomy, None);
+        let dynamic_meta = contract.get_dynamic_metadata("old".to_string());
+        assert_eq!(dynamic_meta.emotion_history.len(), 2);
+        assert!(dynamic_meta.emotional_state.valence > 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough deposit for storage")]
+    fn test_update_requires_storage_deposit() {
+        let mut context = get_context(accounts(0));
+        context.attached_deposit(10_000_000_000_000_000_000_000);
+        testing_env!(context.build());
+
+        let mut contract = DynamicNFT::new(accounts(0), contract_metadata());
+        let calm = EmotionalState {
+            valence: 0.0,
+            arousal: 0.5,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 0,
+        };
+        contract.nft_mint("token1".to_string(), accounts(0), token_metadata("Paid"), calm.clone());
+
+        context.attached_deposit(0);
+        testing_env!(context.build());
+        contract.update_emotional_state("token1".to_string(), calm, Some("ipfs://next".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid emotional state: valence is not a finite number")]
+    fn test_update_rejects_nan() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = DynamicNFT::new(accounts(0), contract_metadata());
+
+        let emotion = EmotionalState {
+            valence: f32::NAN,
+            arousal: 0.5,
+            dominance: 0.5,
+            confidence: 0.9,
+            timestamp: 0,
+        };
+        contract.update_emotional_state("token1".to_string(), emotion, None);
     }
 }
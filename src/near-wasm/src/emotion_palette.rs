@@ -0,0 +1,231 @@
+//! Emotion palette - Harmonious color palettes from a VAD state
+//!
+//! Colors are laid out in OKLCH so equal steps look equally different:
+//! valence picks the base hue (cool to warm), arousal the chroma and
+//! dominance the lightness contrast. Colors outside sRGB are brought into
+//! gamut by reducing chroma rather than clipping channels.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use emotion_model::Emotion;
+
+/// Hue for the most negative valence (blue)
+const COOL_HUE: f32 = 260.0;
+/// Hue for the most positive valence (orange)
+const WARM_HUE: f32 = 40.0;
+/// Chroma range from calm to excited
+const CHROMA_RANGE: (f32, f32) = (0.03, 0.22);
+/// Lightness the palette is centred on
+const MID_LIGHTNESS: f32 = 0.62;
+/// Lightness spread from submissive (low contrast) to dominant (high contrast)
+const CONTRAST_RANGE: (f32, f32) = (0.15, 0.7);
+/// Bisection steps when reducing chroma into the sRGB gamut
+const GAMUT_STEPS: usize = 16;
+
+/// How hues are spread across the palette
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Harmony {
+    /// Neighbouring hues within 60 degrees
+    Analogous,
+    /// Base hue and its opposite
+    Complementary,
+    /// Three hues 120 degrees apart
+    Triadic,
+}
+
+/// Color in OKLCH: lightness 0..1, chroma 0..~0.37, hue in degrees
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+/// Generated palette, darkest first
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmotionPalette {
+    pub colors: Vec<Oklch>,
+}
+
+impl Oklch {
+    /// Convert to 8-bit sRGB, reducing chroma until the color fits
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        let mut rgb = self.to_linear_srgb();
+        if !in_gamut(rgb) {
+            let (mut lo, mut hi) = (0.0, self.c);
+            for _ in 0..GAMUT_STEPS {
+                let mid = (lo + hi) / 2.0;
+                if in_gamut(Oklch { c: mid, ..*self }.to_linear_srgb()) {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            rgb = Oklch { c: lo, ..*self }.to_linear_srgb();
+        }
+        rgb.map(|channel| (encode_srgb(channel.clamp(0.0, 1.0)) * 255.0).round() as u8)
+    }
+
+    /// Packed `0xRRGGBB`, as used by `FractalParams::color_palette`
+    pub fn to_u32(&self) -> u32 {
+        let [r, g, b] = self.to_rgb8();
+        (r as u32) << 16 | (g as u32) << 8 | b as u32
+    }
+
+    fn to_linear_srgb(self) -> [f32; 3] {
+        let (sin, cos) = self.h.to_radians().sin_cos();
+        let (a, b) = (self.c * cos, self.c * sin);
+        let l = (self.l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m = (self.l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s = (self.l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        [
+            4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+            -1.268_438 * l + 2.609_757_4 * m - 0.341_319_4 * s,
+            -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+        ]
+    }
+}
+
+impl EmotionPalette {
+    /// Palette of `size` colors for the emotion
+    pub fn generate(emotion: &Emotion, size: usize, harmony: Harmony) -> Self {
+        let emotion = emotion.clone().clamped();
+        let positivity = (emotion.valence + 1.0) / 2.0;
+        let base_hue = COOL_HUE + (WARM_HUE - COOL_HUE) * positivity;
+        let chroma = lerp(CHROMA_RANGE, emotion.arousal);
+        let contrast = lerp(CONTRAST_RANGE, emotion.dominance);
+        let darkest = (MID_LIGHTNESS - contrast / 2.0).max(0.05);
+        let lightest = (MID_LIGHTNESS + contrast / 2.0).min(0.98);
+
+        let colors = (0..size)
+            .map(|i| {
+                let t = if size > 1 {
+                    i as f32 / (size - 1) as f32
+                } else {
+                    0.5
+                };
+                let hue = base_hue + harmony.offset(i, size);
+                // Extremes of lightness hold less chroma, so taper it
+                let taper = 1.0 - (2.0 * t - 1.0).powi(2) * 0.5;
+                Oklch {
+                    l: darkest + (lightest - darkest) * t,
+                    c: chroma * taper,
+                    h: hue.rem_euclid(360.0),
+                }
+            })
+            .collect();
+        Self { colors }
+    }
+
+    pub fn to_rgb8(&self) -> Vec<[u8; 3]> {
+        self.colors.iter().map(Oklch::to_rgb8).collect()
+    }
+
+    pub fn to_u32(&self) -> Vec<u32> {
+        self.colors.iter().map(Oklch::to_u32).collect()
+    }
+}
+
+impl Harmony {
+    /// Hue offset in degrees for the `index`th of `size` colors
+    fn offset(self, index: usize, size: usize) -> f32 {
+        match self {
+            Harmony::Analogous if size > 1 => -30.0 + 60.0 * index as f32 / (size - 1) as f32,
+            Harmony::Analogous => 0.0,
+            Harmony::Complementary => (index % 2) as f32 * 180.0,
+            Harmony::Triadic => (index % 3) as f32 * 120.0,
+        }
+    }
+}
+
+fn lerp((min, max): (f32, f32), t: f32) -> f32 {
+    min + (max - min) * t
+}
+
+fn in_gamut(rgb: [f32; 3]) -> bool {
+    const EPSILON: f32 = 1e-4;
+    rgb.iter().all(|&c| (-EPSILON..=1.0 + EPSILON).contains(&c))
+}
+
+fn encode_srgb(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spread(rgb: [u8; 3]) -> i32 {
+        *rgb.iter().max().unwrap() as i32 - *rgb.iter().min().unwrap() as i32
+    }
+
+    #[test]
+    fn test_known_colors() {
+        let white = Oklch {
+            l: 1.0,
+            c: 0.0,
+            h: 0.0,
+        };
+        let black = Oklch {
+            l: 0.0,
+            c: 0.0,
+            h: 0.0,
+        };
+        assert_eq!(white.to_rgb8(), [255, 255, 255]);
+        assert_eq!(black.to_u32(), 0x000000);
+        assert_eq!(white.to_u32(), 0xFFFFFF);
+    }
+
+    #[test]
+    fn test_valence_sets_warmth() {
+        let warm = EmotionPalette::generate(&Emotion::new(0.9, 0.6, 0.5), 5, Harmony::Analogous);
+        let cool = EmotionPalette::generate(&Emotion::new(-0.9, 0.6, 0.5), 5, Harmony::Analogous);
+        let [wr, _, wb] = warm.to_rgb8()[2];
+        let [cr, _, cb] = cool.to_rgb8()[2];
+        assert!(wr > wb);
+        assert!(cb > cr);
+    }
+
+    #[test]
+    fn test_arousal_and_dominance() {
+        let calm = EmotionPalette::generate(&Emotion::new(0.3, 0.1, 0.5), 5, Harmony::Analogous);
+        let excited = EmotionPalette::generate(&Emotion::new(0.3, 0.9, 0.5), 5, Harmony::Analogous);
+        assert!(spread(excited.to_rgb8()[2]) > spread(calm.to_rgb8()[2]));
+
+        let meek = EmotionPalette::generate(&Emotion::new(0.3, 0.5, 0.1), 5, Harmony::Analogous);
+        let bold = EmotionPalette::generate(&Emotion::new(0.3, 0.5, 0.9), 5, Harmony::Analogous);
+        let contrast = |p: &EmotionPalette| p.colors[4].l - p.colors[0].l;
+        assert!(contrast(&bold) > contrast(&meek));
+    }
+
+    #[test]
+    fn test_extreme_inputs_stay_in_gamut() {
+        for emotion in [
+            Emotion::new(1.0, 1.0, 1.0),
+            Emotion::new(-1.0, 1.0, 0.0),
+            Emotion::new(5.0, -3.0, f32::NAN),
+        ] {
+            for harmony in [Harmony::Analogous, Harmony::Complementary, Harmony::Triadic] {
+                let palette = EmotionPalette::generate(&emotion, 7, harmony);
+                assert_eq!(palette.to_u32().len(), 7);
+                assert!(palette.colors.windows(2).all(|w| w[0].l <= w[1].l));
+                for color in &palette.colors {
+                    assert!(color.l.is_finite() && color.c.is_finite() && color.h.is_finite());
+                    assert!(color.to_u32() <= 0xFFFFFF);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Time-series prediction over emotional trajectories
+//!
+//! Exponential smoothing, Holt linear trend and Kalman filtering for VAD
+//! trajectories sampled at irregular timestamps, with confidence intervals.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Timestamp;
+
+use crate::emotional::EmotionalVector;
+
+/// z-score of the two-sided 95% interval
+const Z_95: f64 = 1.96;
+/// Residual spread assumed before any one-step errors are observed
+const DEFAULT_SIGMA: f64 = 0.1;
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+
+/// Forecasting method and its parameters
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PredictionMethod {
+    /// Level only; `alpha` is the weight of a new sample at the typical interval
+    ExponentialSmoothing { alpha: f32 },
+    /// Level and trend (Holt's linear method)
+    HoltLinear { alpha: f32, beta: f32 },
+    /// Constant-velocity Kalman filter; noise values are variances
+    Kalman {
+        process_noise: f32,
+        measurement_noise: f32,
+    },
+}
+
+/// Forecast for one VAD dimension
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DimensionForecast {
+    pub value: f32,
+    pub lower: f32,
+    pub upper: f32,
+    pub std_dev: f32,
+}
+
+/// Forecast of the full emotional state at a future timestamp
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmotionForecast {
+    pub method: PredictionMethod,
+    pub timestamp: Timestamp,
+    pub valence: DimensionForecast,
+    pub arousal: DimensionForecast,
+    pub dominance: DimensionForecast,
+    /// 1.0 for a point forecast, falling towards 0.0 as intervals widen
+    pub confidence: f32,
+}
+
+impl Default for PredictionMethod {
+    fn default() -> Self {
+        PredictionMethod::HoltLinear {
+            alpha: 0.5,
+            beta: 0.3,
+        }
+    }
+}
+
+impl EmotionForecast {
+    pub fn to_vector(&self) -> EmotionalVector {
+        EmotionalVector {
+            valence: self.valence.value,
+            arousal: self.arousal.value,
+            dominance: self.dominance.value,
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// Median spacing between consecutive samples, in nanoseconds
+pub fn typical_interval(trajectory: &[EmotionalVector]) -> Option<u64> {
+    let mut intervals: Vec<u64> = trajectory
+        .windows(2)
+        .map(|pair| pair[1].timestamp.saturating_sub(pair[0].timestamp))
+        .filter(|dt| *dt > 0)
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    intervals.sort_unstable();
+    Some(intervals[intervals.len() / 2])
+}
+
+/// Forecast the trajectory at `target` using the given method
+///
+/// Samples must be ordered by timestamp; samples sharing a timestamp are
+/// treated as repeated measurements.
+pub fn forecast(
+    trajectory: &[EmotionalVector],
+    method: &PredictionMethod,
+    target: Timestamp,
+) -> Option<EmotionForecast> {
+    let last = trajectory.last()?;
+    let origin = trajectory[0].timestamp;
+    let times: Vec<f64> = trajectory
+        .iter()
+        .map(|e| e.timestamp.saturating_sub(origin) as f64 / NANOS_PER_SECOND)
+        .collect();
+    let reference = typical_interval(trajectory).map_or(1.0, |dt| dt as f64 / NANOS_PER_SECOND);
+    let horizon = target.saturating_sub(last.timestamp) as f64 / NANOS_PER_SECOND;
+
+    let dimension = |values: Vec<f64>, min: f32, max: f32| {
+        let (value, std_dev) = forecast_series(&times, &values, method, reference, horizon);
+        let clamp = |v: f64| (v as f32).clamp(min, max);
+        DimensionForecast {
+            value: clamp(value),
+            lower: clamp(value - Z_95 * std_dev),
+            upper: clamp(value + Z_95 * std_dev),
+            std_dev: std_dev as f32,
+        }
+    };
+
+    let valence = dimension(
+        trajectory.iter().map(|e| e.valence as f64).collect(),
+        -1.0,
+        1.0,
+    );
+    let arousal = dimension(
+        trajectory.iter().map(|e| e.arousal as f64).collect(),
+        0.0,
+        1.0,
+    );
+    let dominance = dimension(
+        trajectory.iter().map(|e| e.dominance as f64).collect(),
+        0.0,
+        1.0,
+    );
+
+    // Unclamped interval widths relative to each dimension's range
+    let relative_width =
+        2.0 * Z_95 as f32 * (valence.std_dev / 2.0 + arousal.std_dev + dominance.std_dev) / 3.0;
+
+    Some(EmotionForecast {
+        method: method.clone(),
+        timestamp: target,
+        valence,
+        arousal,
+        dominance,
+        confidence: (1.0 - relative_width).clamp(0.0, 1.0),
+    })
+}
+
+/// Forecast one series `horizon` seconds after its last sample, returning (value, std dev)
+fn forecast_series(
+    times: &[f64],
+    values: &[f64],
+    method: &PredictionMethod,
+    reference: f64,
+    horizon: f64,
+) -> (f64, f64) {
+    let steps = horizon / reference;
+    match method {
+        PredictionMethod::ExponentialSmoothing { alpha } => {
+            let alpha = (*alpha as f64).clamp(0.0, 1.0);
+            let mut level = values[0];
+            let mut errors = Vec::new();
+            for i in 1..values.len() {
+                let a = interval_weight(alpha, times[i] - times[i - 1], reference);
+                let error = values[i] - level;
+                errors.push(error);
+                level += a * error;
+            }
+            let sigma = residual_sigma(&errors);
+            (level, sigma * (1.0 + steps * alpha * alpha).sqrt())
+        }
+        PredictionMethod::HoltLinear { alpha, beta } => {
+            let alpha = (*alpha as f64).clamp(0.0, 1.0);
+            let beta = (*beta as f64).clamp(0.0, 1.0);
+            if values.len() == 1 {
+                return (values[0], DEFAULT_SIGMA * (1.0 + steps).sqrt());
+            }
+
+            // Initialise from the first interval with a non-zero duration
+            let first_dt = (times[1] - times[0]).max(f64::EPSILON);
+            let mut level = values[1];
+            let mut trend = if times[1] > times[0] {
+                (values[1] - values[0]) / first_dt
+            } else {
+                0.0
+            };
+            let mut errors = Vec::new();
+            for i in 2..values.len() {
+                let dt = times[i] - times[i - 1];
+                let predicted = level + trend * dt;
+                let error = values[i] - predicted;
+                errors.push(error);
+                let a = interval_weight(alpha, dt, reference);
+                level = predicted + a * error;
+                if dt > 0.0 {
+                    trend += beta * a * error / dt.max(reference);
+                }
+            }
+
+            let sigma = residual_sigma(&errors);
+            // h-step variance: sigma^2 * (1 + sum_j (alpha + j*alpha*beta)^2)
+            let whole_steps = steps.ceil().min(1_000.0) as usize;
+            let spread: f64 = (1..whole_steps)
+                .map(|j| (alpha + j as f64 * alpha * beta).powi(2))
+                .sum();
+            (level + trend * horizon, sigma * (1.0 + spread).sqrt())
+        }
+        PredictionMethod::Kalman {
+            process_noise,
+            measurement_noise,
+        } => {
+            let q = (*process_noise as f64).max(0.0);
+            let r = (*measurement_noise as f64).max(1e-9);
+            // State [position, velocity] with covariance p
+            let (mut x, mut v) = (values[0], 0.0);
+            let mut p = [[r, 0.0], [0.0, 1.0 / (reference * reference)]];
+
+            for i in 1..values.len() {
+                let dt = times[i] - times[i - 1];
+                predict_kalman(&mut x, &mut v, &mut p, dt, q);
+
+                let s = p[0][0] + r;
+                let (k0, k1) = (p[0][0] / s, p[1][0] / s);
+                let innovation = values[i] - x;
+                x += k0 * innovation;
+                v += k1 * innovation;
+                p = [
+                    [(1.0 - k0) * p[0][0], (1.0 - k0) * p[0][1]],
+                    [p[1][0] - k1 * p[0][0], p[1][1] - k1 * p[0][1]],
+                ];
+            }
+
+            predict_kalman(&mut x, &mut v, &mut p, horizon, q);
+            (x, (p[0][0] + r).max(0.0).sqrt())
+        }
+    }
+}
+
+/// Smoothing weight for an interval `dt`, given the weight at the reference interval
+fn interval_weight(weight: f64, dt: f64, reference: f64) -> f64 {
+    if dt <= 0.0 {
+        return weight;
+    }
+    1.0 - (1.0 - weight).powf(dt / reference)
+}
+
+fn residual_sigma(errors: &[f64]) -> f64 {
+    if errors.is_empty() {
+        return DEFAULT_SIGMA;
+    }
+    (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt()
+}
+
+fn predict_kalman(x: &mut f64, v: &mut f64, p: &mut [[f64; 2]; 2], dt: f64, q: f64) {
+    *x += *v * dt;
+    let p00 = p[0][0] + dt * (p[1][0] + p[0][1]) + dt * dt * p[1][1] + q * dt.powi(3) / 3.0;
+    let p01 = p[0][1] + dt * p[1][1] + q * dt * dt / 2.0;
+    let p11 = p[1][1] + q * dt;
+    *p = [[p00, p01], [p01, p11]];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn sample(valence: f32, arousal: f32, dominance: f32, timestamp: u64) -> EmotionalVector {
+        EmotionalVector {
+            valence,
+            arousal,
+            dominance,
+            timestamp,
+        }
+    }
+
+    /// Linear arousal ramp sampled at irregular times with a small deterministic wobble
+    fn irregular_ramp() -> Vec<EmotionalVector> {
+        let offsets = [0, 1, 3, 4, 7, 8, 12, 13, 14, 18];
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let wobble = if i % 2 == 0 { 0.005 } else { -0.005 };
+                sample(0.0, 0.1 + 0.04 * *t as f32 + wobble, 0.5, t * SECOND)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_typical_interval_is_median() {
+        assert_eq!(typical_interval(&irregular_ramp()), Some(SECOND));
+        assert_eq!(typical_interval(&irregular_ramp()[..1]), None);
+    }
+
+    #[test]
+    fn test_trend_methods_follow_irregular_ramp() {
+        let trajectory = irregular_ramp();
+        let target = 20 * SECOND;
+        let expected = 0.1 + 0.04 * 20.0;
+
+        let holt = forecast(&trajectory, &PredictionMethod::default(), target).unwrap();
+        assert!(
+            (holt.arousal.value - expected).abs() < 0.05,
+            "holt {}",
+            holt.arousal.value
+        );
+
+        let kalman = PredictionMethod::Kalman {
+            process_noise: 0.001,
+            measurement_noise: 0.0001,
+        };
+        let kalman = forecast(&trajectory, &kalman, target).unwrap();
+        assert!(
+            (kalman.arousal.value - expected).abs() < 0.05,
+            "kalman {}",
+            kalman.arousal.value
+        );
+        assert!(
+            kalman.arousal.lower <= kalman.arousal.value
+                && kalman.arousal.value <= kalman.arousal.upper
+        );
+    }
+
+    #[test]
+    fn test_exponential_smoothing_tracks_level() {
+        let trajectory: Vec<EmotionalVector> = (0..20)
+            .map(|i| sample(if i % 2 == 0 { 0.45 } else { 0.55 }, 0.5, 0.5, i * SECOND))
+            .collect();
+        let method = PredictionMethod::ExponentialSmoothing { alpha: 0.2 };
+        let result = forecast(&trajectory, &method, 25 * SECOND).unwrap();
+
+        assert!((result.valence.value - 0.5).abs() < 0.05);
+        assert!(result.valence.lower < 0.45 && result.valence.upper > 0.55);
+        assert_eq!(result.timestamp, 25 * SECOND);
+    }
+
+    #[test]
+    fn test_intervals_widen_with_horizon() {
+        let trajectory = irregular_ramp();
+        let method = PredictionMethod::default();
+        let near = forecast(&trajectory, &method, 19 * SECOND).unwrap();
+        let far = forecast(&trajectory, &method, 40 * SECOND).unwrap();
+        assert!(far.arousal.std_dev > near.arousal.std_dev);
+        assert!(far.confidence <= near.confidence);
+    }
+
+    #[test]
+    fn test_forecast_clamps_to_valid_range() {
+        let trajectory = vec![sample(0.5, 0.8, 0.5, 0), sample(0.9, 0.95, 0.5, SECOND)];
+        let result = forecast(&trajectory, &PredictionMethod::default(), 10 * SECOND).unwrap();
+        assert_eq!(result.valence.value, 1.0);
+        assert_eq!(result.arousal.value, 1.0);
+        assert!(forecast(&[], &PredictionMethod::default(), SECOND).is_none());
+    }
+}
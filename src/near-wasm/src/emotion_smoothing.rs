@@ -0,0 +1,325 @@
+//! Emotion smoothing - Filter noisy emotional input before it drives visuals
+//!
+//! Each VAD dimension is filtered with either an attack/release EMA or a
+//! one-euro filter, both adapting to the time between samples. The emotion
+//! category only changes once a new category is clearly closer than the
+//! current one and has held for a minimum dwell time.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Timestamp;
+
+use emotion_model::Emotion;
+
+use crate::emotion_taxonomy::{self, BasicEmotion};
+
+const NANOS_PER_SECOND: f32 = 1_000_000_000.0;
+/// Samples in the same block are treated as this far apart, in seconds
+const MIN_STEP_SECONDS: f32 = 0.001;
+/// Neutral point each dimension decays towards on release
+const NEUTRAL: [f32; 3] = [0.0, 0.5, 0.5];
+
+/// Filter applied to each VAD dimension
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SmoothingMethod {
+    /// Apply input as-is
+    Off,
+    /// Exponential moving average with separate time constants for moving
+    /// away from neutral (attack) and back towards it (release)
+    AttackRelease { attack_ns: u64, release_ns: u64 },
+    /// One-euro filter: heavy smoothing when still, little lag when moving fast
+    OneEuro {
+        min_cutoff_hz: f32,
+        beta: f32,
+        derivative_cutoff_hz: f32,
+    },
+}
+
+/// Smoothing and category hysteresis settings
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SmoothingConfig {
+    pub method: SmoothingMethod,
+    /// How much closer a new category's prototype must be before switching
+    pub hysteresis: f32,
+    /// How long a new category must hold before switching
+    pub min_dwell_ns: u64,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            method: SmoothingMethod::AttackRelease {
+                attack_ns: 1_000_000_000,
+                release_ns: 5_000_000_000,
+            },
+            hysteresis: 0.05,
+            min_dwell_ns: 0,
+        }
+    }
+}
+
+/// Filter output for one sample
+#[derive(Clone, Debug)]
+pub struct SmoothedEmotion {
+    pub emotion: Emotion,
+    pub category: BasicEmotion,
+    /// Whether this sample switched the category
+    pub category_changed: bool,
+}
+
+impl SmoothedEmotion {
+    /// Intensity-specific name of the category at the smoothed state
+    pub fn label(&self) -> &'static str {
+        let e = &self.emotion;
+        let intensity = self
+            .category
+            .intensity_at(e.valence, e.arousal, e.dominance);
+        self.category.label(intensity)
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct FilterState {
+    timestamp: Timestamp,
+    value: [f32; 3],
+    derivative: [f32; 3],
+}
+
+/// Stateful smoother for one emotional stream
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmotionSmoother {
+    config: SmoothingConfig,
+    state: Option<FilterState>,
+    category: Option<BasicEmotion>,
+    /// Candidate category and when it first qualified
+    pending: Option<(BasicEmotion, Timestamp)>,
+}
+
+impl EmotionSmoother {
+    pub fn new(config: SmoothingConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &SmoothingConfig {
+        &self.config
+    }
+
+    /// Change settings, keeping the current filter state
+    pub fn set_config(&mut self, config: SmoothingConfig) {
+        self.config = config;
+    }
+
+    /// Current category, once a sample has been applied
+    pub fn category(&self) -> Option<BasicEmotion> {
+        self.category
+    }
+
+    /// Forget all history; the next sample passes through unfiltered
+    pub fn reset(&mut self) {
+        self.state = None;
+        self.category = None;
+        self.pending = None;
+    }
+
+    /// Filter one sample taken at `now`
+    pub fn apply(&mut self, emotion: &Emotion, now: Timestamp) -> SmoothedEmotion {
+        let input = [emotion.valence, emotion.arousal, emotion.dominance];
+        let state = match &self.state {
+            Some(prev) if now >= prev.timestamp => self.filter(prev, input, now),
+            // First sample, or out of order: restart from this one
+            _ => FilterState {
+                timestamp: now,
+                value: input,
+                derivative: [0.0; 3],
+            },
+        };
+        let [valence, arousal, dominance] = state.value;
+        self.state = Some(state);
+
+        let smoothed = Emotion {
+            valence,
+            arousal,
+            dominance,
+            ..emotion.clone()
+        };
+        let (category, category_changed) = self.update_category(&smoothed, now);
+        SmoothedEmotion {
+            emotion: smoothed,
+            category,
+            category_changed,
+        }
+    }
+
+    fn filter(&self, prev: &FilterState, input: [f32; 3], now: Timestamp) -> FilterState {
+        let dt = ((now - prev.timestamp) as f32 / NANOS_PER_SECOND).max(MIN_STEP_SECONDS);
+        let mut value = input;
+        let mut derivative = [0.0; 3];
+        for i in 0..3 {
+            let (x, last) = (input[i], prev.value[i]);
+            match &self.config.method {
+                SmoothingMethod::Off => {}
+                SmoothingMethod::AttackRelease {
+                    attack_ns,
+                    release_ns,
+                } => {
+                    let intensifying = (x - NEUTRAL[i]).abs() > (last - NEUTRAL[i]).abs();
+                    let tau_ns = if intensifying { attack_ns } else { release_ns };
+                    let alpha = ema_alpha(dt, *tau_ns as f32 / NANOS_PER_SECOND);
+                    value[i] = last + alpha * (x - last);
+                }
+                SmoothingMethod::OneEuro {
+                    min_cutoff_hz,
+                    beta,
+                    derivative_cutoff_hz,
+                } => {
+                    let raw_derivative = (x - last) / dt;
+                    let d = prev.derivative[i];
+                    derivative[i] =
+                        d + one_euro_alpha(dt, *derivative_cutoff_hz) * (raw_derivative - d);
+                    let cutoff = min_cutoff_hz + beta * derivative[i].abs();
+                    value[i] = last + one_euro_alpha(dt, cutoff) * (x - last);
+                }
+            }
+        }
+        FilterState {
+            timestamp: now,
+            value,
+            derivative,
+        }
+    }
+
+    /// Switch category only past the hysteresis margin and dwell time
+    fn update_category(&mut self, emotion: &Emotion, now: Timestamp) -> (BasicEmotion, bool) {
+        let (v, a, d) = (emotion.valence, emotion.arousal, emotion.dominance);
+        let nearest = emotion_taxonomy::classify(v, a, d);
+        let current = match self.category {
+            Some(current) => current,
+            None => {
+                self.category = Some(nearest.primary);
+                return (nearest.primary, true);
+            }
+        };
+        if nearest.primary == current {
+            self.pending = None;
+            return (current, false);
+        }
+
+        let margin = current.distance_to(v, a, d) - nearest.distance;
+        if margin < self.config.hysteresis {
+            self.pending = None;
+            return (current, false);
+        }
+        let since = match self.pending {
+            Some((candidate, since)) if candidate == nearest.primary => since,
+            _ => now,
+        };
+        if now - since >= self.config.min_dwell_ns {
+            self.category = Some(nearest.primary);
+            self.pending = None;
+            (nearest.primary, true)
+        } else {
+            self.pending = Some((nearest.primary, since));
+            (current, false)
+        }
+    }
+}
+
+/// EMA weight for a sample `dt` seconds after the last, time constant `tau`
+fn ema_alpha(dt: f32, tau: f32) -> f32 {
+    if tau <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-dt / tau).exp()
+    }
+}
+
+/// Low-pass weight for the one-euro filter at the given cutoff frequency
+fn one_euro_alpha(dt: f32, cutoff_hz: f32) -> f32 {
+    let tau = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz.max(f32::EPSILON));
+    1.0 / (1.0 + tau / dt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_attack_is_faster_than_release() {
+        let mut smoother = EmotionSmoother::new(SmoothingConfig::default());
+        smoother.apply(&Emotion::neutral(), 0);
+
+        let rise = smoother.apply(&Emotion::new(1.0, 0.5, 0.5), SECOND);
+        assert!(rise.emotion.valence > 0.6 && rise.emotion.valence < 0.7);
+
+        let peak = smoother.apply(&Emotion::new(1.0, 0.5, 0.5), 10 * SECOND);
+        let fall = smoother.apply(&Emotion::neutral(), 11 * SECOND);
+        let dropped = peak.emotion.valence - fall.emotion.valence;
+        assert!(dropped < rise.emotion.valence / 2.0);
+    }
+
+    #[test]
+    fn test_one_euro_follows_fast_motion() {
+        let config = SmoothingConfig {
+            method: SmoothingMethod::OneEuro {
+                min_cutoff_hz: 0.1,
+                beta: 1.0,
+                derivative_cutoff_hz: 1.0,
+            },
+            ..Default::default()
+        };
+        let mut smoother = EmotionSmoother::new(config);
+        smoother.apply(&Emotion::neutral(), 0);
+
+        // Small jitter is damped heavily
+        let jitter = smoother.apply(&Emotion::new(0.05, 0.5, 0.5), SECOND / 10);
+        assert!(jitter.emotion.valence < 0.01);
+
+        // A large jump raises the cutoff and passes mostly through
+        let jump = smoother.apply(&Emotion::new(1.0, 0.5, 0.5), SECOND / 5);
+        assert!(jump.emotion.valence > 0.5);
+    }
+
+    #[test]
+    fn test_category_hysteresis() {
+        let config = SmoothingConfig {
+            method: SmoothingMethod::Off,
+            hysteresis: 0.1,
+            min_dwell_ns: 2 * SECOND,
+        };
+        let mut smoother = EmotionSmoother::new(config);
+        let joy = Emotion::new(0.8, 0.7, 0.6);
+        let first = smoother.apply(&joy, 0);
+        assert_eq!(first.category, BasicEmotion::Joy);
+        assert!(first.category_changed);
+
+        // Barely past the Joy/Anticipation boundary: not enough margin
+        let (jv, ja, jd) = BasicEmotion::Joy.prototype();
+        let (av, aa, ad) = BasicEmotion::Anticipation.prototype();
+        let border = Emotion::new(
+            jv * 0.45 + av * 0.55,
+            ja * 0.45 + aa * 0.55,
+            jd * 0.45 + ad * 0.55,
+        );
+        assert_eq!(smoother.apply(&border, SECOND).category, BasicEmotion::Joy);
+
+        // A clear change must still hold for the dwell time
+        let fear = Emotion::new(-0.64, 0.8, 0.29);
+        assert_eq!(
+            smoother.apply(&fear, 2 * SECOND).category,
+            BasicEmotion::Joy
+        );
+        let switched = smoother.apply(&fear, 4 * SECOND);
+        assert_eq!(switched.category, BasicEmotion::Fear);
+        assert!(switched.category_changed);
+        assert_eq!(switched.label(), "Fear");
+    }
+}
@@ -0,0 +1,292 @@
+//! Emotion taxonomy - Plutchik emotions classified from the full VAD space
+//!
+//! Nearest-prototype classification over valence, arousal and dominance,
+//! with intensity levels, weighted secondary emotions and a distance-based
+//! confidence.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Weight of the dominance axis in prototype distances; self-reported and
+/// inferred dominance is noisier than valence and arousal
+const DOMINANCE_WEIGHT: f32 = 0.5;
+/// Softmax temperature used to weight secondary emotions
+const SOFTMAX_TEMPERATURE: f32 = 0.1;
+/// Number of secondary emotions reported
+const SECONDARY_COUNT: usize = 3;
+/// Largest possible weighted distance in the normalized VAD cube
+const MAX_DISTANCE: f32 = 1.581_139; // sqrt(1 + 1 + DOMINANCE_WEIGHT)
+
+/// Plutchik's basic emotions plus a neutral centre
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BasicEmotion {
+    Joy,
+    Trust,
+    Fear,
+    Surprise,
+    Sadness,
+    Disgust,
+    Anger,
+    Anticipation,
+    Neutral,
+}
+
+/// Plutchik intensity ring
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum EmotionIntensity {
+    Mild,
+    Moderate,
+    Intense,
+}
+
+/// Emotion with its share of the classification
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WeightedEmotion {
+    pub emotion: BasicEmotion,
+    pub weight: f32,
+}
+
+/// Result of classifying a VAD point
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmotionClassification {
+    pub primary: BasicEmotion,
+    pub intensity: EmotionIntensity,
+    /// Weight of the primary emotion among all prototypes
+    pub weight: f32,
+    /// Weighted distance to the primary prototype in the normalized VAD cube
+    pub distance: f32,
+    pub confidence: f32,
+    /// Next closest emotions, strongest first
+    pub secondary: Vec<WeightedEmotion>,
+}
+
+/// Prototype VAD coordinates (valence -1..1, arousal and dominance 0..1),
+/// adapted from Mehrabian's PAD ratings of emotion terms
+const PROTOTYPES: [(BasicEmotion, f32, f32, f32); 9] = [
+    (BasicEmotion::Joy, 0.76, 0.74, 0.68),
+    (BasicEmotion::Trust, 0.60, 0.40, 0.55),
+    (BasicEmotion::Fear, -0.64, 0.80, 0.29),
+    (BasicEmotion::Surprise, 0.20, 0.84, 0.44),
+    (BasicEmotion::Sadness, -0.63, 0.37, 0.34),
+    (BasicEmotion::Disgust, -0.60, 0.68, 0.56),
+    (BasicEmotion::Anger, -0.51, 0.80, 0.63),
+    (BasicEmotion::Anticipation, 0.25, 0.65, 0.60),
+    (BasicEmotion::Neutral, 0.0, 0.5, 0.5),
+];
+
+impl BasicEmotion {
+    pub const ALL: [BasicEmotion; 9] = [
+        BasicEmotion::Joy,
+        BasicEmotion::Trust,
+        BasicEmotion::Fear,
+        BasicEmotion::Surprise,
+        BasicEmotion::Sadness,
+        BasicEmotion::Disgust,
+        BasicEmotion::Anger,
+        BasicEmotion::Anticipation,
+        BasicEmotion::Neutral,
+    ];
+
+    /// Prototype as (valence, arousal, dominance)
+    pub fn prototype(&self) -> (f32, f32, f32) {
+        PROTOTYPES
+            .iter()
+            .find(|(emotion, ..)| emotion == self)
+            .map(|(_, v, a, d)| (*v, *a, *d))
+            .unwrap_or((0.0, 0.5, 0.5))
+    }
+
+    /// Weighted distance from a VAD point to this emotion's prototype
+    pub fn distance_to(&self, valence: f32, arousal: f32, dominance: f32) -> f32 {
+        vad_distance(clamp_point(valence, arousal, dominance), self.prototype())
+    }
+
+    /// Intensity of this emotion at a VAD point
+    pub fn intensity_at(&self, valence: f32, arousal: f32, dominance: f32) -> EmotionIntensity {
+        intensity_of(*self, clamp_point(valence, arousal, dominance))
+    }
+
+    /// Plutchik's name for this emotion at the given intensity
+    pub fn label(&self, intensity: EmotionIntensity) -> &'static str {
+        use EmotionIntensity::*;
+        match (self, intensity) {
+            (BasicEmotion::Joy, Mild) => "Serenity",
+            (BasicEmotion::Joy, Moderate) => "Joy",
+            (BasicEmotion::Joy, Intense) => "Ecstasy",
+            (BasicEmotion::Trust, Mild) => "Acceptance",
+            (BasicEmotion::Trust, Moderate) => "Trust",
+            (BasicEmotion::Trust, Intense) => "Admiration",
+            (BasicEmotion::Fear, Mild) => "Apprehension",
+            (BasicEmotion::Fear, Moderate) => "Fear",
+            (BasicEmotion::Fear, Intense) => "Terror",
+            (BasicEmotion::Surprise, Mild) => "Distraction",
+            (BasicEmotion::Surprise, Moderate) => "Surprise",
+            (BasicEmotion::Surprise, Intense) => "Amazement",
+            (BasicEmotion::Sadness, Mild) => "Pensiveness",
+            (BasicEmotion::Sadness, Moderate) => "Sadness",
+            (BasicEmotion::Sadness, Intense) => "Grief",
+            (BasicEmotion::Disgust, Mild) => "Boredom",
+            (BasicEmotion::Disgust, Moderate) => "Disgust",
+            (BasicEmotion::Disgust, Intense) => "Loathing",
+            (BasicEmotion::Anger, Mild) => "Annoyance",
+            (BasicEmotion::Anger, Moderate) => "Anger",
+            (BasicEmotion::Anger, Intense) => "Rage",
+            (BasicEmotion::Anticipation, Mild) => "Interest",
+            (BasicEmotion::Anticipation, Moderate) => "Anticipation",
+            (BasicEmotion::Anticipation, Intense) => "Vigilance",
+            (BasicEmotion::Neutral, _) => "Neutral",
+        }
+    }
+}
+
+impl EmotionClassification {
+    /// Intensity-specific name of the primary emotion
+    pub fn label(&self) -> &'static str {
+        self.primary.label(self.intensity)
+    }
+}
+
+/// Classify a VAD point by its nearest emotion prototype
+pub fn classify(valence: f32, arousal: f32, dominance: f32) -> EmotionClassification {
+    let point = clamp_point(valence, arousal, dominance);
+
+    let mut distances: Vec<(BasicEmotion, f32)> = PROTOTYPES
+        .iter()
+        .map(|(emotion, v, a, d)| (*emotion, vad_distance(point, (*v, *a, *d))))
+        .collect();
+    distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Softmax over negative distances gives each prototype a share
+    let exps: Vec<f32> = distances
+        .iter()
+        .map(|(_, d)| (-(d - distances[0].1) / SOFTMAX_TEMPERATURE).exp())
+        .collect();
+    let total: f32 = exps.iter().sum();
+
+    let (primary, nearest) = distances[0];
+    let runner_up = distances[1].1;
+
+    // Close to the prototype and well separated from the runner-up
+    let closeness = (1.0 - nearest / MAX_DISTANCE).clamp(0.0, 1.0);
+    let separation = if runner_up > 0.0 {
+        1.0 - nearest / runner_up
+    } else {
+        0.0
+    };
+    let confidence = (closeness * (0.5 + 0.5 * separation)).clamp(0.0, 1.0);
+
+    let secondary = distances
+        .iter()
+        .zip(exps.iter())
+        .skip(1)
+        .take(SECONDARY_COUNT)
+        .map(|((emotion, _), e)| WeightedEmotion {
+            emotion: *emotion,
+            weight: e / total,
+        })
+        .collect();
+
+    EmotionClassification {
+        primary,
+        intensity: intensity_of(primary, point),
+        weight: exps[0] / total,
+        distance: nearest,
+        confidence,
+        secondary,
+    }
+}
+
+fn clamp_point(valence: f32, arousal: f32, dominance: f32) -> (f32, f32, f32) {
+    (
+        valence.clamp(-1.0, 1.0),
+        arousal.clamp(0.0, 1.0),
+        dominance.clamp(0.0, 1.0),
+    )
+}
+
+/// Intensity from how far the point lies from neutral relative to its prototype
+fn intensity_of(emotion: BasicEmotion, point: (f32, f32, f32)) -> EmotionIntensity {
+    let neutral = BasicEmotion::Neutral.prototype();
+    let reach = vad_distance(emotion.prototype(), neutral);
+    if reach == 0.0 {
+        return EmotionIntensity::Moderate;
+    }
+
+    let ratio = vad_distance(point, neutral) / reach;
+    if ratio < 0.6 {
+        EmotionIntensity::Mild
+    } else if ratio < 1.2 {
+        EmotionIntensity::Moderate
+    } else {
+        EmotionIntensity::Intense
+    }
+}
+
+/// Distance with dominance down-weighted, so it separates rather than
+/// dominates categories
+fn vad_distance(a: (f32, f32, f32), b: (f32, f32, f32)) -> f32 {
+    emotion_model::vad_distance(a, b, DOMINANCE_WEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prototypes_classify_as_themselves() {
+        for emotion in BasicEmotion::ALL {
+            let (v, a, d) = emotion.prototype();
+            let result = classify(v, a, d);
+            assert_eq!(result.primary, emotion);
+            assert!(
+                result.confidence > 0.9,
+                "{:?} {}",
+                emotion,
+                result.confidence
+            );
+        }
+    }
+
+    #[test]
+    fn test_dominance_separates_anger_and_fear() {
+        assert_eq!(classify(-0.6, 0.8, 0.8).primary, BasicEmotion::Anger);
+        assert_eq!(classify(-0.6, 0.8, 0.2).primary, BasicEmotion::Fear);
+    }
+
+    #[test]
+    fn test_moderate_positive_valence_is_positive() {
+        let result = classify(0.3, 0.3, 0.5);
+        assert!(matches!(
+            result.primary,
+            BasicEmotion::Trust | BasicEmotion::Joy
+        ));
+    }
+
+    #[test]
+    fn test_intensity_rings() {
+        assert_eq!(classify(-0.9, 0.95, 0.9).label(), "Rage");
+        assert_eq!(classify(-0.63, 0.37, 0.34).label(), "Sadness");
+        assert_eq!(classify(0.0, 0.5, 0.5).label(), "Neutral");
+    }
+
+    #[test]
+    fn test_secondary_weights() {
+        let result = classify(0.5, 0.8, 0.55);
+        assert_eq!(result.secondary.len(), SECONDARY_COUNT);
+        let total: f32 = result.weight + result.secondary.iter().map(|s| s.weight).sum::<f32>();
+        assert!(total <= 1.0 + 1e-5);
+        assert!(result
+            .secondary
+            .windows(2)
+            .all(|w| w[0].weight >= w[1].weight));
+        assert!(result.secondary.iter().all(|s| s.weight <= result.weight));
+    }
+}
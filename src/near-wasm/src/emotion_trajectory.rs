@@ -0,0 +1,360 @@
+//! Emotion trajectory - Bounded multi-resolution history of emotional states
+//!
+//! Keeps recent raw samples alongside per-minute and per-hour aggregates so
+//! long sessions retain their emotional arc without unbounded growth.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Timestamp;
+use std::collections::VecDeque;
+use std::ops::Index;
+
+use crate::emotional::EmotionalVector;
+
+pub const NANOS_PER_MINUTE: u64 = 60 * 1_000_000_000;
+pub const NANOS_PER_HOUR: u64 = 60 * NANOS_PER_MINUTE;
+
+/// Limits for each resolution tier
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrajectoryConfig {
+    /// Maximum raw samples kept
+    pub raw_capacity: u32,
+    /// Raw samples older than this relative to the newest are dropped (0 = no limit)
+    pub raw_retention_ns: u64,
+    /// Maximum per-minute aggregates kept
+    pub minute_capacity: u32,
+    /// Maximum per-hour aggregates kept
+    pub hour_capacity: u32,
+}
+
+/// Resolution of a trajectory view
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TrajectoryResolution {
+    Raw,
+    Minute,
+    Hour,
+}
+
+/// Mean, min and max of one VAD dimension within a bucket
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DimensionStats {
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Aggregate of all samples falling in one time bucket
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrajectoryAggregate {
+    pub start: Timestamp,
+    pub count: u32,
+    pub valence: DimensionStats,
+    pub arousal: DimensionStats,
+    pub dominance: DimensionStats,
+}
+
+/// Bounded emotional history at raw, minute and hour resolution
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrajectoryBuffer {
+    pub config: TrajectoryConfig,
+    raw: VecDeque<EmotionalVector>,
+    minutes: VecDeque<TrajectoryAggregate>,
+    hours: VecDeque<TrajectoryAggregate>,
+}
+
+impl Default for TrajectoryConfig {
+    fn default() -> Self {
+        Self {
+            raw_capacity: 64,
+            raw_retention_ns: 10 * NANOS_PER_MINUTE,
+            minute_capacity: 180,
+            hour_capacity: 168,
+        }
+    }
+}
+
+impl TrajectoryResolution {
+    /// Bucket width in nanoseconds; raw samples have none
+    pub fn bucket_ns(&self) -> Option<u64> {
+        match self {
+            TrajectoryResolution::Raw => None,
+            TrajectoryResolution::Minute => Some(NANOS_PER_MINUTE),
+            TrajectoryResolution::Hour => Some(NANOS_PER_HOUR),
+        }
+    }
+}
+
+impl DimensionStats {
+    fn new(value: f32) -> Self {
+        Self {
+            mean: value,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn add(&mut self, value: f32, count: u32) {
+        self.mean += (value - self.mean) / count as f32;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+}
+
+impl TrajectoryAggregate {
+    fn new(start: Timestamp, sample: &EmotionalVector) -> Self {
+        Self {
+            start,
+            count: 1,
+            valence: DimensionStats::new(sample.valence),
+            arousal: DimensionStats::new(sample.arousal),
+            dominance: DimensionStats::new(sample.dominance),
+        }
+    }
+
+    fn add(&mut self, sample: &EmotionalVector) {
+        self.count += 1;
+        self.valence.add(sample.valence, self.count);
+        self.arousal.add(sample.arousal, self.count);
+        self.dominance.add(sample.dominance, self.count);
+    }
+
+    /// Mean state of the bucket, stamped at the bucket start
+    pub fn mean_vector(&self) -> EmotionalVector {
+        EmotionalVector {
+            valence: self.valence.mean,
+            arousal: self.arousal.mean,
+            dominance: self.dominance.mean,
+            timestamp: self.start,
+        }
+    }
+}
+
+impl TrajectoryBuffer {
+    pub fn new(config: TrajectoryConfig) -> Self {
+        Self {
+            config,
+            raw: VecDeque::new(),
+            minutes: VecDeque::new(),
+            hours: VecDeque::new(),
+        }
+    }
+
+    /// Record a sample in every tier, keeping samples ordered by timestamp
+    pub fn push(&mut self, sample: EmotionalVector) {
+        add_to_buckets(&mut self.minutes, &sample, NANOS_PER_MINUTE);
+        add_to_buckets(&mut self.hours, &sample, NANOS_PER_HOUR);
+
+        let position = self
+            .raw
+            .partition_point(|s| s.timestamp <= sample.timestamp);
+        self.raw.insert(position, sample);
+        self.enforce_limits();
+    }
+
+    /// Replace the limits and trim every tier to them
+    pub fn set_config(&mut self, config: TrajectoryConfig) {
+        self.config = config;
+        self.enforce_limits();
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&EmotionalVector> {
+        self.raw.back()
+    }
+
+    /// Raw samples, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &EmotionalVector> {
+        self.raw.iter()
+    }
+
+    /// Raw samples as a contiguous vector, oldest first
+    pub fn to_vec(&self) -> Vec<EmotionalVector> {
+        self.raw.iter().cloned().collect()
+    }
+
+    /// Aggregates for a bucketed resolution, oldest first
+    pub fn aggregates(&self, resolution: TrajectoryResolution) -> Vec<TrajectoryAggregate> {
+        match resolution {
+            TrajectoryResolution::Raw => self
+                .raw
+                .iter()
+                .map(|s| TrajectoryAggregate::new(s.timestamp, s))
+                .collect(),
+            TrajectoryResolution::Minute => self.minutes.iter().cloned().collect(),
+            TrajectoryResolution::Hour => self.hours.iter().cloned().collect(),
+        }
+    }
+
+    /// Emotional arc at the given resolution, using bucket means
+    pub fn arc(&self, resolution: TrajectoryResolution) -> Vec<EmotionalVector> {
+        match resolution {
+            TrajectoryResolution::Raw => self.to_vec(),
+            _ => self
+                .aggregates(resolution)
+                .iter()
+                .map(|a| a.mean_vector())
+                .collect(),
+        }
+    }
+
+    /// Clear every tier
+    pub fn clear(&mut self) {
+        self.raw.clear();
+        self.minutes.clear();
+        self.hours.clear();
+    }
+
+    fn enforce_limits(&mut self) {
+        if self.config.raw_retention_ns > 0 {
+            if let Some(newest) = self.raw.back().map(|s| s.timestamp) {
+                let cutoff = newest.saturating_sub(self.config.raw_retention_ns);
+                while self.raw.front().is_some_and(|s| s.timestamp < cutoff) {
+                    self.raw.pop_front();
+                }
+            }
+        }
+        truncate_front(&mut self.raw, self.config.raw_capacity);
+        truncate_front(&mut self.minutes, self.config.minute_capacity);
+        truncate_front(&mut self.hours, self.config.hour_capacity);
+    }
+}
+
+impl Default for TrajectoryBuffer {
+    fn default() -> Self {
+        Self::new(TrajectoryConfig::default())
+    }
+}
+
+impl Index<usize> for TrajectoryBuffer {
+    type Output = EmotionalVector;
+
+    fn index(&self, index: usize) -> &EmotionalVector {
+        &self.raw[index]
+    }
+}
+
+/// Fold a sample into its bucket, creating the bucket in order if needed
+fn add_to_buckets(
+    buckets: &mut VecDeque<TrajectoryAggregate>,
+    sample: &EmotionalVector,
+    width: u64,
+) {
+    let start = sample.timestamp - sample.timestamp % width;
+    let position = buckets.partition_point(|b| b.start < start);
+    match buckets.get_mut(position) {
+        Some(bucket) if bucket.start == start => bucket.add(sample),
+        _ => buckets.insert(position, TrajectoryAggregate::new(start, sample)),
+    }
+}
+
+fn truncate_front<T>(items: &mut VecDeque<T>, capacity: u32) {
+    let excess = items.len().saturating_sub(capacity as usize);
+    items.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn sample(valence: f32, timestamp: u64) -> EmotionalVector {
+        EmotionalVector {
+            valence,
+            arousal: 0.5,
+            dominance: 0.5,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_capacity_and_retention() {
+        let mut buffer = TrajectoryBuffer::new(TrajectoryConfig {
+            raw_capacity: 5,
+            raw_retention_ns: 35 * SECOND,
+            ..TrajectoryConfig::default()
+        });
+        for i in 0..8 {
+            buffer.push(sample(0.0, i * SECOND));
+        }
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(buffer[0].timestamp, 3 * SECOND);
+
+        buffer.push(sample(0.0, 40 * SECOND));
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer[0].timestamp, 5 * SECOND);
+    }
+
+    #[test]
+    fn test_minute_and_hour_aggregates() {
+        let mut buffer = TrajectoryBuffer::default();
+        // Two hours of samples every 20 seconds with valence cycling per minute
+        for i in 0..360u64 {
+            let valence = [-0.5, 0.0, 0.5][(i % 3) as usize];
+            buffer.push(sample(valence, i * 20 * SECOND));
+        }
+
+        let minutes = buffer.aggregates(TrajectoryResolution::Minute);
+        assert_eq!(minutes.len(), 120);
+        assert_eq!(minutes[0].count, 3);
+        assert!(minutes[0].valence.mean.abs() < 1e-6);
+        assert_eq!(minutes[0].valence.min, -0.5);
+        assert_eq!(minutes[0].valence.max, 0.5);
+
+        let hours = buffer.arc(TrajectoryResolution::Hour);
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[1].timestamp, NANOS_PER_HOUR);
+        assert!(buffer.len() <= TrajectoryConfig::default().raw_capacity as usize);
+    }
+
+    #[test]
+    fn test_out_of_order_samples_stay_sorted() {
+        let mut buffer = TrajectoryBuffer::default();
+        buffer.push(sample(0.1, 10 * SECOND));
+        buffer.push(sample(0.2, 5 * SECOND));
+        buffer.push(sample(0.3, 70 * SECOND));
+        buffer.push(sample(0.4, 2 * SECOND));
+
+        let times: Vec<u64> = buffer.iter().map(|s| s.timestamp).collect();
+        assert_eq!(
+            times,
+            vec![2 * SECOND, 5 * SECOND, 10 * SECOND, 70 * SECOND]
+        );
+        let minutes = buffer.aggregates(TrajectoryResolution::Minute);
+        assert_eq!(minutes.len(), 2);
+        assert_eq!(minutes[0].count, 3);
+    }
+
+    #[test]
+    fn test_bounded_over_long_session() {
+        let mut buffer = TrajectoryBuffer::default();
+        // Eight days at one sample per minute
+        for i in 0..(8 * 24 * 60u64) {
+            buffer.push(sample(0.0, i * NANOS_PER_MINUTE));
+        }
+        let config = TrajectoryConfig::default();
+        assert!(buffer.len() <= config.raw_capacity as usize);
+        assert_eq!(
+            buffer.aggregates(TrajectoryResolution::Minute).len(),
+            config.minute_capacity as usize
+        );
+        assert_eq!(
+            buffer.aggregates(TrajectoryResolution::Hour).len(),
+            config.hour_capacity as usize
+        );
+    }
+}
@@ -5,6 +5,13 @@
 use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::Timestamp;
+use emotion_model::Emotion;
+
+use crate::emotion_prediction::{self, EmotionForecast, PredictionMethod};
+use crate::emotion_taxonomy::{self, EmotionClassification};
+use crate::emotion_trajectory::{TrajectoryBuffer, TrajectoryConfig};
+use crate::emotional_similarity::{self, SimilarityMatch, SimilarityPage, SimilarityQuery};
+use crate::signal_models::SignalModel;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -17,7 +24,7 @@ pub struct EmotionalData {
     pub raw_vector: Vec<f32>,
     pub emotional_vector: EmotionalVector,
     // Add advanced emotional metrics
-    pub emotional_trajectory: Vec<EmotionalVector>,
+    pub emotional_trajectory: TrajectoryBuffer,
     pub predicted_emotion: Option<EmotionalVector>,
     pub emotional_complexity: f32,
 }
@@ -31,6 +38,31 @@ pub struct EmotionalVector {
     pub timestamp: Timestamp,
 }
 
+impl From<EmotionalVector> for Emotion {
+    fn from(v: EmotionalVector) -> Self {
+        Emotion::new(v.valence, v.arousal, v.dominance).with_timestamp(v.timestamp)
+    }
+}
+
+impl From<Emotion> for EmotionalVector {
+    fn from(e: Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            timestamp: e.timestamp,
+        }
+    }
+}
+
+impl From<&EmotionalData> for Emotion {
+    fn from(data: &EmotionalData) -> Self {
+        Emotion::new(data.valence, data.arousal, data.dominance)
+            .with_confidence(data.confidence)
+            .with_timestamp(data.timestamp)
+    }
+}
+
 impl EmotionalData {
     pub fn new() -> Self {
         Self {
@@ -46,7 +78,7 @@ impl EmotionalData {
                 dominance: 0.5,
                 timestamp: near_sdk::env::block_timestamp(),
             },
-            emotional_trajectory: vec![],
+            emotional_trajectory: TrajectoryBuffer::default(),
             predicted_emotion: None,
             emotional_complexity: 0.0,
         }
@@ -72,48 +104,61 @@ impl EmotionalData {
                 dominance,
                 timestamp: near_sdk::env::block_timestamp(),
             },
-            emotional_trajectory: vec![],
+            emotional_trajectory: TrajectoryBuffer::default(),
             predicted_emotion: None,
             emotional_complexity: 0.0,
         }
     }
     
+    /// Estimate the emotional state from raw features using a signal model
+    pub fn from_signal(model: &dyn SignalModel, raw_vector: Vec<f32>) -> Result<Self, String> {
+        let estimate = model.estimate(&raw_vector)?;
+        let mut data = Self::from_vector(vec![estimate.valence, estimate.arousal, estimate.dominance]);
+        data.confidence = estimate.confidence;
+        data.raw_vector = raw_vector;
+        Ok(data)
+    }
+    
     /// Add a new emotional state to the trajectory
     pub fn add_to_trajectory(&mut self, emotion: EmotionalVector) {
         self.emotional_trajectory.push(emotion);
-        // Keep only the last 10 emotional states
-        if self.emotional_trajectory.len() > 10 {
-            self.emotional_trajectory.remove(0);
-        }
         // Update complexity based on trajectory variance
         self.update_emotional_complexity();
     }
+
+    /// Change trajectory capacity and retention, trimming existing history
+    pub fn set_trajectory_config(&mut self, config: TrajectoryConfig) {
+        self.emotional_trajectory.set_config(config);
+        self.update_emotional_complexity();
+    }
     
     /// Predict next emotional state based on trajectory
+    ///
+    /// Forecasts one typical sampling interval past the last sample using
+    /// the default predictor; see `forecast` for other methods and intervals.
     pub fn predict_next_emotion(&mut self) -> EmotionalVector {
         if self.emotional_trajectory.len() < 2 {
             return self.emotional_vector.clone();
         }
-        
-        // Simple linear regression prediction
-        let len = self.emotional_trajectory.len();
-        let last = &self.emotional_trajectory[len - 1];
-        let prev = &self.emotional_trajectory[len - 2];
-        
-        let delta_valence = last.valence - prev.valence;
-        let delta_arousal = last.arousal - prev.arousal;
-        let delta_dominance = last.dominance - prev.dominance;
-        
-        let predicted = EmotionalVector {
-            valence: (last.valence + delta_valence).clamp(-1.0, 1.0),
-            arousal: (last.arousal + delta_arousal).clamp(0.0, 1.0),
-            dominance: (last.dominance + delta_dominance).clamp(0.0, 1.0),
-            timestamp: near_sdk::env::block_timestamp() + 1000000000, // 1 second in the future
+
+        let samples = self.emotional_trajectory.to_vec();
+        let last = &samples[samples.len() - 1];
+        let interval = emotion_prediction::typical_interval(&samples).unwrap_or(1_000_000_000);
+        let target = last.timestamp + interval;
+
+        let predicted = match self.forecast(&PredictionMethod::default(), target) {
+            Some(forecast) => forecast.to_vector(),
+            None => return self.emotional_vector.clone(),
         };
-        
+
         self.predicted_emotion = Some(predicted.clone());
         predicted
     }
+
+    /// Forecast the trajectory at `target` with confidence intervals
+    pub fn forecast(&self, method: &PredictionMethod, target: Timestamp) -> Option<EmotionForecast> {
+        emotion_prediction::forecast(&self.emotional_trajectory.to_vec(), method, target)
+    }
     
     /// Update emotional complexity based on trajectory variance
     fn update_emotional_complexity(&mut self) {
@@ -133,7 +178,7 @@ impl EmotionalData {
         let mean_dominance: f32 = self.emotional_trajectory.iter().map(|e| e.dominance).sum::<f32>() / len;
         
         // Calculate variance
-        for emotion in &self.emotional_trajectory {
+        for emotion in self.emotional_trajectory.iter() {
             valence_variance += (emotion.valence - mean_valence).powi(2);
             arousal_variance += (emotion.arousal - mean_arousal).powi(2);
             dominance_variance += (emotion.dominance - mean_dominance).powi(2);
@@ -147,20 +192,46 @@ impl EmotionalData {
         self.emotional_complexity = ((valence_variance + arousal_variance + dominance_variance) / 3.0).min(1.0);
     }
     
+    /// Classify the current state against the emotion taxonomy
+    pub fn classify_emotion(&self) -> EmotionClassification {
+        emotion_taxonomy::classify(self.valence, self.arousal, self.dominance)
+    }
+
     /// Get emotional category based on VAD values
     pub fn get_emotional_category(&self) -> String {
-        match (self.valence, self.arousal) {
-            (v, a) if v > 0.5 && a > 0.5 => "Excited".to_string(),
-            (v, a) if v > 0.5 && a <= 0.5 => "Happy".to_string(),
-            (v, a) if v <= 0.5 && a > 0.5 => "Anxious".to_string(),
-            _ => "Calm".to_string(),
-        }
+        self.classify_emotion().label().to_string()
+    }
+
+    /// Score this state and its trajectory against a similarity query
+    pub fn similarity_to(&self, id: String, query: &SimilarityQuery) -> SimilarityMatch {
+        query.score(id, &Emotion::from(self), &self.trajectory_emotions())
+    }
+
+    /// Rank emotional data by similarity to a query, one page at a time
+    pub fn rank_by_similarity<'a, I>(
+        query: &SimilarityQuery,
+        candidates: I,
+        from_index: u64,
+        limit: u64,
+    ) -> SimilarityPage
+    where
+        I: IntoIterator<Item = (String, &'a EmotionalData)>,
+    {
+        let candidates = candidates
+            .into_iter()
+            .map(|(id, data)| (id, Emotion::from(data), data.trajectory_emotions()));
+        emotional_similarity::rank(query, candidates, from_index, limit)
+    }
+
+    fn trajectory_emotions(&self) -> Vec<Emotion> {
+        self.emotional_trajectory.iter().cloned().map(Emotion::from).collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::emotion_taxonomy::BasicEmotion;
 
     #[test]
     fn test_emotional_data_creation() {
@@ -180,6 +251,17 @@ mod tests {
         assert_eq!(emotion.dominance, 0.5);
     }
 
+    #[test]
+    fn test_emotional_data_from_signal() {
+        let model = crate::signal_models::HrvModel::new();
+        let raw_vector = vec![110.0, 20.0, 3.0];
+        let emotion = EmotionalData::from_signal(&model, raw_vector.clone()).unwrap();
+        assert_eq!(emotion.raw_vector, raw_vector);
+        assert!(emotion.arousal > 0.7);
+        assert!(emotion.confidence < 0.8);
+        assert!(EmotionalData::from_signal(&model, vec![0.8, 0.9, 0.5, 0.1]).is_err());
+    }
+
     #[test]
     fn test_emotional_vector_creation() {
         let timestamp = near_sdk::env::block_timestamp();
@@ -239,28 +321,68 @@ mod tests {
         emotion.add_to_trajectory(vector2);
         
         let predicted = emotion.predict_next_emotion();
-        assert_eq!(predicted.valence, 0.3);
-        assert_eq!(predicted.arousal, 0.4);
-        assert_eq!(predicted.dominance, 0.5);
+        assert!((predicted.valence - 0.3).abs() < 1e-6);
+        assert!((predicted.arousal - 0.4).abs() < 1e-6);
+        assert!((predicted.dominance - 0.5).abs() < 1e-6);
+        assert_eq!(predicted.timestamp, near_sdk::env::block_timestamp() + 2000);
     }
     
     #[test]
     fn test_get_emotional_category() {
         let mut emotion = EmotionalData::new();
+        assert_eq!(emotion.get_emotional_category(), "Neutral");
+
         emotion.valence = 0.8;
         emotion.arousal = 0.9;
-        assert_eq!(emotion.get_emotional_category(), "Excited");
+        assert_eq!(emotion.classify_emotion().primary, BasicEmotion::Joy);
         
         emotion.valence = 0.8;
         emotion.arousal = 0.3;
-        assert_eq!(emotion.get_emotional_category(), "Happy");
+        assert_eq!(emotion.classify_emotion().primary, BasicEmotion::Trust);
         
-        emotion.valence = -0.3;
+        emotion.valence = -0.6;
         emotion.arousal = 0.8;
-        assert_eq!(emotion.get_emotional_category(), "Anxious");
+        emotion.dominance = 0.2;
+        assert_eq!(emotion.get_emotional_category(), "Fear");
         
         emotion.valence = -0.3;
         emotion.arousal = 0.3;
-        assert_eq!(emotion.get_emotional_category(), "Calm");
+        emotion.dominance = 0.4;
+        assert_eq!(emotion.classify_emotion().primary, BasicEmotion::Sadness);
+    }
+
+    #[test]
+    fn test_rank_by_similarity() {
+        let calm = EmotionalData::from_vector(vec![0.3, 0.2, 0.5]);
+        let excited = EmotionalData::from_vector(vec![0.8, 0.9, 0.7]);
+        let mut rising = EmotionalData::from_vector(vec![0.8, 0.9, 0.7]);
+        for (i, valence) in [-0.4, 0.2, 0.8].iter().enumerate() {
+            rising.add_to_trajectory(EmotionalVector {
+                valence: *valence,
+                arousal: 0.9,
+                dominance: 0.7,
+                timestamp: i as u64 * 1000,
+            });
+        }
+
+        let query = SimilarityQuery::new(0.8, 0.9, 0.7);
+        let page = EmotionalData::rank_by_similarity(
+            &query,
+            vec![
+                ("calm".to_string(), &calm),
+                ("excited".to_string(), &excited),
+            ],
+            0,
+            10,
+        );
+        assert_eq!(page.matches[0].id, "excited");
+        assert_eq!(page.total, 2);
+
+        // A steady query trajectory prefers the candidate without the swing
+        let steady = vec![Emotion::new(0.8, 0.9, 0.7); 3];
+        let query = query.with_trajectory(steady);
+        let score = rising.similarity_to("rising".to_string(), &query);
+        assert!(score.trajectory_distance.unwrap() > 0.0);
+        assert!(score.similarity < 1.0);
     }
-}
\ No newline at end of file
+}
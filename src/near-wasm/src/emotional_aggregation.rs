@@ -0,0 +1,452 @@
+//! Emotional aggregation - Collective mood of many viewers of one token
+//!
+//! Each viewer's inputs are folded into a recency-decayed running mean.
+//! The collective state combines viewers with per-viewer weight caps after
+//! rejecting viewers whose mood is far from the crowd.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{AccountId, Timestamp};
+
+use emotion_model::{vad_distance, Emotion};
+
+use crate::emotion_taxonomy::{self, BasicEmotion, WeightedEmotion};
+
+/// Viewers whose decayed weight falls below this no longer contribute
+const MIN_VIEWER_WEIGHT: f32 = 1e-3;
+/// Outlier rejection needs enough viewers to define a crowd
+const MIN_VIEWERS_FOR_OUTLIERS: usize = 3;
+/// Floor on the robust spread so a unanimous crowd doesn't reject everyone else
+const MIN_SPREAD: f32 = 0.05;
+/// Scale from median absolute deviation to standard deviation
+const MAD_TO_STD: f32 = 1.4826;
+
+/// Tuning for the aggregation engine
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AggregationConfig {
+    /// Time for an input's weight to halve
+    pub half_life_ns: u64,
+    /// Most confidence mass one viewer can accumulate, however often they interact
+    pub max_viewer_weight: f32,
+    /// Largest share of the collective any one viewer may hold
+    pub max_viewer_share: f32,
+    /// Robust standard deviations beyond which a viewer is an outlier
+    pub outlier_threshold: f32,
+    /// Viewers tracked; the weakest is evicted when full
+    pub max_viewers: u32,
+}
+
+/// One viewer's decayed running mood
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ViewerContribution {
+    pub viewer: AccountId,
+    /// Confidence mass at `last_update`
+    pub weight: f32,
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    pub inputs: u32,
+    pub last_update: Timestamp,
+}
+
+/// Collective emotional state of a token's viewers
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectiveMood {
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    /// Agreement between contributing viewers (0.0 to 1.0)
+    pub agreement: f32,
+    pub dominant_emotion: BasicEmotion,
+    pub label: String,
+    /// Share of capped weight per viewer emotion, strongest first
+    pub distribution: Vec<WeightedEmotion>,
+    pub contributing_viewers: u32,
+    pub rejected_outliers: u32,
+    pub computed_at: Timestamp,
+}
+
+/// Aggregates many viewers' emotional inputs for one token
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmotionalAggregator {
+    pub config: AggregationConfig,
+    pub viewers: Vec<ViewerContribution>,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            half_life_ns: 30 * 60 * 1_000_000_000,
+            max_viewer_weight: 3.0,
+            max_viewer_share: 0.25,
+            outlier_threshold: 3.0,
+            max_viewers: 256,
+        }
+    }
+}
+
+impl ViewerContribution {
+    fn decayed_weight(&self, now: Timestamp, half_life_ns: u64) -> f32 {
+        self.weight * decay(now.saturating_sub(self.last_update), half_life_ns)
+    }
+
+    fn vad(&self) -> [f32; 3] {
+        [self.valence, self.arousal, self.dominance]
+    }
+}
+
+impl CollectiveMood {
+    fn neutral(now: Timestamp) -> Self {
+        let classification = emotion_taxonomy::classify(0.0, 0.5, 0.5);
+        Self {
+            valence: 0.0,
+            arousal: 0.5,
+            dominance: 0.5,
+            agreement: 0.0,
+            dominant_emotion: classification.primary,
+            label: classification.label().to_string(),
+            distribution: Vec::new(),
+            contributing_viewers: 0,
+            rejected_outliers: 0,
+            computed_at: now,
+        }
+    }
+}
+
+impl EmotionalAggregator {
+    pub fn new(config: AggregationConfig) -> Self {
+        Self {
+            config,
+            viewers: Vec::new(),
+        }
+    }
+
+    /// Fold a viewer's input into their running mood, weighted by confidence
+    pub fn record(&mut self, viewer: &AccountId, emotion: &Emotion, now: Timestamp) {
+        let weight = if emotion.confidence.is_finite() {
+            emotion.confidence.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        if weight <= 0.0 {
+            return;
+        }
+        let half_life = self.config.half_life_ns;
+
+        if let Some(entry) = self.viewers.iter_mut().find(|v| &v.viewer == viewer) {
+            let previous = entry.decayed_weight(now, half_life);
+            let total = previous + weight;
+            entry.valence = (entry.valence * previous + emotion.valence * weight) / total;
+            entry.arousal = (entry.arousal * previous + emotion.arousal * weight) / total;
+            entry.dominance = (entry.dominance * previous + emotion.dominance * weight) / total;
+            entry.weight = total.min(self.config.max_viewer_weight);
+            entry.inputs += 1;
+            entry.last_update = entry.last_update.max(now);
+            return;
+        }
+
+        if self.viewers.len() >= self.config.max_viewers as usize {
+            let weakest = self
+                .viewers
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.decayed_weight(now, half_life)
+                        .total_cmp(&b.decayed_weight(now, half_life))
+                })
+                .map(|(i, _)| i);
+            if let Some(index) = weakest {
+                self.viewers.swap_remove(index);
+            }
+        }
+
+        self.viewers.push(ViewerContribution {
+            viewer: viewer.clone(),
+            weight,
+            valence: emotion.valence,
+            arousal: emotion.arousal,
+            dominance: emotion.dominance,
+            inputs: 1,
+            last_update: now,
+        });
+    }
+
+    /// Drop viewers whose inputs have decayed away
+    pub fn prune(&mut self, now: Timestamp) {
+        let half_life = self.config.half_life_ns;
+        self.viewers
+            .retain(|v| v.decayed_weight(now, half_life) >= MIN_VIEWER_WEIGHT);
+    }
+
+    /// Collective mood at `now`
+    pub fn collective(&self, now: Timestamp) -> CollectiveMood {
+        let half_life = self.config.half_life_ns;
+        let active: Vec<(&ViewerContribution, f32)> = self
+            .viewers
+            .iter()
+            .map(|v| (v, v.decayed_weight(now, half_life)))
+            .filter(|(_, w)| *w >= MIN_VIEWER_WEIGHT)
+            .collect();
+        if active.is_empty() {
+            return CollectiveMood::neutral(now);
+        }
+
+        let inliers = self.reject_outliers(&active);
+        let rejected = (active.len() - inliers.len()) as u32;
+        let weights = cap_shares(
+            &inliers.iter().map(|(_, w)| *w).collect::<Vec<f32>>(),
+            self.config.max_viewer_share,
+        );
+        let total: f32 = weights.iter().sum();
+
+        let mut mean = [0.0f32; 3];
+        for ((viewer, _), weight) in inliers.iter().zip(&weights) {
+            for (m, x) in mean.iter_mut().zip(viewer.vad()) {
+                *m += x * weight / total;
+            }
+        }
+
+        // Weighted mean distance to the collective, with valence rescaled to 0..1
+        let dispersion: f32 = inliers
+            .iter()
+            .zip(&weights)
+            .map(|((viewer, _), weight)| {
+                let [v, a, d] = viewer.vad();
+                let distance = vad_distance((v, a, d), (mean[0], mean[1], mean[2]), 1.0);
+                distance * weight / total
+            })
+            .sum();
+
+        let mut distribution: Vec<WeightedEmotion> = Vec::new();
+        for ((viewer, _), weight) in inliers.iter().zip(&weights) {
+            let emotion =
+                emotion_taxonomy::classify(viewer.valence, viewer.arousal, viewer.dominance)
+                    .primary;
+            match distribution.iter_mut().find(|d| d.emotion == emotion) {
+                Some(entry) => entry.weight += weight / total,
+                None => distribution.push(WeightedEmotion {
+                    emotion,
+                    weight: weight / total,
+                }),
+            }
+        }
+        distribution.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+        let classification = emotion_taxonomy::classify(mean[0], mean[1], mean[2]);
+        CollectiveMood {
+            valence: mean[0],
+            arousal: mean[1],
+            dominance: mean[2],
+            agreement: (1.0 - dispersion * 2.0).clamp(0.0, 1.0),
+            dominant_emotion: classification.primary,
+            label: classification.label().to_string(),
+            distribution,
+            contributing_viewers: inliers.len() as u32,
+            rejected_outliers: rejected,
+            computed_at: now,
+        }
+    }
+
+    /// Keep viewers within `outlier_threshold` robust deviations of the
+    /// median on every dimension
+    fn reject_outliers<'a>(
+        &self,
+        active: &[(&'a ViewerContribution, f32)],
+    ) -> Vec<(&'a ViewerContribution, f32)> {
+        if active.len() < MIN_VIEWERS_FOR_OUTLIERS {
+            return active.to_vec();
+        }
+
+        let mut centres = [0.0f32; 3];
+        let mut spreads = [0.0f32; 3];
+        for dim in 0..3 {
+            let values: Vec<f32> = active.iter().map(|(v, _)| v.vad()[dim]).collect();
+            let centre = median(values.clone());
+            let deviations = values.iter().map(|x| (x - centre).abs()).collect();
+            centres[dim] = centre;
+            spreads[dim] = (median(deviations) * MAD_TO_STD).max(MIN_SPREAD);
+        }
+
+        let inliers: Vec<(&'a ViewerContribution, f32)> = active
+            .iter()
+            .filter(|(viewer, _)| {
+                viewer.vad().iter().enumerate().all(|(dim, x)| {
+                    (x - centres[dim]).abs() <= self.config.outlier_threshold * spreads[dim]
+                })
+            })
+            .cloned()
+            .collect();
+        // With no consensus on any axis every viewer can look like an outlier
+        if inliers.is_empty() {
+            return active.to_vec();
+        }
+        inliers
+    }
+}
+
+impl Default for EmotionalAggregator {
+    fn default() -> Self {
+        Self::new(AggregationConfig::default())
+    }
+}
+
+/// Weight multiplier after `elapsed` nanoseconds
+fn decay(elapsed: u64, half_life_ns: u64) -> f32 {
+    if half_life_ns == 0 {
+        return 1.0;
+    }
+    0.5f32.powf(elapsed as f32 / half_life_ns as f32)
+}
+
+/// Cap each weight at `max_share` of the capped total, redistributing the
+/// excess; the cap only applies once there are enough viewers to satisfy it
+fn cap_shares(weights: &[f32], max_share: f32) -> Vec<f32> {
+    let n = weights.len();
+    if n == 0 || max_share <= 0.0 || max_share >= 1.0 {
+        return weights.to_vec();
+    }
+    if (n as f32) * max_share <= 1.0 {
+        return weights.to_vec();
+    }
+
+    // Water-filling: fix capped viewers at a common level c so that
+    // c = max_share * (capped * c + uncapped_sum)
+    let mut sorted: Vec<f32> = weights.to_vec();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let mut level = f32::INFINITY;
+    for capped in 0..n {
+        let rest: f32 = sorted[capped..].iter().sum();
+        let candidate = max_share * rest / (1.0 - max_share * capped as f32);
+        if sorted[capped] <= candidate {
+            level = if capped == 0 {
+                f32::INFINITY
+            } else {
+                candidate
+            };
+            break;
+        }
+    }
+    weights.iter().map(|w| w.min(level)).collect()
+}
+
+/// Median of a non-empty set of values, averaging the middle pair
+pub fn median(mut values: Vec<f32>) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60 * 1_000_000_000;
+
+    fn account(name: &str) -> AccountId {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn test_recency_decay() {
+        let mut aggregator = EmotionalAggregator::default();
+        aggregator.record(&account("a.near"), &Emotion::new(-0.8, 0.5, 0.5), 0);
+        aggregator.record(
+            &account("b.near"),
+            &Emotion::new(0.8, 0.5, 0.5),
+            120 * MINUTE,
+        );
+
+        // Four half-lives later the first viewer carries 1/16 of the weight
+        let mood = aggregator.collective(120 * MINUTE);
+        assert!(mood.valence > 0.6, "valence {}", mood.valence);
+        assert_eq!(mood.contributing_viewers, 2);
+    }
+
+    #[test]
+    fn test_single_viewer_cannot_dominate() {
+        let mut aggregator = EmotionalAggregator::default();
+        for i in 0..100 {
+            aggregator.record(&account("spammer.near"), &Emotion::new(-0.9, 0.9, 0.9), i);
+        }
+        for name in ["a.near", "b.near", "c.near", "d.near", "e.near", "f.near"] {
+            aggregator.record(&account(name), &Emotion::new(0.6, 0.4, 0.5), 100);
+        }
+
+        let mood = aggregator.collective(100);
+        assert_eq!(aggregator.viewers.len(), 7);
+        assert_eq!(aggregator.viewers[0].weight, 3.0);
+        // The spammer is both an outlier and capped; the crowd decides
+        assert!(mood.valence > 0.3, "valence {}", mood.valence);
+        assert_eq!(mood.rejected_outliers, 1);
+    }
+
+    #[test]
+    fn test_disagreement_keeps_every_viewer() {
+        let mut aggregator = EmotionalAggregator::default();
+        aggregator.record(&account("a.near"), &Emotion::new(1.0, 0.5, 0.5), 0);
+        aggregator.record(&account("b.near"), &Emotion::new(0.0, 1.0, 0.5), 0);
+        aggregator.record(&account("c.near"), &Emotion::new(0.0, 0.5, 1.0), 0);
+
+        let mood = aggregator.collective(0);
+        assert_eq!(mood.contributing_viewers, 3);
+        assert_eq!(mood.rejected_outliers, 0);
+        assert!((mood.valence - 1.0 / 3.0).abs() < 1e-4, "valence {}", mood.valence);
+        assert!((mood.arousal - 2.0 / 3.0).abs() < 1e-4, "arousal {}", mood.arousal);
+    }
+
+    #[test]
+    fn test_cap_shares() {
+        let capped = cap_shares(&[100.0, 1.0, 1.0, 1.0, 1.0], 0.25);
+        let total: f32 = capped.iter().sum();
+        assert!(capped[0] / total <= 0.25 + 1e-5);
+        assert_eq!(&capped[1..], &[1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(cap_shares(&[5.0, 1.0], 0.25), vec![5.0, 1.0]);
+    }
+
+    #[test]
+    fn test_distribution_and_label() {
+        let mut aggregator = EmotionalAggregator::default();
+        for (i, name) in ["a.near", "b.near", "c.near"].iter().enumerate() {
+            aggregator.record(&account(name), &Emotion::new(0.76, 0.74, 0.68), i as u64);
+        }
+        aggregator.record(&account("d.near"), &Emotion::new(0.6, 0.4, 0.55), 3);
+
+        let mood = aggregator.collective(3);
+        let total: f32 = mood.distribution.iter().map(|d| d.weight).sum();
+        assert!((total - 1.0).abs() < 1e-5);
+        assert_eq!(mood.distribution[0].emotion, BasicEmotion::Joy);
+        assert_eq!(mood.dominant_emotion, BasicEmotion::Joy);
+        assert!(mood.agreement > 0.8);
+    }
+
+    #[test]
+    fn test_eviction_and_prune() {
+        let mut aggregator = EmotionalAggregator::new(AggregationConfig {
+            max_viewers: 2,
+            ..AggregationConfig::default()
+        });
+        aggregator.record(
+            &account("a.near"),
+            &Emotion::new(0.0, 0.5, 0.5).with_confidence(0.2),
+            0,
+        );
+        aggregator.record(&account("b.near"), &Emotion::new(0.0, 0.5, 0.5), 0);
+        aggregator.record(&account("c.near"), &Emotion::new(0.0, 0.5, 0.5), 0);
+        assert!(aggregator
+            .viewers
+            .iter()
+            .all(|v| v.viewer.as_str() != "a.near"));
+
+        aggregator.prune(100 * 30 * MINUTE);
+        assert!(aggregator.viewers.is_empty());
+        assert_eq!(aggregator.collective(0).contributing_viewers, 0);
+    }
+}
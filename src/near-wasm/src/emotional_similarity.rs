@@ -0,0 +1,261 @@
+//! Emotional similarity - Rank emotional states and trajectories against a query
+//!
+//! Current states are compared by normalized VAD distance. Trajectories are
+//! compared with dynamic time warping so histories recorded at different
+//! rates or lengths still line up.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use emotion_model::{vad_distance, Emotion};
+
+/// Default page size for ranked results
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+/// Largest page a single query may request
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// What to search for; only passed as JSON view arguments, so not stored
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SimilarityQuery {
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    /// Recent states to match against candidate histories, oldest first
+    #[serde(default)]
+    pub trajectory: Vec<Emotion>,
+    /// Share of the score taken by trajectory distance when both sides have one
+    #[serde(default = "default_trajectory_weight")]
+    pub trajectory_weight: f32,
+    /// Sakoe-Chiba band width for DTW, unbounded when `None`
+    #[serde(default)]
+    pub band: Option<u32>,
+}
+
+fn default_trajectory_weight() -> f32 {
+    0.5
+}
+
+/// One ranked candidate
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SimilarityMatch {
+    pub id: String,
+    /// Combined distance, 0 (identical) to 1 (opposite corners of VAD space)
+    pub distance: f32,
+    pub similarity: f32,
+    /// DTW distance to the query trajectory, when one was compared
+    pub trajectory_distance: Option<f32>,
+}
+
+/// A page of ranked candidates
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SimilarityPage {
+    pub matches: Vec<SimilarityMatch>,
+    /// Candidates ranked in total
+    pub total: u64,
+    /// Index to pass for the next page, `None` on the last page
+    pub next_index: Option<u64>,
+}
+
+impl SimilarityQuery {
+    pub fn new(valence: f32, arousal: f32, dominance: f32) -> Self {
+        Self {
+            valence,
+            arousal,
+            dominance,
+            trajectory: Vec::new(),
+            trajectory_weight: default_trajectory_weight(),
+            band: None,
+        }
+    }
+
+    pub fn with_trajectory(mut self, trajectory: Vec<Emotion>) -> Self {
+        self.trajectory = trajectory;
+        self
+    }
+
+    pub fn target(&self) -> Emotion {
+        Emotion::new(self.valence, self.arousal, self.dominance)
+    }
+
+    /// Score one candidate's current state and history
+    pub fn score(&self, id: String, current: &Emotion, history: &[Emotion]) -> SimilarityMatch {
+        let state_distance = state_distance(&self.target(), current);
+        let trajectory_distance = dtw_distance(&self.trajectory, history, self.band);
+        let distance = match trajectory_distance {
+            Some(dtw) => {
+                let weight = self.trajectory_weight.clamp(0.0, 1.0);
+                (1.0 - weight) * state_distance + weight * dtw
+            }
+            None => state_distance,
+        };
+        SimilarityMatch {
+            id,
+            distance,
+            similarity: 1.0 - distance,
+            trajectory_distance,
+        }
+    }
+}
+
+/// VAD distance scaled so the diagonal of the space is 1
+pub fn state_distance(a: &Emotion, b: &Emotion) -> f32 {
+    let point = |e: &Emotion| (e.valence, e.arousal, e.dominance);
+    (vad_distance(point(a), point(b), 1.0) / 3f32.sqrt()).min(1.0)
+}
+
+/// Dynamic time warping distance between two trajectories, averaged over
+/// the warping path so it stays on the `state_distance` scale
+///
+/// `band` limits how far the alignment may drift from the diagonal,
+/// bounding the cost for long histories. Returns `None` if either side is
+/// empty.
+pub fn dtw_distance(a: &[Emotion], b: &[Emotion], band: Option<u32>) -> Option<f32> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n, m) = (a.len(), b.len());
+    // The band must at least cover the length difference to reach the corner
+    let band = band.map(|w| (w as usize).max(n.abs_diff(m)));
+
+    // Each cell holds (accumulated cost, path length)
+    let unreachable = (f32::INFINITY, 0u32);
+    let mut previous = vec![unreachable; m + 1];
+    let mut current = vec![unreachable; m + 1];
+    previous[0] = (0.0, 0);
+
+    for i in 1..=n {
+        current.fill(unreachable);
+        let (lo, hi) = match band {
+            Some(w) => (i.saturating_sub(w).max(1), (i + w).min(m)),
+            None => (1, m),
+        };
+        for j in lo..=hi {
+            let cost = state_distance(&a[i - 1], &b[j - 1]);
+            let best = [previous[j - 1], previous[j], current[j - 1]]
+                .into_iter()
+                .min_by(|x, y| x.0.total_cmp(&y.0))
+                .unwrap_or(unreachable);
+            if best.0.is_finite() {
+                current[j] = (best.0 + cost, best.1 + 1);
+            }
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let (cost, steps) = previous[m];
+    (steps > 0).then(|| cost / steps as f32)
+}
+
+/// Rank candidates by similarity to the query and return one page
+///
+/// Each candidate is `(id, current state, history oldest first)`. Ties are
+/// broken by id so pages are stable across calls.
+pub fn rank<I>(
+    query: &SimilarityQuery,
+    candidates: I,
+    from_index: u64,
+    limit: u64,
+) -> SimilarityPage
+where
+    I: IntoIterator<Item = (String, Emotion, Vec<Emotion>)>,
+{
+    let mut matches: Vec<SimilarityMatch> = candidates
+        .into_iter()
+        .map(|(id, current, history)| query.score(id, &current, &history))
+        .collect();
+    matches.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let total = matches.len() as u64;
+    let limit = limit.min(MAX_PAGE_SIZE);
+    let end = from_index.saturating_add(limit).min(total);
+    let page = matches
+        .into_iter()
+        .skip(from_index as usize)
+        .take(limit as usize)
+        .collect();
+    SimilarityPage {
+        matches: page,
+        total,
+        next_index: (end < total).then_some(end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(points: &[(f32, f32)]) -> Vec<Emotion> {
+        points
+            .iter()
+            .map(|&(v, a)| Emotion::new(v, a, 0.5))
+            .collect()
+    }
+
+    #[test]
+    fn test_state_distance_is_normalized() {
+        let low = Emotion::new(-1.0, 0.0, 0.0);
+        let high = Emotion::new(1.0, 1.0, 1.0);
+        assert_eq!(state_distance(&low, &low), 0.0);
+        assert!((state_distance(&low, &high) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dtw_aligns_different_rates() {
+        let slow = path(&[(-0.5, 0.2), (-0.5, 0.2), (0.0, 0.5), (0.0, 0.5), (0.5, 0.8)]);
+        let fast = path(&[(-0.5, 0.2), (0.0, 0.5), (0.5, 0.8)]);
+        let reversed: Vec<Emotion> = fast.iter().rev().cloned().collect();
+
+        assert_eq!(dtw_distance(&slow, &fast, None), Some(0.0));
+        assert!(dtw_distance(&slow, &reversed, None).unwrap() > 0.1);
+        assert_eq!(dtw_distance(&slow, &fast, Some(0)), Some(0.0));
+        assert_eq!(dtw_distance(&[], &fast, None), None);
+    }
+
+    #[test]
+    fn test_rank_orders_and_paginates() {
+        let query = SimilarityQuery::new(0.8, 0.7, 0.6);
+        let candidates = vec![
+            ("sad".to_string(), Emotion::new(-0.8, 0.2, 0.3), vec![]),
+            ("joy".to_string(), Emotion::new(0.8, 0.7, 0.6), vec![]),
+            ("calm".to_string(), Emotion::new(0.3, 0.2, 0.5), vec![]),
+        ];
+
+        let first = rank(&query, candidates.clone(), 0, 2);
+        let ids: Vec<&str> = first.matches.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["joy", "calm"]);
+        assert_eq!(first.matches[0].similarity, 1.0);
+        assert_eq!((first.total, first.next_index), (3, Some(2)));
+
+        let second = rank(&query, candidates, 2, 2);
+        assert_eq!(second.matches[0].id, "sad");
+        assert_eq!(second.next_index, None);
+    }
+
+    #[test]
+    fn test_trajectory_breaks_state_ties() {
+        let rising = path(&[(-0.5, 0.3), (0.0, 0.5), (0.5, 0.7)]);
+        let falling: Vec<Emotion> = rising.iter().rev().cloned().collect();
+        let query = SimilarityQuery::new(0.5, 0.7, 0.5).with_trajectory(rising.clone());
+        let now = Emotion::new(0.5, 0.7, 0.5);
+
+        let page = rank(
+            &query,
+            vec![
+                ("down".to_string(), now.clone(), falling),
+                ("up".to_string(), now, rising),
+            ],
+            0,
+            DEFAULT_PAGE_SIZE,
+        );
+        assert_eq!(page.matches[0].id, "up");
+        assert_eq!(page.matches[0].trajectory_distance, Some(0.0));
+        assert!(page.matches[1].distance > 0.0);
+    }
+}
@@ -0,0 +1,224 @@
+//! Engagement trend - Detect rising or falling activity from event timestamps
+//!
+//! Compares the event rate in the most recent window with the window
+//! before it and fits a least-squares line to binned counts across both.
+//! A trend is only reported when the rate change and the slope are both
+//! significant and agree in direction.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Timestamp;
+
+const NANOS_PER_HOUR: f64 = 3_600.0 * 1_000_000_000.0;
+
+/// Direction of engagement over time
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum EngagementTrend {
+    Increasing,
+    Decreasing,
+    Stable,
+    /// Too few events in the analysis windows to tell
+    InsufficientData,
+}
+
+impl EngagementTrend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EngagementTrend::Increasing => "increasing",
+            EngagementTrend::Decreasing => "decreasing",
+            EngagementTrend::Stable => "stable",
+            EngagementTrend::InsufficientData => "insufficient_data",
+        }
+    }
+}
+
+/// Windows and thresholds for trend detection
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrendConfig {
+    /// Length of the recent window; the prior window has the same length
+    pub window_ns: u64,
+    /// Bins across both windows for the regression
+    pub bins: u32,
+    /// Fewest events across both windows before a trend is reported
+    pub min_events: u32,
+    /// Smallest relative rate change counted as a trend
+    pub min_relative_change: f32,
+    /// Smallest slope t-statistic counted as significant
+    pub min_t_statistic: f32,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            window_ns: 24 * 3_600 * 1_000_000_000,
+            bins: 12,
+            min_events: 6,
+            min_relative_change: 0.25,
+            min_t_statistic: 2.0,
+        }
+    }
+}
+
+/// Trend together with the evidence behind it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrendAnalysis {
+    pub trend: EngagementTrend,
+    /// Events per hour in the recent and prior windows
+    pub recent_rate: f32,
+    pub prior_rate: f32,
+    /// Regression slope in events per bin, per bin
+    pub slope: f32,
+    /// Slope divided by its standard error
+    pub t_statistic: f32,
+}
+
+/// Analyse event timestamps up to `now`
+pub fn analyze(timestamps: &[Timestamp], now: Timestamp, config: &TrendConfig) -> TrendAnalysis {
+    let window = config.window_ns.max(1);
+    let bins = config.bins.max(2) as usize;
+    let start = now.saturating_sub(2 * window);
+    let span = now - start;
+
+    let mut counts = vec![0u32; bins];
+    let (mut recent, mut prior) = (0u32, 0u32);
+    for &t in timestamps.iter().filter(|&&t| t >= start && t <= now) {
+        if t > now.saturating_sub(window) {
+            recent += 1;
+        } else {
+            prior += 1;
+        }
+        let bin = if span == 0 {
+            bins - 1
+        } else {
+            (((t - start) as u128 * bins as u128 / span as u128) as usize).min(bins - 1)
+        };
+        counts[bin] += 1;
+    }
+
+    let hours = (window as f64 / NANOS_PER_HOUR) as f32;
+    let (slope, t_statistic) = regression(&counts);
+    let mut analysis = TrendAnalysis {
+        trend: EngagementTrend::Stable,
+        recent_rate: recent as f32 / hours,
+        prior_rate: prior as f32 / hours,
+        slope,
+        t_statistic,
+    };
+
+    // A history that starts inside the recent window has nothing to
+    // compare against, however busy it is
+    let young = timestamps
+        .iter()
+        .min()
+        .is_none_or(|&first| first > now.saturating_sub(window));
+    if young || recent + prior < config.min_events {
+        analysis.trend = EngagementTrend::InsufficientData;
+        return analysis;
+    }
+    let change = (recent as f32 - prior as f32) / (prior.max(1) as f32);
+    let significant = t_statistic.abs() >= config.min_t_statistic;
+    if significant && change >= config.min_relative_change && slope > 0.0 {
+        analysis.trend = EngagementTrend::Increasing;
+    } else if significant && change <= -config.min_relative_change && slope < 0.0 {
+        analysis.trend = EngagementTrend::Decreasing;
+    }
+    analysis
+}
+
+/// Least-squares slope of counts against bin index and its t-statistic
+fn regression(counts: &[u32]) -> (f32, f32) {
+    let n = counts.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = counts.iter().map(|&c| c as f64).sum::<f64>() / n;
+    let (mut sxx, mut sxy) = (0.0, 0.0);
+    for (i, &c) in counts.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        sxx += dx * dx;
+        sxy += dx * (c as f64 - mean_y);
+    }
+    let slope = sxy / sxx;
+    let residual: f64 = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let fitted = mean_y + slope * (i as f64 - mean_x);
+            (c as f64 - fitted).powi(2)
+        })
+        .sum();
+    let standard_error = (residual / (n - 2.0) / sxx).sqrt();
+    let t = if standard_error > 0.0 {
+        slope / standard_error
+    } else if slope == 0.0 {
+        0.0
+    } else {
+        // A perfect fit is as significant as it gets
+        f64::INFINITY.copysign(slope)
+    };
+    (slope as f32, t as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3_600 * 1_000_000_000;
+    const NOW: u64 = 1_000 * HOUR;
+
+    /// Events spread over the two days before NOW, `per_hour(h)` in hour
+    /// `h` counted back from NOW
+    fn events(per_hour: impl Fn(u64) -> u64) -> Vec<Timestamp> {
+        (0..48)
+            .flat_map(|h| {
+                let hour_start = NOW - (h + 1) * HOUR;
+                let n = per_hour(h);
+                (0..n).map(move |i| hour_start + (i + 1) * HOUR / (n + 1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_rising_and_falling_engagement() {
+        let config = TrendConfig::default();
+        let rising = analyze(&events(|h| if h < 24 { 4 } else { 1 }), NOW, &config);
+        assert_eq!(rising.trend, EngagementTrend::Increasing);
+        assert_eq!((rising.recent_rate, rising.prior_rate), (4.0, 1.0));
+
+        let falling = analyze(&events(|h| 1 + h / 8), NOW, &config);
+        assert_eq!(falling.trend, EngagementTrend::Decreasing);
+        assert!(falling.slope < 0.0);
+    }
+
+    #[test]
+    fn test_steady_and_sparse_histories() {
+        let config = TrendConfig::default();
+        let steady = analyze(&events(|_| 2), NOW, &config);
+        assert_eq!(steady.trend, EngagementTrend::Stable);
+
+        // Many events long ago, a handful recently: not enough in the windows
+        let sparse = analyze(
+            &[NOW - 200 * HOUR, NOW - 3 * HOUR, NOW - HOUR],
+            NOW,
+            &config,
+        );
+        assert_eq!(sparse.trend, EngagementTrend::InsufficientData);
+
+        // A burst in a brand-new token has no prior window to compare with
+        let young: Vec<Timestamp> = (0..20).map(|i| HOUR + i).collect();
+        assert_eq!(
+            analyze(&young, 2 * HOUR, &config).trend,
+            EngagementTrend::InsufficientData
+        );
+    }
+
+    #[test]
+    fn test_serializes_as_lowercase() {
+        let json = near_sdk::serde_json::to_string(&EngagementTrend::InsufficientData).unwrap();
+        assert_eq!(json, "\"insufficient_data\"");
+        assert_eq!(EngagementTrend::Increasing.as_str(), "increasing");
+    }
+}
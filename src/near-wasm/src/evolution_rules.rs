@@ -0,0 +1,444 @@
+//! Evolution rules - Data-driven stages for interactive NFTs
+//!
+//! A collection defines its stages in order, each with entry conditions
+//! over the token's interaction metrics, whether it can be left backwards,
+//! and effects applied to visual parameters on entry. The evaluator moves a
+//! token to the furthest stage whose conditions hold and logs every step.
+
+use std::collections::BTreeMap;
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+use near_sdk::Timestamp;
+
+/// Most stages a definition may declare
+pub const MAX_STAGES: usize = 32;
+
+/// Token metric a condition can test
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Metric {
+    Streak,
+    Energy,
+    Creativity,
+    CommunityScore,
+    UniqueUsers,
+    /// Frequency-weighted means of the interaction patterns' signatures
+    AvgValence,
+    AvgArousal,
+    AvgDominance,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+}
+
+/// One entry condition; a stage is entered when all of its conditions hold
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Condition {
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub value: f32,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum EffectOp {
+    Set,
+    Add,
+    Multiply,
+}
+
+/// Change to a named visual parameter when a stage is entered
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VisualEffect {
+    pub param: String,
+    pub op: EffectOp,
+    pub value: f32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvolutionStage {
+    pub name: String,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// Whether the token falls back out of this stage when its conditions
+    /// stop holding; one-way stages are kept once reached
+    #[serde(default)]
+    pub reversible: bool,
+    #[serde(default)]
+    pub on_enter: Vec<VisualEffect>,
+}
+
+/// A collection's evolution stages, earliest first
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvolutionDefinition {
+    pub collection_id: String,
+    pub stages: Vec<EvolutionStage>,
+}
+
+/// Validated definitions keyed by `collection_id`, with the default
+/// definition for collections that have not registered their own
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvolutionRegistry {
+    definitions: BTreeMap<String, EvolutionDefinition>,
+    fallback: EvolutionDefinition,
+}
+
+/// Metric values a definition is evaluated against
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EvolutionMetrics {
+    pub streak: u32,
+    pub energy: f32,
+    pub creativity: f32,
+    pub community_score: f32,
+    pub unique_users: u32,
+    pub avg_valence: f32,
+    pub avg_arousal: f32,
+    pub avg_dominance: f32,
+}
+
+/// One logged stage change
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StageTransition {
+    pub from: String,
+    pub to: String,
+    pub timestamp: Timestamp,
+    /// Whether this moved back to an earlier stage
+    pub regression: bool,
+}
+
+impl Condition {
+    pub fn holds(&self, metrics: &EvolutionMetrics) -> bool {
+        let actual = metrics.get(self.metric);
+        match self.comparison {
+            Comparison::AtLeast => actual >= self.value,
+            Comparison::AtMost => actual <= self.value,
+        }
+    }
+}
+
+impl EvolutionMetrics {
+    pub fn get(&self, metric: Metric) -> f32 {
+        match metric {
+            Metric::Streak => self.streak as f32,
+            Metric::Energy => self.energy,
+            Metric::Creativity => self.creativity,
+            Metric::CommunityScore => self.community_score,
+            Metric::UniqueUsers => self.unique_users as f32,
+            Metric::AvgValence => self.avg_valence,
+            Metric::AvgArousal => self.avg_arousal,
+            Metric::AvgDominance => self.avg_dominance,
+        }
+    }
+}
+
+impl VisualEffect {
+    pub fn apply(&self, params: &mut BTreeMap<String, f32>) {
+        let current = params.entry(self.param.clone()).or_insert(0.0);
+        *current = match self.op {
+            EffectOp::Set => self.value,
+            EffectOp::Add => *current + self.value,
+            EffectOp::Multiply => *current * self.value,
+        };
+    }
+}
+
+impl EvolutionStage {
+    fn new(name: &str, min_streak: u32) -> Self {
+        let conditions = if min_streak == 0 {
+            vec![]
+        } else {
+            vec![Condition {
+                metric: Metric::Streak,
+                comparison: Comparison::AtLeast,
+                value: min_streak as f32,
+            }]
+        };
+        Self {
+            name: name.to_string(),
+            conditions,
+            reversible: true,
+            on_enter: vec![],
+        }
+    }
+
+    pub fn is_met(&self, metrics: &EvolutionMetrics) -> bool {
+        self.conditions.iter().all(|c| c.holds(metrics))
+    }
+}
+
+impl Default for EvolutionDefinition {
+    /// Streak-only stages: seed, sprout at 6, bloom at 21, thrive at 51
+    fn default() -> Self {
+        Self {
+            collection_id: "default".to_string(),
+            stages: vec![
+                EvolutionStage::new("seed", 0),
+                EvolutionStage::new("sprout", 6),
+                EvolutionStage::new("bloom", 21),
+                EvolutionStage::new("thrive", 51),
+            ],
+        }
+    }
+}
+
+impl EvolutionDefinition {
+    /// Parse and validate a definition from JSON
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let definition: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid evolution definition: {}", e))?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Load a definition from a JSON file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: &std::path::Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stages.is_empty() {
+            return Err("Evolution definition has no stages".to_string());
+        }
+        if self.stages.len() > MAX_STAGES {
+            return Err(format!(
+                "Evolution definition has more than {} stages",
+                MAX_STAGES
+            ));
+        }
+        for (i, stage) in self.stages.iter().enumerate() {
+            if self.stages[..i].iter().any(|s| s.name == stage.name) {
+                return Err(format!("Stage {} is defined twice", stage.name));
+            }
+            let values = stage.conditions.iter().map(|c| c.value);
+            let effects = stage.on_enter.iter().map(|e| e.value);
+            if values.chain(effects).any(|v| !v.is_finite()) {
+                return Err(format!("Stage {} has a non-finite value", stage.name));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn initial_stage(&self) -> Option<&str> {
+        self.stages.first().map(|s| s.name.as_str())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.stages.iter().position(|s| s.name == name)
+    }
+
+    /// Move from `current` towards the furthest stage whose conditions
+    /// hold, applying on-enter effects and returning each step taken
+    ///
+    /// Advancing stops at the first stage whose conditions fail; falling
+    /// back stops at the first one-way stage. An unknown current stage
+    /// restarts from the initial stage. Invalid definitions are rejected.
+    pub fn evaluate(
+        &self,
+        current: &str,
+        metrics: &EvolutionMetrics,
+        visual_params: &mut BTreeMap<String, f32>,
+        now: Timestamp,
+    ) -> Result<Vec<StageTransition>, String> {
+        self.validate()?;
+        let mut transitions = Vec::new();
+        let mut index = match self.position(current) {
+            Some(index) => index,
+            None => {
+                self.enter(0, current, false, visual_params, now, &mut transitions);
+                0
+            }
+        };
+
+        while index + 1 < self.stages.len() && self.stages[index + 1].is_met(metrics) {
+            let from = self.stages[index].name.clone();
+            index += 1;
+            self.enter(index, &from, false, visual_params, now, &mut transitions);
+        }
+        if transitions.is_empty() {
+            while index > 0 && self.stages[index].reversible && !self.stages[index].is_met(metrics)
+            {
+                let from = self.stages[index].name.clone();
+                index -= 1;
+                self.enter(index, &from, true, visual_params, now, &mut transitions);
+            }
+        }
+        Ok(transitions)
+    }
+
+    fn enter(
+        &self,
+        index: usize,
+        from: &str,
+        regression: bool,
+        visual_params: &mut BTreeMap<String, f32>,
+        now: Timestamp,
+        transitions: &mut Vec<StageTransition>,
+    ) {
+        let stage = &self.stages[index];
+        stage.on_enter.iter().for_each(|e| e.apply(visual_params));
+        transitions.push(StageTransition {
+            from: from.to_string(),
+            to: stage.name.clone(),
+            timestamp: now,
+            regression,
+        });
+    }
+}
+
+impl EvolutionRegistry {
+    /// Add or replace the definition for its `collection_id`
+    pub fn register(&mut self, definition: EvolutionDefinition) -> Result<(), String> {
+        definition.validate()?;
+        self.definitions
+            .insert(definition.collection_id.clone(), definition);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, collection_id: &str) -> Option<EvolutionDefinition> {
+        self.definitions.remove(collection_id)
+    }
+
+    /// The collection's definition, or the default one if it has none
+    pub fn definition(&self, collection_id: &str) -> &EvolutionDefinition {
+        self.definitions.get(collection_id).unwrap_or(&self.fallback)
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(streak: u32, energy: f32) -> EvolutionMetrics {
+        EvolutionMetrics {
+            streak,
+            energy,
+            ..Default::default()
+        }
+    }
+
+    const DEFINITION: &str = r#"{
+        "collection_id": "aurora",
+        "stages": [
+            { "name": "ember" },
+            {
+                "name": "flame",
+                "conditions": [{ "metric": "Streak", "comparison": "AtLeast", "value": 3 }],
+                "reversible": true,
+                "on_enter": [{ "param": "glow", "op": "Add", "value": 0.5 }]
+            },
+            {
+                "name": "nova",
+                "conditions": [
+                    { "metric": "Streak", "comparison": "AtLeast", "value": 10 },
+                    { "metric": "Energy", "comparison": "AtLeast", "value": 0.8 }
+                ],
+                "on_enter": [{ "param": "glow", "op": "Multiply", "value": 2.0 }]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_default_matches_streak_thresholds() {
+        let definition = EvolutionDefinition::default();
+        let mut params = BTreeMap::new();
+        let steps = definition.evaluate("seed", &metrics(30, 0.5), &mut params, 7).unwrap();
+        let names: Vec<&str> = steps.iter().map(|t| t.to.as_str()).collect();
+        assert_eq!(names, vec!["sprout", "bloom"]);
+
+        let back = definition.evaluate("bloom", &metrics(3, 0.5), &mut params, 8).unwrap();
+        assert_eq!(back.last().unwrap().to, "seed");
+        assert!(back.iter().all(|t| t.regression));
+    }
+
+    #[test]
+    fn test_json_definition_with_effects_and_one_way_stage() {
+        let definition = EvolutionDefinition::from_json(DEFINITION).unwrap();
+        assert_eq!(definition.collection_id, "aurora");
+        let mut params = BTreeMap::new();
+
+        // Streak alone is not enough for nova
+        let steps = definition.evaluate("ember", &metrics(12, 0.5), &mut params, 1).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(params["glow"], 0.5);
+
+        let steps = definition.evaluate("flame", &metrics(12, 0.9), &mut params, 2).unwrap();
+        assert_eq!(steps[0].to, "nova");
+        assert_eq!(params["glow"], 1.0);
+
+        // Nova is one-way, so losing the streak keeps it
+        assert!(definition
+            .evaluate("nova", &metrics(0, 0.1), &mut params, 3)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_validation() {
+        let duplicate = DEFINITION.replace("\"nova\"", "\"flame\"");
+        assert!(EvolutionDefinition::from_json(&duplicate)
+            .unwrap_err()
+            .contains("defined twice"));
+        let empty = r#"{ "collection_id": "x", "stages": [] }"#;
+        assert!(EvolutionDefinition::from_json(empty).is_err());
+
+        // Definitions built directly are checked when evaluated
+        let unchecked = EvolutionDefinition {
+            collection_id: "x".to_string(),
+            stages: vec![],
+        };
+        assert_eq!(unchecked.initial_stage(), None);
+        let mut params = BTreeMap::new();
+        assert!(unchecked
+            .evaluate("seed", &metrics(1, 0.0), &mut params, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_registry_keys_definitions_by_collection() {
+        let mut registry = EvolutionRegistry::default();
+        registry
+            .register(EvolutionDefinition::from_json(DEFINITION).unwrap())
+            .unwrap();
+        assert_eq!(registry.definition("aurora").initial_stage(), Some("ember"));
+        assert_eq!(registry.definition("other").initial_stage(), Some("seed"));
+
+        let empty = EvolutionDefinition {
+            collection_id: "broken".to_string(),
+            stages: vec![],
+        };
+        assert!(registry.register(empty).is_err());
+        assert_eq!(registry.len(), 1);
+
+        assert!(registry.remove("aurora").is_some());
+        assert_eq!(registry.definition("aurora").initial_stage(), Some("seed"));
+    }
+}
@@ -5,6 +5,14 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env};
+use std::collections::VecDeque;
+use emotion_model::Emotion;
+
+use crate::emotion_palette::{EmotionPalette, Harmony};
+use crate::performance_stats::{FrameStats, PerformanceSummary, PerformanceTracker};
+
+/// Performance snapshots kept per session
+pub const MAX_PERFORMANCE_SNAPSHOTS: usize = 256;
 
 /// Fractal types supported by the studio
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
@@ -42,7 +50,9 @@ pub struct FractalSession {
     pub start_time: u64,
     pub params: FractalParams,
     pub keyframes: Vec<FractalKeyframe>,
-    pub performance_data: Vec<PerformanceSnapshot>,
+    pub performance_data: VecDeque<PerformanceSnapshot>,
+    #[serde(default)]
+    pub performance: PerformanceTracker,
 }
 
 /// Keyframe for fractal animation
@@ -73,6 +83,22 @@ pub struct EmotionalVector {
     pub dominance: f32,
 }
 
+impl From<EmotionalVector> for Emotion {
+    fn from(v: EmotionalVector) -> Self {
+        Emotion::new(v.valence, v.arousal, v.dominance)
+    }
+}
+
+impl From<Emotion> for EmotionalVector {
+    fn from(e: Emotion) -> Self {
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+        }
+    }
+}
+
 impl Default for FractalParams {
     fn default() -> Self {
         Self {
@@ -123,8 +149,10 @@ impl FractalParams {
 
     /// Apply emotional modulation to fractal parameters
     pub fn apply_emotional_modulation(&mut self, emotion: &EmotionalVector) {
-        // Valence affects color intensity
-        let _color_intensity = ((emotion.valence + 1.0) / 2.0).clamp(0.0, 1.0);
+        // Valence, arousal and dominance shape the palette, keeping its size
+        let size = self.color_palette.len().max(2);
+        self.color_palette =
+            EmotionPalette::generate(&Emotion::from(emotion.clone()), size, Harmony::Analogous).to_u32();
         
         // Arousal affects iteration count (more arousal = more detail)
         self.max_iterations = (100.0 + emotion.arousal * 200.0) as u32;
@@ -143,14 +171,20 @@ impl FractalSession {
             start_time: env::block_timestamp(),
             params,
             keyframes: Vec::new(),
-            performance_data: Vec::new(),
+            performance_data: VecDeque::new(),
+            performance: PerformanceTracker::new(),
         }
     }
 
     /// Add a keyframe to the session
     pub fn add_keyframe(&mut self, params: FractalParams, emotional_state: Option<EmotionalVector>) {
+        self.add_keyframe_at(params, emotional_state, env::block_timestamp());
+    }
+
+    /// Add a keyframe with an explicit timestamp (used by replay)
+    pub fn add_keyframe_at(&mut self, params: FractalParams, emotional_state: Option<EmotionalVector>, timestamp: u64) {
         self.keyframes.push(FractalKeyframe {
-            timestamp: env::block_timestamp(),
+            timestamp,
             params,
             emotional_state,
         });
@@ -158,14 +192,48 @@ impl FractalSession {
 
     /// Record performance metrics
     pub fn record_performance(&mut self, fps: f32, zoom_velocity: f64, changes: Vec<String>) {
-        self.performance_data.push(PerformanceSnapshot {
-            timestamp: env::block_timestamp(),
+        self.record_performance_at(fps, zoom_velocity, changes, env::block_timestamp());
+    }
+
+    /// Record performance metrics with an explicit timestamp (used by replay)
+    ///
+    /// Only the most recent snapshots are kept. The reported fps is an
+    /// average, so it is tracked apart from the raw frame samples.
+    pub fn record_performance_at(&mut self, fps: f32, zoom_velocity: f64, changes: Vec<String>, timestamp: u64) {
+        self.performance.record_reported_fps(fps);
+        if self.performance_data.len() >= MAX_PERFORMANCE_SNAPSHOTS {
+            self.performance_data.pop_front();
+        }
+        self.performance_data.push_back(PerformanceSnapshot {
+            timestamp,
             fps,
             zoom_velocity,
             parameter_changes: changes,
         });
     }
 
+    /// Record raw frame times (milliseconds) for the session
+    pub fn record_frame_times(&mut self, frame_times_ms: &[f32]) {
+        for frame_ms in frame_times_ms {
+            self.performance.frames.record_frame_time(*frame_ms);
+        }
+    }
+
+    /// Summary of the session's frame statistics
+    pub fn performance_summary(&self) -> PerformanceSummary {
+        self.performance.summary()
+    }
+
+    /// Reset frame statistics with a new frame budget
+    pub fn set_target_fps(&mut self, fps: f32) -> Result<(), String> {
+        let target_ms = 1000.0 / fps;
+        if !fps.is_finite() || fps <= 0.0 || !target_ms.is_finite() {
+            return Err(format!("Invalid target fps: {}", fps));
+        }
+        self.performance.frames = FrameStats::new(target_ms);
+        Ok(())
+    }
+
     /// Get session duration in nanoseconds
     pub fn duration(&self) -> u64 {
         env::block_timestamp() - self.start_time
@@ -350,6 +418,35 @@ mod tests {
         
         assert!(params.zoom > original_zoom);
         assert!(params.max_iterations > 100);
+
+        // Positive valence gives a warm palette of the same size
+        assert_eq!(params.color_palette.len(), 2);
+        let mid = params.color_palette[1];
+        assert!(mid >> 16 > mid & 0xFF);
+    }
+
+    #[test]
+    fn test_performance_history_is_bounded() {
+        let mut session = FractalSession::new("vj".to_string(), FractalParams::mandelbrot());
+        for i in 0..(MAX_PERFORMANCE_SNAPSHOTS + 10) {
+            session.record_performance_at(60.0, 0.0, vec![], i as u64);
+        }
+        assert_eq!(session.performance_data.len(), MAX_PERFORMANCE_SNAPSHOTS);
+        assert_eq!(session.performance_data[0].timestamp, 10);
+
+        let summary = session.performance_summary();
+        assert_eq!(summary.total_frames, 0);
+        assert!((summary.mean_reported_fps - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_target_fps_must_be_positive_and_finite() {
+        let mut session = FractalSession::new("vj".to_string(), FractalParams::mandelbrot());
+        for fps in [0.0, -30.0, f32::INFINITY, f32::NAN, 1e-40] {
+            assert!(session.set_target_fps(fps).is_err(), "{}", fps);
+        }
+        assert!(session.set_target_fps(30.0).is_ok());
+        assert!((session.performance.frames.target_frame_ms - 1000.0 / 30.0).abs() < 0.01);
     }
 
     #[test]
@@ -2,10 +2,24 @@
 //!
 //! Enhanced with advanced interaction tracking and evolution mechanics.
 
+use std::collections::BTreeMap;
+
 use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, AccountId, Timestamp};
 
+use crate::adaptive_learning::{self, InteractionOutcome, ResponseAction};
+use crate::community_metrics::CommunityTracker;
+use crate::engagement_trend::{self, EngagementTrend, TrendConfig};
+use crate::evolution_rules::{EvolutionDefinition, EvolutionMetrics, EvolutionRegistry, StageTransition};
+use crate::pattern_stats::{self, DecayedMean, RunningStats, SequenceDetector};
+
+const NANOS_PER_HOUR: u64 = 3_600 * 1_000_000_000;
+/// Stage transitions kept per token
+const MAX_EVOLUTION_LOG: usize = 64;
+/// Half-life of the recency-weighted signature means (one week)
+const SIGNATURE_HALF_LIFE_NS: u64 = 7 * 24 * NANOS_PER_HOUR;
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct InteractionEvent {
@@ -31,16 +45,47 @@ pub struct EmotionalImpact {
 #[serde(crate = "near_sdk::serde")]
 pub struct InteractiveState {
     pub mood: String,
+    /// Energy as of `last_activity`; read `energy_at` for the decayed value
     pub energy_level: f32,
     pub creativity_index: f32,
+    /// Streak as of `last_activity`; read `streak_at` to account for breaks
     pub interaction_streak: u32,
     pub last_activity: Timestamp,
+    pub activity_config: ActivityConfig,
     pub evolution_stage: String,
     // Add advanced interactive features
     pub interaction_patterns: Vec<InteractionPattern>,
     pub community_engagement: CommunityEngagement,
     pub adaptive_behavior: AdaptiveBehavior,
     pub interaction_history_summary: InteractionHistorySummary,
+    /// Recurring sequences of event types
+    pub sequence_patterns: SequenceDetector,
+    /// Visual parameters changed by evolution stage effects
+    pub visual_params: BTreeMap<String, f32>,
+    /// Recent stage transitions, oldest first
+    pub evolution_log: Vec<StageTransition>,
+}
+
+/// How streaks break and energy decays between interactions
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActivityConfig {
+    /// Inactivity after which the streak resets
+    pub streak_timeout_ns: u64,
+    /// Time for energy to halve without interactions
+    pub energy_half_life_ns: u64,
+    /// Energy regained per interaction, capped at 1
+    pub energy_per_interaction: f32,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            streak_timeout_ns: 48 * NANOS_PER_HOUR,
+            energy_half_life_ns: 24 * NANOS_PER_HOUR,
+            energy_per_interaction: 0.1,
+        }
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -50,6 +95,10 @@ pub struct InteractionPattern {
     pub frequency: u32,
     pub last_occurrence: Timestamp,
     pub emotional_signature: EmotionalSignature,
+    /// Occurrences per UTC hour of day
+    pub hour_histogram: [u32; 24],
+    /// Occurrences per UTC day of week, Monday first
+    pub weekday_histogram: [u32; 7],
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -58,6 +107,34 @@ pub struct EmotionalSignature {
     pub avg_valence: f32,
     pub avg_arousal: f32,
     pub avg_dominance: f32,
+    /// Running mean and variance of valence, arousal and dominance
+    pub stats: [RunningStats; 3],
+    /// Recency-weighted means of valence, arousal and dominance
+    pub recent: [DecayedMean; 3],
+}
+
+impl EmotionalSignature {
+    fn neutral() -> Self {
+        Self {
+            avg_valence: 0.0,
+            avg_arousal: 0.5,
+            avg_dominance: 0.5,
+            stats: Default::default(),
+            recent: Default::default(),
+        }
+    }
+    
+    /// Fold in one emotional impact
+    pub fn record(&mut self, impact: &EmotionalImpact, timestamp: Timestamp) {
+        let values = [impact.valence_shift, impact.arousal_shift, impact.dominance_shift];
+        for (i, value) in values.into_iter().enumerate() {
+            self.stats[i].push(value);
+            self.recent[i].push(value, timestamp, SIGNATURE_HALF_LIFE_NS);
+        }
+        self.avg_valence = self.stats[0].mean as f32;
+        self.avg_arousal = self.stats[1].mean as f32;
+        self.avg_dominance = self.stats[2].mean as f32;
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -67,14 +144,28 @@ pub struct CommunityEngagement {
     pub unique_users: u32,
     pub community_score: f32,
     pub trending: bool,
+    /// Interactions per hour over the last hour and the last day
+    pub hourly_rate: f32,
+    pub daily_rate: f32,
+    /// Recent velocity relative to the long-term baseline
+    pub trending_score: f32,
+    /// Missing from JSON written before the tracker was exported
+    #[serde(default)]
+    pub tracker: CommunityTracker,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AdaptiveBehavior {
+    /// Step size of preference weight updates
     pub learning_rate: f32,
+    /// Estimated reward of each `ResponseAction`, in `ResponseAction::ALL` order
     pub preference_weights: Vec<f32>,
     pub behavior_adaptations: Vec<BehaviorAdaptation>,
+    /// UCB exploration strength; 0 always picks the best-weighted action
+    pub exploration: f32,
+    /// Times each action has been rewarded
+    pub action_pulls: Vec<u32>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -83,7 +174,9 @@ pub struct BehaviorAdaptation {
     pub adaptation_type: String,
     pub trigger_condition: String,
     pub response_action: String,
+    /// Mean reward of outcomes following this response
     pub effectiveness: f32,
+    pub trials: u32,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
@@ -92,7 +185,10 @@ pub struct InteractionHistorySummary {
     pub total_interactions: u32,
     pub avg_interaction_intensity: f32,
     pub most_common_event_type: String,
-    pub engagement_trend: String, // "increasing", "decreasing", "stable"
+    pub engagement_trend: EngagementTrend,
+    /// Events per hour in the last day and the day before
+    pub recent_rate: f32,
+    pub prior_rate: f32,
 }
 
 impl Default for InteractiveState {
@@ -103,6 +199,7 @@ impl Default for InteractiveState {
             creativity_index: 0.5,
             interaction_streak: 0,
             last_activity: env::block_timestamp(),
+            activity_config: ActivityConfig::default(),
             evolution_stage: "seed".to_string(),
             interaction_patterns: vec![],
             community_engagement: CommunityEngagement {
@@ -110,18 +207,29 @@ impl Default for InteractiveState {
                 unique_users: 0,
                 community_score: 0.5,
                 trending: false,
+                hourly_rate: 0.0,
+                daily_rate: 0.0,
+                trending_score: 0.0,
+                tracker: CommunityTracker::default(),
             },
             adaptive_behavior: AdaptiveBehavior {
                 learning_rate: 0.1,
-                preference_weights: vec![0.5; 5], // Default weights for 5 preference types
+                preference_weights: vec![0.5; ResponseAction::ALL.len()],
                 behavior_adaptations: vec![],
+                exploration: 0.3,
+                action_pulls: vec![0; ResponseAction::ALL.len()],
             },
             interaction_history_summary: InteractionHistorySummary {
                 total_interactions: 0,
                 avg_interaction_intensity: 0.5,
                 most_common_event_type: "view".to_string(),
-                engagement_trend: "stable".to_string(),
+                engagement_trend: EngagementTrend::InsufficientData,
+                recent_rate: 0.0,
+                prior_rate: 0.0,
             },
+            sequence_patterns: SequenceDetector::default(),
+            visual_params: BTreeMap::new(),
+            evolution_log: vec![],
         }
     }
 }
@@ -135,67 +243,139 @@ impl InteractiveState {
         };
     }
     
+    /// Energy at `now`, decayed exponentially since the last interaction
+    pub fn energy_at(&self, now: Timestamp) -> f32 {
+        let elapsed = now.saturating_sub(self.last_activity) as f32;
+        let half_life = self.activity_config.energy_half_life_ns.max(1) as f32;
+        self.energy_level * 0.5f32.powf(elapsed / half_life)
+    }
+    
+    /// Streak at `now`; zero once the inactivity timeout has passed
+    pub fn streak_at(&self, now: Timestamp) -> u32 {
+        if now.saturating_sub(self.last_activity) > self.activity_config.streak_timeout_ns {
+            0
+        } else {
+            self.interaction_streak
+        }
+    }
+    
+    pub fn current_energy(&self) -> f32 {
+        self.energy_at(env::block_timestamp())
+    }
+    
+    pub fn current_streak(&self) -> u32 {
+        self.streak_at(env::block_timestamp())
+    }
+    
+    /// Settle decay up to `now`, then extend the streak and restore energy
+    pub fn record_activity(&mut self, now: Timestamp) {
+        // Out-of-order events count but do not move the clock back
+        let now = now.max(self.last_activity);
+        self.interaction_streak = self.streak_at(now) + 1;
+        self.energy_level = (self.energy_at(now) + self.activity_config.energy_per_interaction).min(1.0);
+        self.last_activity = now;
+    }
+    
+    /// Evolve using the default streak-based stages
     pub fn update_evolution(&mut self) {
-        // Simple evolution based on interaction streak
-        self.evolution_stage = match self.interaction_streak {
-            0..=5 => "seed".to_string(),
-            6..=20 => "sprout".to_string(),
-            21..=50 => "bloom".to_string(),
-            _ => "thrive".to_string(),
+        self.evolve(&EvolutionDefinition::default(), env::block_timestamp())
+            .expect("Default evolution definition is valid");
+    }
+    
+    /// Evolve using the definition registered for `collection_id`
+    pub fn evolve_in(&mut self, registry: &EvolutionRegistry, collection_id: &str, now: Timestamp) -> Result<Vec<StageTransition>, String> {
+        self.evolve(registry.definition(collection_id), now)
+    }
+    
+    /// Evolve using a collection's stage definition, logging each transition
+    pub fn evolve(&mut self, definition: &EvolutionDefinition, now: Timestamp) -> Result<Vec<StageTransition>, String> {
+        let metrics = self.evolution_metrics_at(now);
+        let transitions = definition.evaluate(&self.evolution_stage, &metrics, &mut self.visual_params, now)?;
+        if let Some(last) = transitions.last() {
+            self.evolution_stage = last.to.clone();
+        }
+        self.evolution_log.extend(transitions.iter().cloned());
+        if self.evolution_log.len() > MAX_EVOLUTION_LOG {
+            self.evolution_log.drain(..self.evolution_log.len() - MAX_EVOLUTION_LOG);
+        }
+        Ok(transitions)
+    }
+    
+    /// Current values of the metrics evolution conditions can test
+    pub fn evolution_metrics(&self) -> EvolutionMetrics {
+        self.evolution_metrics_at(env::block_timestamp())
+    }
+    
+    pub fn evolution_metrics_at(&self, now: Timestamp) -> EvolutionMetrics {
+        let total: u32 = self.interaction_patterns.iter().map(|p| p.frequency).sum();
+        let weighted = |f: fn(&EmotionalSignature) -> f32, neutral: f32| {
+            if total == 0 {
+                return neutral;
+            }
+            self.interaction_patterns
+                .iter()
+                .map(|p| f(&p.emotional_signature) * p.frequency as f32)
+                .sum::<f32>()
+                / total as f32
         };
+        EvolutionMetrics {
+            streak: self.streak_at(now),
+            energy: self.energy_at(now),
+            creativity: self.creativity_index,
+            community_score: self.community_engagement.community_score,
+            unique_users: self.community_engagement.unique_users,
+            avg_valence: weighted(|s| s.avg_valence, 0.0),
+            avg_arousal: weighted(|s| s.avg_arousal, 0.5),
+            avg_dominance: weighted(|s| s.avg_dominance, 0.5),
+        }
     }
     
     /// Update interaction patterns based on new event
     pub fn update_interaction_patterns(&mut self, event: &InteractionEvent) {
-        // Check if we already have this pattern type
-        let pattern_exists = self.interaction_patterns.iter_mut().find(|p| p.pattern_type == event.event_type);
-        
-        if let Some(pattern) = pattern_exists {
-            pattern.frequency += 1;
-            pattern.last_occurrence = event.timestamp;
-            // Update emotional signature (simplified)
-            if let Some(impact) = &event.emotional_impact {
-                pattern.emotional_signature.avg_valence = (pattern.emotional_signature.avg_valence + impact.valence_shift) / 2.0;
-                pattern.emotional_signature.avg_arousal = (pattern.emotional_signature.avg_arousal + impact.arousal_shift) / 2.0;
-                pattern.emotional_signature.avg_dominance = (pattern.emotional_signature.avg_dominance + impact.dominance_shift) / 2.0;
+        self.record_activity(event.timestamp);
+        self.sequence_patterns.push(&event.event_type, event.timestamp);
+        
+        // Find or create the pattern for this event type
+        let index = match self.interaction_patterns.iter().position(|p| p.pattern_type == event.event_type) {
+            Some(index) => index,
+            None => {
+                self.interaction_patterns.push(InteractionPattern {
+                    pattern_type: event.event_type.clone(),
+                    frequency: 0,
+                    last_occurrence: event.timestamp,
+                    emotional_signature: EmotionalSignature::neutral(),
+                    hour_histogram: [0; 24],
+                    weekday_histogram: [0; 7],
+                });
+                self.interaction_patterns.len() - 1
             }
-        } else {
-            // Create new pattern
-            let emotional_signature = if let Some(impact) = &event.emotional_impact {
-                EmotionalSignature {
-                    avg_valence: impact.valence_shift,
-                    avg_arousal: impact.arousal_shift,
-                    avg_dominance: impact.dominance_shift,
-                }
-            } else {
-                EmotionalSignature {
-                    avg_valence: 0.0,
-                    avg_arousal: 0.5,
-                    avg_dominance: 0.5,
-                }
-            };
-            
-            self.interaction_patterns.push(InteractionPattern {
-                pattern_type: event.event_type.clone(),
-                frequency: 1,
-                last_occurrence: event.timestamp,
-                emotional_signature,
-            });
+        };
+        
+        let pattern = &mut self.interaction_patterns[index];
+        pattern.frequency += 1;
+        pattern.last_occurrence = pattern.last_occurrence.max(event.timestamp);
+        pattern.hour_histogram[pattern_stats::hour_of_day(event.timestamp)] += 1;
+        pattern.weekday_histogram[pattern_stats::day_of_week(event.timestamp)] += 1;
+        if let Some(impact) = &event.emotional_impact {
+            pattern.emotional_signature.record(impact, event.timestamp);
         }
     }
     
     /// Update community engagement metrics
     pub fn update_community_engagement(&mut self, user_id: &AccountId) {
+        self.update_community_engagement_at(user_id, env::block_timestamp());
+    }
+
+    /// Update community engagement metrics for an interaction at `now`
+    pub fn update_community_engagement_at(&mut self, user_id: &AccountId, now: Timestamp) {
         self.community_engagement.total_interactions += 1;
-        
-        // In a real implementation, we'd track unique users properly
-        // For now, we'll just increment unique users for demo purposes
-        self.community_engagement.unique_users += 1;
+        self.community_engagement.tracker.record(user_id, now);
+        self.community_engagement.unique_users = self.community_engagement.tracker.users.count();
+        self.refresh_community_engagement(now);
         
         // Update community score based on interaction frequency
         if self.community_engagement.total_interactions > 50 {
             self.community_engagement.community_score = 0.9;
-            self.community_engagement.trending = true;
         } else if self.community_engagement.total_interactions > 20 {
             self.community_engagement.community_score = 0.7;
         } else {
@@ -203,8 +383,26 @@ impl InteractiveState {
         }
     }
     
+    /// Recompute windowed rates and trending; trending lapses once
+    /// activity falls back to the baseline
+    pub fn refresh_community_engagement(&mut self, now: Timestamp) {
+        let engagement = &mut self.community_engagement;
+        engagement.hourly_rate = engagement.tracker.rate_per_hour(NANOS_PER_HOUR, now);
+        engagement.daily_rate = engagement.tracker.rate_per_hour(24 * NANOS_PER_HOUR, now);
+        engagement.trending_score = engagement.tracker.trending_score(now);
+        engagement.trending = engagement.tracker.is_trending(now);
+    }
+    
     /// Update interaction history summary
     pub fn update_interaction_history_summary(&mut self, events: &[InteractionEvent]) {
+        self.update_interaction_history_summary_at(events, env::block_timestamp());
+    }
+
+    pub fn update_interaction_history_summary_at(
+        &mut self,
+        events: &[InteractionEvent],
+        now: Timestamp,
+    ) {
         if events.is_empty() {
             return;
         }
@@ -225,37 +423,105 @@ impl InteractiveState {
             self.interaction_history_summary.most_common_event_type = most_common.clone();
         }
         
-        // Determine engagement trend (simplified)
-        if events.len() > 10 {
-            self.interaction_history_summary.engagement_trend = "increasing".to_string();
-        } else if events.len() > 5 {
-            self.interaction_history_summary.engagement_trend = "stable".to_string();
-        } else {
-            self.interaction_history_summary.engagement_trend = "decreasing".to_string();
-        }
+        // Compare the last day's event rate with the day before
+        let timestamps: Vec<Timestamp> = events.iter().map(|e| e.timestamp).collect();
+        let analysis = engagement_trend::analyze(&timestamps, now, &TrendConfig::default());
+        let summary = &mut self.interaction_history_summary;
+        summary.engagement_trend = analysis.trend;
+        summary.recent_rate = analysis.recent_rate;
+        summary.prior_rate = analysis.prior_rate;
     }
     
     /// Adapt behavior based on interaction history
+    ///
+    /// Each event is treated as the outcome of the response currently in
+    /// force for its event type; the response is then re-chosen.
     pub fn adapt_behavior(&mut self, events: &[InteractionEvent]) {
-        // Simple adaptation: increase learning rate with more interactions
-        if events.len() > 20 {
-            self.adaptive_behavior.learning_rate = 0.3;
-        } else if events.len() > 10 {
-            self.adaptive_behavior.learning_rate = 0.2;
-        } else {
-            self.adaptive_behavior.learning_rate = 0.1;
+        for event in events {
+            let behavior = &mut self.adaptive_behavior;
+            let action = match behavior.adaptation(&event.event_type) {
+                Some(adaptation) => ResponseAction::from_name(&adaptation.response_action),
+                None => None,
+            }
+            .unwrap_or_else(|| behavior.respond_to(&event.event_type));
+            behavior.record_outcome(&event.event_type, action, &InteractionOutcome::from(event));
+            behavior.respond_to(&event.event_type);
         }
-        
-        // Add behavior adaptation based on popular event types
-        let popular_event_type = self.interaction_history_summary.most_common_event_type.clone();
-        if !self.adaptive_behavior.behavior_adaptations.iter().any(|a| a.adaptation_type == popular_event_type) {
-            self.adaptive_behavior.behavior_adaptations.push(BehaviorAdaptation {
-                adaptation_type: popular_event_type,
-                trigger_condition: "frequent_interaction".to_string(),
-                response_action: "enhance_response".to_string(),
-                effectiveness: 0.8,
-            });
+    }
+}
+
+impl From<&InteractionEvent> for InteractionOutcome {
+    fn from(event: &InteractionEvent) -> Self {
+        Self {
+            dwell_ms: InteractionOutcome::dwell_from_data(&event.data),
+            intensity: event.intensity,
+            emotional_impact: event
+                .emotional_impact
+                .as_ref()
+                .map_or(0.0, |i| i.valence_shift * i.confidence),
+        }
+    }
+}
+
+impl AdaptiveBehavior {
+    pub fn adaptation(&self, event_type: &str) -> Option<&BehaviorAdaptation> {
+        self.behavior_adaptations.iter().find(|a| a.adaptation_type == event_type)
+    }
+
+    /// Action the bandit would pick next
+    pub fn choose_action(&self) -> ResponseAction {
+        ResponseAction::ALL[adaptive_learning::select(
+            &self.preference_weights,
+            &self.action_pulls,
+            self.exploration,
+        )]
+    }
+
+    /// Choose the response to an event type and make it the one in force
+    pub fn respond_to(&mut self, event_type: &str) -> ResponseAction {
+        self.ensure_arms();
+        let action = self.choose_action();
+        match self.behavior_adaptations.iter_mut().find(|a| a.adaptation_type == event_type) {
+            Some(adaptation) => adaptation.response_action = action.as_str().to_string(),
+            None => self.behavior_adaptations.push(BehaviorAdaptation {
+                adaptation_type: event_type.to_string(),
+                trigger_condition: "interaction".to_string(),
+                response_action: action.as_str().to_string(),
+                effectiveness: 0.0,
+                trials: 0,
+            }),
+        }
+        action
+    }
+
+    /// Learn from the outcome of responding to `event_type` with `action`,
+    /// returning the reward
+    pub fn record_outcome(
+        &mut self,
+        event_type: &str,
+        action: ResponseAction,
+        outcome: &InteractionOutcome,
+    ) -> f32 {
+        self.ensure_arms();
+        let reward = outcome.reward();
+        let arm = action.index();
+        adaptive_learning::update(&mut self.preference_weights[arm], reward, self.learning_rate);
+        self.action_pulls[arm] += 1;
+        if let Some(adaptation) = self
+            .behavior_adaptations
+            .iter_mut()
+            .find(|a| a.adaptation_type == event_type && a.response_action == action.as_str())
+        {
+            adaptation.trials += 1;
+            adaptation.effectiveness += (reward - adaptation.effectiveness) / adaptation.trials as f32;
         }
+        reward
+    }
+
+    /// Pad or trim weights and pull counts to one per action
+    fn ensure_arms(&mut self) {
+        self.preference_weights.resize(ResponseAction::ALL.len(), 0.5);
+        self.action_pulls.resize(ResponseAction::ALL.len(), 0);
     }
 }
 
@@ -308,6 +574,89 @@ mod tests {
         assert_eq!(state.evolution_stage, "thrive");
     }
     
+    #[test]
+    fn test_evolve_logs_transitions() {
+        let mut state = InteractiveState::default();
+        let definition = EvolutionDefinition::from_json(r#"{
+            "collection_id": "calm-waters",
+            "stages": [
+                { "name": "still" },
+                {
+                    "name": "ripple",
+                    "conditions": [{ "metric": "Energy", "comparison": "AtLeast", "value": 0.7 }],
+                    "on_enter": [{ "param": "wave_height", "op": "Set", "value": 2.0 }]
+                }
+            ]
+        }"#).unwrap();
+        
+        // An unknown stage restarts from the collection's first stage
+        state.evolve(&definition, 1).unwrap();
+        assert_eq!(state.evolution_stage, "still");
+        
+        state.energy_level = 0.9;
+        state.evolve(&definition, 2).unwrap();
+        assert_eq!(state.evolution_stage, "ripple");
+        assert_eq!(state.visual_params["wave_height"], 2.0);
+        
+        // Ripple is one-way
+        state.energy_level = 0.1;
+        assert!(state.evolve(&definition, 3).unwrap().is_empty());
+        
+        let log: Vec<(&str, &str)> = state.evolution_log.iter().map(|t| (t.from.as_str(), t.to.as_str())).collect();
+        assert_eq!(log, vec![("seed", "still"), ("still", "ripple")]);
+    }
+    
+    #[test]
+    fn test_evolve_in_uses_the_collection_definition() {
+        let mut registry = EvolutionRegistry::default();
+        registry.register(EvolutionDefinition::from_json(r#"{
+            "collection_id": "calm-waters",
+            "stages": [{ "name": "still" }]
+        }"#).unwrap()).unwrap();
+        
+        let mut state = InteractiveState::default();
+        state.evolve_in(&registry, "calm-waters", 1).unwrap();
+        assert_eq!(state.evolution_stage, "still");
+        
+        // Unregistered collections keep the default stages
+        let mut other = InteractiveState::default();
+        assert!(other.evolve_in(&registry, "unknown", 1).unwrap().is_empty());
+        assert_eq!(other.evolution_stage, "seed");
+        
+        let empty = EvolutionDefinition { collection_id: "empty".to_string(), stages: vec![] };
+        assert!(state.evolve(&empty, 2).is_err());
+        assert_eq!(state.evolution_stage, "still");
+    }
+    
+    #[test]
+    fn test_streak_breaks_and_energy_decays_lazily() {
+        let mut state = InteractiveState {
+            last_activity: 0,
+            ..Default::default()
+        };
+        let hour = NANOS_PER_HOUR;
+        for i in 0..5 {
+            state.record_activity(i * hour);
+        }
+        assert_eq!(state.interaction_streak, 5);
+        // Interactions restore energy faster than it decays
+        assert!(state.energy_at(4 * hour) > 0.8);
+        
+        // A day later energy has halved without any write
+        let later = 28 * hour;
+        assert!((state.energy_at(later) - state.energy_level / 2.0).abs() < 1e-6);
+        assert_eq!(state.streak_at(later), 5);
+        assert_eq!(state.evolution_metrics_at(later).streak, 5);
+        
+        // Past the timeout the streak reads as broken and restarts
+        let broken = 4 * hour + 49 * hour;
+        assert_eq!(state.streak_at(broken), 0);
+        assert_eq!(state.evolution_metrics_at(broken).streak, 0);
+        state.record_activity(broken);
+        assert_eq!(state.interaction_streak, 1);
+        assert_eq!(state.last_activity, broken);
+    }
+    
     #[test]
     fn test_update_interaction_patterns() {
         let mut state = InteractiveState::default();
@@ -331,6 +680,114 @@ mod tests {
         assert_eq!(state.interaction_patterns[0].frequency, 1);
     }
     
+    #[test]
+    fn test_pattern_signature_statistics() {
+        let mut state = InteractiveState::default();
+        // 2024-01-01 09:00 UTC, a Monday
+        let monday_9am = 1_704_099_600 * 1_000_000_000;
+        for (i, (event_type, valence)) in [("view", 0.3), ("like", 0.0), ("view", 0.6), ("like", 0.0), ("view", 0.9)]
+            .iter()
+            .enumerate()
+        {
+            let event = InteractionEvent {
+                event_type: event_type.to_string(),
+                timestamp: monday_9am + i as u64 * 60_000_000_000,
+                user_id: "user.testnet".parse().unwrap(),
+                data: "{}".to_string(),
+                intensity: 0.5,
+                emotional_impact: Some(EmotionalImpact {
+                    valence_shift: *valence,
+                    arousal_shift: 0.5,
+                    dominance_shift: 0.5,
+                    confidence: 0.8,
+                }),
+            };
+            state.update_interaction_patterns(&event);
+        }
+        
+        // Every event counts equally rather than the latest counting half
+        let view = &state.interaction_patterns[0];
+        assert_eq!(view.frequency, 3);
+        assert!((view.emotional_signature.avg_valence - 0.6).abs() < 1e-6);
+        assert!((view.emotional_signature.stats[0].variance() - 0.06).abs() < 1e-6);
+        assert_eq!(view.hour_histogram[9], 3);
+        assert_eq!(view.weekday_histogram[0], 3);
+        
+        let sequences = state.sequence_patterns.frequent(2);
+        assert!(sequences.iter().any(|s| s.events == vec!["view", "like"] && s.count == 2));
+    }
+    
+    #[test]
+    fn test_history_summary_trend_uses_timestamps() {
+        let mut state = InteractiveState::default();
+        let now = 1_000 * NANOS_PER_HOUR;
+        let view = |timestamp| InteractionEvent {
+            event_type: "view".to_string(),
+            timestamp,
+            user_id: "user.testnet".parse().unwrap(),
+            data: "{}".to_string(),
+            intensity: 0.5,
+            emotional_impact: None,
+        };
+        
+        // Lots of events, but all from a single minute: no prior day to compare
+        let burst: Vec<InteractionEvent> = (0..20).map(|i| view(now - i)).collect();
+        state.update_interaction_history_summary_at(&burst, now);
+        assert_eq!(state.interaction_history_summary.engagement_trend, EngagementTrend::InsufficientData);
+        
+        // One event an hour yesterday, three an hour today
+        let rising: Vec<InteractionEvent> = (0..48u64)
+            .flat_map(|h| {
+                let per_hour = if h < 24 { 3 } else { 1 };
+                (0..per_hour).map(move |i| now - h * NANOS_PER_HOUR - (i + 1) * 60_000_000_000)
+            })
+            .map(view)
+            .collect();
+        state.update_interaction_history_summary_at(&rising, now);
+        let summary = &state.interaction_history_summary;
+        assert_eq!(summary.engagement_trend, EngagementTrend::Increasing);
+        assert_eq!((summary.recent_rate, summary.prior_rate), (3.0, 1.0));
+    }
+    
+    #[test]
+    fn test_adapt_behavior_learns_from_outcomes() {
+        let mut state = InteractiveState::default();
+        let behavior = &mut state.adaptive_behavior;
+        // Viewers linger after calm responses and leave after everything else
+        for _ in 0..100 {
+            let action = behavior.respond_to("view");
+            let outcome = InteractionOutcome {
+                dwell_ms: if action == ResponseAction::CalmResponse { 30_000 } else { 0 },
+                intensity: 0.5,
+                emotional_impact: 0.0,
+            };
+            behavior.record_outcome("view", action, &outcome);
+        }
+        behavior.exploration = 0.0;
+        assert_eq!(behavior.choose_action(), ResponseAction::CalmResponse);
+        assert!(behavior.action_pulls[ResponseAction::CalmResponse.index()] > 50);
+        
+        // Events are scored against the response in force for their type
+        let event = InteractionEvent {
+            event_type: "like".to_string(),
+            timestamp: 0,
+            user_id: "user.testnet".parse().unwrap(),
+            data: r#"{"dwell_ms": 10000}"#.to_string(),
+            intensity: 1.0,
+            emotional_impact: Some(EmotionalImpact {
+                valence_shift: 1.0,
+                arousal_shift: 0.0,
+                dominance_shift: 0.0,
+                confidence: 1.0,
+            }),
+        };
+        state.adapt_behavior(&[event]);
+        let like = state.adaptive_behavior.adaptation("like").unwrap();
+        assert_eq!(like.response_action, "calm_response");
+        assert_eq!(like.trials, 1);
+        assert!((like.effectiveness - 0.8).abs() < 1e-6);
+    }
+    
     #[test]
     fn test_update_community_engagement() {
         let mut state = InteractiveState::default();
@@ -339,5 +796,48 @@ mod tests {
         state.update_community_engagement(&user_id);
         assert_eq!(state.community_engagement.total_interactions, 1);
         assert_eq!(state.community_engagement.unique_users, 1);
+        
+        // Repeated interactions from the same account are not new users
+        for _ in 0..10 {
+            state.update_community_engagement(&user_id);
+        }
+        assert_eq!(state.community_engagement.total_interactions, 11);
+        assert_eq!(state.community_engagement.unique_users, 1);
+        
+        let other: AccountId = "other.testnet".parse().unwrap();
+        state.update_community_engagement(&other);
+        assert_eq!(state.community_engagement.unique_users, 2);
+    }
+
+    #[test]
+    fn test_community_tracker_survives_json_round_trip() {
+        let mut state = InteractiveState::default();
+        let user_id: AccountId = "user.testnet".parse().unwrap();
+        state.update_community_engagement(&user_id);
+
+        let json = near_sdk::serde_json::to_string(&state).unwrap();
+        let mut state: InteractiveState = near_sdk::serde_json::from_str(&json).unwrap();
+        state.update_community_engagement(&user_id);
+        assert_eq!(state.community_engagement.unique_users, 1);
+    }
+    
+    #[test]
+    fn test_trending_resets_after_burst() {
+        let mut state = InteractiveState::default();
+        let minute = 60 * 1_000_000_000;
+        
+        // A burst of 30 interactions from 3 accounts over 15 minutes
+        for i in 0..30u64 {
+            let user: AccountId = format!("fan{}.testnet", i % 3).parse().unwrap();
+            state.update_community_engagement_at(&user, i * minute / 2);
+        }
+        assert!(state.community_engagement.trending);
+        assert_eq!(state.community_engagement.unique_users, 3);
+        assert_eq!(state.community_engagement.hourly_rate, 30.0);
+        
+        // Without further activity trending lapses
+        state.refresh_community_engagement(12 * NANOS_PER_HOUR);
+        assert!(!state.community_engagement.trending);
+        assert_eq!(state.community_engagement.hourly_rate, 0.0);
     }
 }
\ No newline at end of file
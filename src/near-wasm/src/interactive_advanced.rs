@@ -5,8 +5,16 @@
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near, AccountId, Timestamp};
-use near_sdk::collections::{LookupMap, UnorderedMap, Vector};
+use near_sdk::{env, AccountId, Timestamp};
+use near_sdk::collections::{LookupMap, Vector};
+use emotion_model::compat::ExtendedDimensions;
+use emotion_model::validation::{sanitize_f32, validate_label, Sanitize, ValidationPolicy};
+use emotion_model::{Emotion, EmotionError, AROUSAL_RANGE, CONFIDENCE_RANGE, DOMINANCE_RANGE, VALENCE_RANGE};
+
+use crate::biometric_fusion::{self, FusedBiometrics, FusionConfig, ModalityContribution};
+use crate::emotion_palette::{EmotionPalette, Harmony};
+use crate::emotion_smoothing::{EmotionSmoother, SmoothingConfig};
+use crate::emotional_aggregation::{self, CollectiveMood, EmotionalAggregator};
 
 /// Interactive NFT with biometric integration
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -29,11 +37,24 @@ pub struct BiometricNFT {
     /// Current emotional resonance
     pub emotional_resonance: EmotionalResonance,
     
+    /// Collective mood of everyone who interacted
+    pub collective: EmotionalAggregator,
+    
+    /// Smooths incoming states before they modulate visuals
+    pub smoother: EmotionSmoother,
+    
+    /// Quality gating and weighting of sensor modalities
+    pub fusion: FusionConfig,
+    
     /// NFT metadata
     pub metadata: InteractiveMetadata,
     
     /// Privacy settings
     pub privacy: PrivacySettings,
+    
+    /// Interactions from this index on may predate the privacy settings;
+    /// they are redacted as they are read until `redact_history` reaches them
+    pub redaction_cursor: u64,
 }
 
 /// Visual state of the NFT
@@ -70,6 +91,12 @@ pub struct ColorRGB {
     pub b: u8,
 }
 
+impl From<[u8; 3]> for ColorRGB {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self { r, g, b }
+    }
+}
+
 /// Shader uniform parameter
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -89,6 +116,12 @@ pub struct EmotionalInteraction {
     pub interaction_type: InteractionType,
     pub state_before: VisualStateSnapshot,
     pub state_after: VisualStateSnapshot,
+    /// Whether `user` has been replaced by a random ID
+    #[serde(default)]
+    pub anonymized: bool,
+    /// Sensor modalities the stress, relaxation and engagement came from
+    #[serde(default)]
+    pub contributions: Vec<ModalityContribution>,
 }
 
 /// Detailed emotional state with multiple dimensions
@@ -116,6 +149,98 @@ pub struct DetailedEmotionalState {
     pub intensity: f32,
 }
 
+impl From<DetailedEmotionalState> for Emotion {
+    fn from(s: DetailedEmotionalState) -> Self {
+        Emotion::new(s.valence, s.arousal, s.dominance)
+            .with_confidence(s.confidence)
+            .with_label(s.primary_emotion)
+    }
+}
+
+/// Live biometric readings drift slightly out of range, so clamp them
+const EMOTION_POLICY: ValidationPolicy = ValidationPolicy::Clamp;
+const UNIT_RANGE: (f32, f32) = (0.0, 1.0);
+
+impl Sanitize for DetailedEmotionalState {
+    fn sanitize(self, policy: ValidationPolicy) -> Result<Self, EmotionError> {
+        validate_label("primary_emotion", &self.primary_emotion)?;
+        Ok(Self {
+            valence: sanitize_f32("valence", self.valence, VALENCE_RANGE, policy)?,
+            arousal: sanitize_f32("arousal", self.arousal, AROUSAL_RANGE, policy)?,
+            dominance: sanitize_f32("dominance", self.dominance, DOMINANCE_RANGE, policy)?,
+            engagement: sanitize_f32("engagement", self.engagement, UNIT_RANGE, policy)?,
+            focus: sanitize_f32("focus", self.focus, UNIT_RANGE, policy)?,
+            stress: sanitize_f32("stress", self.stress, UNIT_RANGE, policy)?,
+            relaxation: sanitize_f32("relaxation", self.relaxation, UNIT_RANGE, policy)?,
+            confidence: sanitize_f32("confidence", self.confidence, CONFIDENCE_RANGE, policy)?,
+            intensity: sanitize_f32("intensity", self.intensity, UNIT_RANGE, policy)?,
+            primary_emotion: self.primary_emotion,
+        })
+    }
+}
+
+impl DetailedEmotionalState {
+    /// Resting state from calibration readings: the per-dimension median,
+    /// so one startled reading does not skew the baseline
+    pub fn baseline(readings: &[DetailedEmotionalState]) -> Option<Self> {
+        if readings.is_empty() {
+            return None;
+        }
+        let median = |f: fn(&DetailedEmotionalState) -> f32| {
+            emotional_aggregation::median(readings.iter().map(f).collect())
+        };
+        Some(Self {
+            valence: median(|s| s.valence),
+            arousal: median(|s| s.arousal),
+            dominance: median(|s| s.dominance),
+            engagement: median(|s| s.engagement),
+            focus: median(|s| s.focus),
+            stress: median(|s| s.stress),
+            relaxation: median(|s| s.relaxation),
+            confidence: median(|s| s.confidence),
+            primary_emotion: "baseline".to_string(),
+            intensity: median(|s| s.intensity),
+        })
+    }
+
+    /// This state as a deviation from `baseline`, re-centred on the neutral
+    /// point of each dimension (0 for valence, 0.5 otherwise) and clamped
+    pub fn relative_to(&self, baseline: &DetailedEmotionalState) -> Self {
+        let shift = |value: f32, rest: f32, neutral: f32, (min, max): (f32, f32)| {
+            (value - rest + neutral).clamp(min, max)
+        };
+        Self {
+            valence: shift(self.valence, baseline.valence, 0.0, VALENCE_RANGE),
+            arousal: shift(self.arousal, baseline.arousal, 0.5, AROUSAL_RANGE),
+            dominance: shift(self.dominance, baseline.dominance, 0.5, DOMINANCE_RANGE),
+            engagement: shift(self.engagement, baseline.engagement, 0.5, UNIT_RANGE),
+            focus: shift(self.focus, baseline.focus, 0.5, UNIT_RANGE),
+            stress: shift(self.stress, baseline.stress, 0.5, UNIT_RANGE),
+            relaxation: shift(self.relaxation, baseline.relaxation, 0.5, UNIT_RANGE),
+            ..self.clone()
+        }
+    }
+}
+
+/// Extended dimensions are estimated from VAD since `Emotion` does not carry them
+impl From<Emotion> for DetailedEmotionalState {
+    fn from(e: Emotion) -> Self {
+        let extended = ExtendedDimensions::estimate(&e);
+        Self {
+            valence: e.valence,
+            arousal: e.arousal,
+            dominance: e.dominance,
+            engagement: extended.engagement,
+            focus: extended.focus,
+            stress: extended.stress,
+            relaxation: extended.relaxation,
+            confidence: e.confidence,
+            primary_emotion: e.label.unwrap_or_default(),
+            intensity: extended.intensity,
+        }
+    }
+}
+
 /// Biometric snapshot from sensors
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -224,10 +349,27 @@ pub struct VisualStateSnapshot {
 #[serde(crate = "near_sdk::serde")]
 pub struct BiometricProfile {
     pub account: AccountId,
+    /// The user's resting state; incoming states are read relative to it
     pub baseline_state: DetailedEmotionalState,
     pub interaction_count: u32,
     pub total_interaction_time: u64,
     pub favorite_states: Vec<VisualStateSnapshot>,
+    /// When the user consented to biometric processing
+    pub consent_given_at: Timestamp,
+    /// Set by the token owner; only authorized profiles may interact
+    pub authorized: bool,
+    /// Readings collected so far; cleared once the baseline is computed
+    pub calibration_readings: Vec<DetailedEmotionalState>,
+    pub calibrated: bool,
+}
+
+/// Public summary of a profile, without biometric values
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProfileStatus {
+    pub authorized: bool,
+    pub calibrated: bool,
+    pub calibration_readings: u32,
 }
 
 /// Emotional resonance of the NFT
@@ -289,12 +431,44 @@ pub struct InteractionRules {
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PrivacySettings {
+    /// Keep raw sensor readings; otherwise only quality and a hash of the
+    /// recording CID are stored
     pub store_biometric_data: bool,
+    /// Let anyone read the interaction history, not just the owner and
+    /// authorized profiles
     pub share_interaction_history: bool,
+    /// Feed interactions into the collective mood
     pub allow_emotional_analytics: bool,
+    /// Store a random ID per interaction instead of the viewer's account.
+    /// The collective mood then counts each interaction as its own viewer,
+    /// so per-viewer weight caps no longer span a viewer's interactions.
     pub anonymize_data: bool,
 }
 
+/// Calibration readings needed before a profile's baseline is set
+pub const CALIBRATION_READINGS: usize = 5;
+
+/// Marks a recording CID replaced by its hash
+const REDACTED_CID_PREFIX: &str = "sha256:";
+
+/// Storage prefix for one of a token's collections: a tag byte followed by
+/// the hash of the token ID, so many tokens can share one contract
+fn storage_prefix(tag: u8, token_id: &str) -> Vec<u8> {
+    let mut prefix = vec![tag];
+    prefix.extend(env::sha256(token_id.as_bytes()));
+    prefix
+}
+
+/// Stand-in for a viewer account: an implicit account ID hashed from the
+/// block's random seed and the interaction's index, so it carries nothing
+/// linking it to the account or to the viewer's other interactions
+fn anonymous_id(index: u64) -> AccountId {
+    let mut seed = env::random_seed();
+    seed.extend(index.to_le_bytes());
+    let hex: String = env::sha256(&seed).iter().map(|b| format!("{:02x}", b)).collect();
+    hex.parse().unwrap_or_else(|_| env::panic_str("Invalid anonymous ID"))
+}
+
 impl BiometricNFT {
     /// Create a new biometric NFT
     pub fn new(
@@ -303,12 +477,15 @@ impl BiometricNFT {
         metadata: InteractiveMetadata,
     ) -> Self {
         Self {
+            interaction_history: Vector::new(storage_prefix(b'h', &token_id)),
+            authorized_profiles: LookupMap::new(storage_prefix(b'a', &token_id)),
             token_id,
             owner,
             visual_state: VisualState::default(),
-            interaction_history: Vector::new(b"h"),
-            authorized_profiles: LookupMap::new(b"a"),
             emotional_resonance: EmotionalResonance::default(),
+            collective: EmotionalAggregator::default(),
+            smoother: EmotionSmoother::default(),
+            fusion: FusionConfig::default(),
             metadata,
             privacy: PrivacySettings {
                 store_biometric_data: true,
@@ -316,7 +493,195 @@ impl BiometricNFT {
                 allow_emotional_analytics: true,
                 anonymize_data: false,
             },
+            redaction_cursor: 0,
+        }
+    }
+
+    /// Apply the privacy settings to the interaction stored at `index`
+    fn redact(&self, interaction: &mut EmotionalInteraction, index: u64) {
+        if !self.privacy.store_biometric_data {
+            let snapshot = &mut interaction.biometric_data;
+            snapshot.eeg_data = None;
+            snapshot.heart_rate = None;
+            snapshot.gsr = None;
+            snapshot.facial_data = None;
+            // A hash still lets the viewer prove which recording was used
+            if !snapshot.data_cid.is_empty() && !snapshot.data_cid.starts_with(REDACTED_CID_PREFIX) {
+                let digest = env::sha256(snapshot.data_cid.as_bytes());
+                let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+                snapshot.data_cid = format!("{}{}", REDACTED_CID_PREFIX, hex);
+            }
+        }
+        if self.privacy.anonymize_data && !interaction.anonymized {
+            interaction.user = anonymous_id(index);
+            interaction.anonymized = true;
+        }
+    }
+
+    /// Whether `viewer` may read the interaction history
+    pub fn can_view_history(&self, viewer: &AccountId) -> bool {
+        self.privacy.share_interaction_history
+            || *viewer == self.owner
+            || self.authorized_profiles.get(viewer).is_some_and(|p| p.authorized)
+    }
+
+    /// Register the caller's biometric profile; requires explicit consent
+    /// and awaits calibration and the owner's authorization
+    pub fn register_profile(&mut self, consent: bool) {
+        if !consent {
+            env::panic_str("Consent is required to register a biometric profile");
+        }
+        let account = env::predecessor_account_id();
+        if self.authorized_profiles.contains_key(&account) {
+            env::panic_str("Profile already registered");
+        }
+        let profile = BiometricProfile {
+            account: account.clone(),
+            baseline_state: DetailedEmotionalState::from(Emotion::new(0.0, 0.5, 0.5)),
+            interaction_count: 0,
+            total_interaction_time: 0,
+            favorite_states: Vec::new(),
+            consent_given_at: env::block_timestamp(),
+            authorized: false,
+            calibration_readings: Vec::new(),
+            calibrated: false,
+        };
+        self.authorized_profiles.insert(&account, &profile);
+    }
+
+    /// Add a resting reading to the caller's calibration, returning how
+    /// many more are needed; the baseline is set from the last batch
+    ///
+    /// Recalibrating replaces the baseline every later interaction is read
+    /// against, so it also withdraws authorization until the owner grants
+    /// it again.
+    pub fn submit_calibration(&mut self, reading: DetailedEmotionalState) -> u32 {
+        let reading = reading
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid calibration reading: {}", e)));
+        let account = env::predecessor_account_id();
+        let mut profile = self
+            .authorized_profiles
+            .get(&account)
+            .unwrap_or_else(|| env::panic_str("Profile not registered"));
+        profile.calibration_readings.push(reading);
+        if profile.calibration_readings.len() >= CALIBRATION_READINGS {
+            if let Some(baseline) = DetailedEmotionalState::baseline(&profile.calibration_readings) {
+                profile.baseline_state = baseline;
+            }
+            profile.calibration_readings.clear();
+            if profile.calibrated {
+                profile.authorized = false;
+            }
+            profile.calibrated = true;
+        }
+        let remaining = if profile.calibration_readings.is_empty() {
+            0
+        } else {
+            CALIBRATION_READINGS - profile.calibration_readings.len()
+        };
+        self.authorized_profiles.insert(&account, &profile);
+        remaining as u32
+    }
+
+    /// Let a registered profile interact (owner only)
+    pub fn authorize_profile(&mut self, account: AccountId) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can authorize profiles"
+        );
+        let mut profile = self
+            .authorized_profiles
+            .get(&account)
+            .unwrap_or_else(|| env::panic_str("Profile not registered"));
+        profile.authorized = true;
+        self.authorized_profiles.insert(&account, &profile);
+    }
+
+    /// Remove a profile and its baseline (owner only)
+    pub fn revoke_profile(&mut self, account: AccountId) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can revoke profiles"
+        );
+        if self.authorized_profiles.remove(&account).is_none() {
+            env::panic_str("Profile not registered");
+        }
+    }
+
+    pub fn profile_status(&self, account: &AccountId) -> Option<ProfileStatus> {
+        self.authorized_profiles.get(account).map(|p| ProfileStatus {
+            authorized: p.authorized,
+            calibrated: p.calibrated,
+            calibration_readings: p.calibration_readings.len() as u32,
+        })
+    }
+
+    /// Page of the interaction history, oldest first
+    ///
+    /// Private histories need a signed call from the owner or an
+    /// authorized profile; view calls cannot identify the caller.
+    pub fn interaction_history_page(&self, from_index: u64, limit: u64) -> Vec<EmotionalInteraction> {
+        // Public histories skip the caller lookup so view calls work
+        if !self.privacy.share_interaction_history
+            && !self.can_view_history(&env::predecessor_account_id())
+        {
+            env::panic_str("Interaction history is private");
+        }
+        let end = from_index.saturating_add(limit).min(self.interaction_history.len());
+        (from_index..end)
+            .filter_map(|index| {
+                let mut interaction = self.interaction_history.get(index)?;
+                if index >= self.redaction_cursor {
+                    self.redact(&mut interaction, index);
+                }
+                Some(interaction)
+            })
+            .collect()
+    }
+
+    /// Change privacy settings (owner only)
+    ///
+    /// New interactions are stored redacted right away; stored ones are
+    /// redacted as they are read and rewritten by `redact_history`.
+    pub fn set_privacy_settings(&mut self, privacy: PrivacySettings) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can change privacy settings"
+        );
+        let newly_anonymized = privacy.anonymize_data && !self.privacy.anonymize_data;
+        self.privacy = privacy;
+        if !self.privacy.store_biometric_data || self.privacy.anonymize_data {
+            self.redaction_cursor = 0;
+        }
+        // Viewers are tracked by account until now; starting over keeps
+        // anyone from being counted again under a random ID
+        if newly_anonymized || !self.privacy.allow_emotional_analytics {
+            self.collective.viewers.clear();
+        }
+    }
+
+    /// Rewrite up to `limit` stored interactions from the redaction cursor
+    /// to match the privacy settings (owner only), returning how many remain
+    pub fn redact_history(&mut self, limit: u64) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can redact interaction history"
+        );
+        let len = self.interaction_history.len();
+        let end = self.redaction_cursor.saturating_add(limit).min(len);
+        for index in self.redaction_cursor..end {
+            if let Some(mut interaction) = self.interaction_history.get(index) {
+                self.redact(&mut interaction, index);
+                self.interaction_history.replace(index, &interaction);
+            }
         }
+        self.redaction_cursor = end;
+        len - end
     }
 
     /// Interact with NFT using real-time biometric data
@@ -326,19 +691,56 @@ impl BiometricNFT {
         biometric_data: BiometricSnapshot,
         interaction_type: InteractionType,
     ) {
+        let emotional_state = emotional_state
+            .sanitize(EMOTION_POLICY)
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid emotional state: {}", e)));
+        let fused = biometric_fusion::fuse(&biometric_data, &emotional_state, &self.fusion)
+            .unwrap_or_else(|e| env::panic_str(&format!("Biometric data rejected: {}", e)));
+        // Sensor-derived dimensions replace the reported ones so every
+        // interaction with sensors derives them the same way
+        let emotional_state = DetailedEmotionalState {
+            stress: fused.stress,
+            relaxation: fused.relaxation,
+            engagement: fused.engagement,
+            ..emotional_state
+        };
         let user = env::predecessor_account_id();
+        let mut profile = self
+            .authorized_profiles
+            .get(&user)
+            .unwrap_or_else(|| env::panic_str("Profile not registered"));
+        if !profile.authorized {
+            env::panic_str("Profile not authorized");
+        }
+        if !profile.calibrated {
+            env::panic_str("Profile not calibrated");
+        }
         
         // Capture state before interaction
         let state_before = self.capture_state_snapshot();
         
+        // Read the state relative to this user's resting baseline, then
+        // smooth it and settle its category before it drives visuals
+        let relative = emotional_state.relative_to(&profile.baseline_state);
+        let smoothed = self
+            .smoother
+            .apply(&Emotion::from(relative.clone()), env::block_timestamp());
+        let displayed = DetailedEmotionalState {
+            valence: smoothed.emotion.valence,
+            arousal: smoothed.emotion.arousal,
+            dominance: smoothed.emotion.dominance,
+            primary_emotion: smoothed.label().to_string(),
+            ..relative
+        };
+        
         // Apply emotional modulation to visual state
-        self.apply_emotional_modulation(&emotional_state, &biometric_data);
+        self.apply_emotional_modulation(&displayed, &fused);
         
         // Capture state after interaction
         let state_after = self.capture_state_snapshot();
         
         // Record interaction
-        let interaction = EmotionalInteraction {
+        let mut interaction = EmotionalInteraction {
             timestamp: env::block_timestamp(),
             user: user.clone(),
             emotional_state,
@@ -346,19 +748,48 @@ impl BiometricNFT {
             interaction_type,
             state_before,
             state_after,
+            anonymized: false,
+            contributions: fused.contributions,
         };
         
+        self.redact(&mut interaction, self.interaction_history.len());
         self.interaction_history.push(&interaction);
         
+        profile.interaction_count += 1;
+        self.authorized_profiles.insert(&user, &profile);
+        
         // Update emotional resonance
         self.update_resonance(&interaction);
     }
 
+    /// Configure smoothing and category hysteresis (owner only)
+    pub fn set_smoothing_config(&mut self, config: SmoothingConfig) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can configure smoothing"
+        );
+        self.smoother.set_config(config);
+    }
+
+    /// Configure quality gating and modality weights (owner only)
+    pub fn set_fusion_config(&mut self, config: FusionConfig) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner,
+            "Only owner can configure sensor fusion"
+        );
+        config
+            .validate()
+            .unwrap_or_else(|e| env::panic_str(&format!("Invalid fusion config: {}", e)));
+        self.fusion = config;
+    }
+
     /// Apply emotional modulation to visual parameters
     fn apply_emotional_modulation(
         &mut self,
         emotion: &DetailedEmotionalState,
-        biometric: &BiometricSnapshot,
+        biometric: &FusedBiometrics,
     ) {
         let rules = &self.metadata.interaction_rules;
         let sensitivity = rules.sensitivity;
@@ -368,16 +799,18 @@ impl BiometricNFT {
             self.visual_state.color_intensity = 
                 0.5 + (emotion.valence * sensitivity * 0.5);
             
-            // Shift color palette based on valence
-            for color in &mut self.visual_state.color_palette {
-                if emotion.valence > 0.0 {
-                    // Warm colors for positive valence
-                    color.r = (color.r as f32 * (1.0 + emotion.valence * 0.3)) as u8;
-                } else {
-                    // Cool colors for negative valence
-                    color.b = (color.b as f32 * (1.0 - emotion.valence * 0.3)) as u8;
-                }
-            }
+            // Regenerate the palette: warm for positive valence, cool for negative
+            let modulated = Emotion::new(
+                emotion.valence * sensitivity,
+                emotion.arousal,
+                emotion.dominance,
+            );
+            let size = self.visual_state.color_palette.len().max(2);
+            self.visual_state.color_palette = EmotionPalette::generate(&modulated, size, Harmony::Analogous)
+                .to_rgb8()
+                .into_iter()
+                .map(ColorRGB::from)
+                .collect();
         }
         
         // Arousal affects animation speed
@@ -392,11 +825,9 @@ impl BiometricNFT {
                 (50.0 + emotion.dominance * 150.0) as u32;
         }
         
-        // Meditation (from EEG) affects morphing
+        // Relaxation fused from every usable sensor affects morphing
         if rules.meditation_affects_morphing {
-            if let Some(ref eeg) = biometric.eeg_data {
-                self.visual_state.morphing_rate = eeg.meditation * sensitivity;
-            }
+            self.visual_state.morphing_rate = biometric.relaxation * sensitivity;
         }
         
         // Stress affects complexity
@@ -417,13 +848,25 @@ impl BiometricNFT {
         }
     }
 
+    /// Collective mood of all viewers and its distribution
+    pub fn get_collective_mood(&self) -> CollectiveMood {
+        self.collective.collective(env::block_timestamp())
+    }
+
     /// Update emotional resonance
     fn update_resonance(&mut self, interaction: &EmotionalInteraction) {
         self.emotional_resonance.resonance_level += 
             interaction.emotional_state.intensity * 0.1;
         
-        self.emotional_resonance.dominant_emotion = 
-            interaction.emotional_state.primary_emotion.clone();
+        if self.privacy.allow_emotional_analytics {
+            self.collective.record(
+                &interaction.user,
+                &Emotion::from(interaction.emotional_state.clone()),
+                interaction.timestamp,
+            );
+            self.emotional_resonance.dominant_emotion =
+                self.collective.collective(interaction.timestamp).label;
+        }
         
         let n = self.emotional_resonance.avg_intensity;
         self.emotional_resonance.avg_intensity = 
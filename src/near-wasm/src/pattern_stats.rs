@@ -0,0 +1,221 @@
+//! Pattern statistics - Incremental statistics for interaction patterns
+//!
+//! Welford running mean and variance, time-decayed means, n-gram counts
+//! over event-type sequences and UTC time-of-day/day-of-week helpers.
+
+use std::collections::VecDeque;
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Timestamp;
+
+const NANOS_PER_HOUR: u64 = 3_600 * 1_000_000_000;
+const NANOS_PER_DAY: u64 = 24 * NANOS_PER_HOUR;
+/// Shortest and longest event-type sequences counted
+pub const MIN_NGRAM: usize = 2;
+pub const MAX_NGRAM: usize = 3;
+/// Distinct sequences kept before the rarest is evicted
+const MAX_SEQUENCES: usize = 64;
+
+/// Welford's online mean and variance
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RunningStats {
+    pub count: u64,
+    pub mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn push(&mut self, value: f32) {
+        let x = value as f64;
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Population variance, 0 until there are two samples
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / self.count as f64
+        }
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+/// Mean in which each sample's weight halves every `half_life_ns`
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DecayedMean {
+    pub value: f32,
+    weight: f32,
+    updated: Timestamp,
+}
+
+impl DecayedMean {
+    pub fn push(&mut self, value: f32, now: Timestamp, half_life_ns: u64) {
+        let elapsed = now.saturating_sub(self.updated) as f32;
+        let decay = 0.5f32.powf(elapsed / half_life_ns.max(1) as f32);
+        let weight = self.weight * decay;
+        self.value = (self.value * weight + value) / (weight + 1.0);
+        self.weight = weight + 1.0;
+        self.updated = self.updated.max(now);
+    }
+}
+
+/// Count of one event-type sequence
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SequencePattern {
+    pub events: Vec<String>,
+    pub count: u32,
+    pub last_seen: Timestamp,
+}
+
+/// N-gram counts over the stream of event types
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SequenceDetector {
+    recent: VecDeque<String>,
+    sequences: Vec<SequencePattern>,
+}
+
+impl SequenceDetector {
+    /// Record the next event type, counting every n-gram it completes
+    pub fn push(&mut self, event_type: &str, now: Timestamp) {
+        self.recent.push_back(event_type.to_string());
+        if self.recent.len() > MAX_NGRAM {
+            self.recent.pop_front();
+        }
+        for n in MIN_NGRAM..=self.recent.len() {
+            let events: Vec<String> = self
+                .recent
+                .iter()
+                .skip(self.recent.len() - n)
+                .cloned()
+                .collect();
+            match self.sequences.iter_mut().find(|s| s.events == events) {
+                Some(sequence) => {
+                    sequence.count += 1;
+                    sequence.last_seen = now;
+                }
+                None => {
+                    if self.sequences.len() >= MAX_SEQUENCES {
+                        self.evict();
+                    }
+                    self.sequences.push(SequencePattern {
+                        events,
+                        count: 1,
+                        last_seen: now,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Sequences seen at least `min_count` times, most frequent first
+    pub fn frequent(&self, min_count: u32) -> Vec<&SequencePattern> {
+        let mut frequent: Vec<&SequencePattern> = self
+            .sequences
+            .iter()
+            .filter(|s| s.count >= min_count)
+            .collect();
+        frequent.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(b.events.len().cmp(&a.events.len()))
+        });
+        frequent
+    }
+
+    /// Drop the rarest, then stalest, sequence
+    fn evict(&mut self) {
+        if let Some(index) = self
+            .sequences
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, s)| (s.count, s.last_seen))
+            .map(|(i, _)| i)
+        {
+            self.sequences.swap_remove(index);
+        }
+    }
+}
+
+/// UTC hour of day, 0-23
+pub fn hour_of_day(timestamp: Timestamp) -> usize {
+    ((timestamp % NANOS_PER_DAY) / NANOS_PER_HOUR) as usize
+}
+
+/// UTC day of week, Monday = 0
+pub fn day_of_week(timestamp: Timestamp) -> usize {
+    // The Unix epoch fell on a Thursday
+    ((timestamp / NANOS_PER_DAY + 3) % 7) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_stats() {
+        let mut stats = RunningStats::default();
+        for x in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.push(x);
+        }
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.variance(), 4.0);
+        assert_eq!(stats.std_dev(), 2.0);
+    }
+
+    #[test]
+    fn test_decayed_mean_favours_recent() {
+        let mut mean = DecayedMean::default();
+        let hour = NANOS_PER_HOUR;
+        mean.push(0.0, 0, hour);
+        mean.push(1.0, 10 * hour, hour);
+        assert!(mean.value > 0.99);
+
+        let mut even = DecayedMean::default();
+        even.push(0.0, 0, hour);
+        even.push(1.0, 0, hour);
+        assert_eq!(even.value, 0.5);
+    }
+
+    #[test]
+    fn test_sequence_detection() {
+        let mut detector = SequenceDetector::default();
+        for (i, event) in ["view", "like", "share", "view", "like", "share", "view"]
+            .iter()
+            .enumerate()
+        {
+            detector.push(event, i as u64);
+        }
+        let frequent = detector.frequent(2);
+        assert_eq!(frequent[0].events, vec!["view", "like", "share"]);
+        assert_eq!(frequent[0].count, 2);
+        assert!(frequent
+            .iter()
+            .any(|s| s.events == vec!["view", "like"] && s.count == 2));
+        assert!(detector.frequent(3).is_empty());
+    }
+
+    #[test]
+    fn test_calendar_helpers() {
+        // 2024-01-01 was a Monday
+        let new_year_2024 = 1_704_067_200 * 1_000_000_000;
+        assert_eq!(day_of_week(new_year_2024), 0);
+        assert_eq!(hour_of_day(new_year_2024 + 13 * NANOS_PER_HOUR), 13);
+        assert_eq!(day_of_week(0), 3);
+    }
+}
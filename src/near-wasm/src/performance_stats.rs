@@ -0,0 +1,479 @@
+//! Performance Stats - Rolling-window metrics for shader sessions
+//!
+//! Aggregates raw frame-time samples instead of trusting client-reported
+//! averages: mean and percentile frame times, dropped frame counts,
+//! compile-time history and GPU memory, all in bounded storage.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Frame-time samples kept per tracker
+pub const FRAME_WINDOW_SIZE: u32 = 600;
+/// Compile times kept per tracker
+pub const COMPILE_HISTORY_SIZE: u32 = 32;
+/// Client-reported fps averages kept per tracker
+pub const REPORTED_FPS_HISTORY_SIZE: u32 = 32;
+/// Shaders tracked individually per session
+pub const MAX_TRACKED_SHADERS: usize = 16;
+/// Frame budget at 60 fps
+pub const DEFAULT_TARGET_FRAME_MS: f32 = 1000.0 / 60.0;
+
+/// Fixed-capacity ring buffer of samples
+#[derive(BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde", try_from = "WindowParts")]
+pub struct RollingWindow {
+    capacity: u32,
+    values: Vec<f32>,
+    next: u32,
+}
+
+/// Stored form of a `RollingWindow`, checked before it is used
+#[derive(BorshDeserialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct WindowParts {
+    capacity: u32,
+    values: Vec<f32>,
+    next: u32,
+}
+
+impl TryFrom<WindowParts> for RollingWindow {
+    type Error = String;
+
+    fn try_from(parts: WindowParts) -> Result<Self, Self::Error> {
+        let len = parts.values.len();
+        let capacity = parts.capacity as usize;
+        // Until the window fills, `next` is where the next sample is appended
+        let consistent = parts.capacity > 0
+            && parts.next < parts.capacity
+            && (len == capacity || (len < capacity && parts.next as usize == len));
+        if !consistent {
+            return Err("Inconsistent rolling window".to_string());
+        }
+        Ok(Self {
+            capacity: parts.capacity,
+            values: parts.values,
+            next: parts.next,
+        })
+    }
+}
+
+impl BorshDeserialize for RollingWindow {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Self::try_from(WindowParts::deserialize_reader(reader)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Frame-time statistics against a frame budget
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FrameStats {
+    pub frame_times_ms: RollingWindow,
+    pub target_frame_ms: f32,
+    pub total_frames: u64,
+    pub dropped_frames: u64,
+    pub rejected_samples: u64,
+}
+
+/// Frame, compile and memory tracking for one scope (session or shader)
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PerformanceTracker {
+    pub frames: FrameStats,
+    /// Averages reported by clients, kept apart from the raw frame samples
+    #[serde(default = "reported_fps_window")]
+    pub reported_fps: RollingWindow,
+    pub compile_times_ms: RollingWindow,
+    pub last_compile_ms: Option<f32>,
+    pub gpu_memory_last_mb: f32,
+    pub gpu_memory_peak_mb: f32,
+}
+
+/// Tracker for a single shader within a session
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ShaderPerformance {
+    pub shader_id: String,
+    pub tracker: PerformanceTracker,
+}
+
+/// Session-wide tracker plus per-shader breakdown
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SessionPerformance {
+    pub session: PerformanceTracker,
+    pub shaders: Vec<ShaderPerformance>,
+}
+
+/// Summary view of a tracker
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PerformanceSummary {
+    pub sample_count: u32,
+    pub total_frames: u64,
+    pub mean_frame_ms: f32,
+    pub mean_fps: f32,
+    pub p50_frame_ms: f32,
+    pub p95_frame_ms: f32,
+    pub p99_frame_ms: f32,
+    pub worst_frame_ms: f32,
+    pub mean_reported_fps: f32,
+    pub dropped_frames: u64,
+    pub dropped_ratio: f32,
+    pub mean_compile_ms: f32,
+    pub max_compile_ms: f32,
+    pub last_compile_ms: Option<f32>,
+    pub gpu_memory_last_mb: f32,
+    pub gpu_memory_peak_mb: f32,
+}
+
+impl RollingWindow {
+    pub fn new(capacity: u32) -> Self {
+        assert!(capacity > 0, "Window capacity must be positive");
+        Self {
+            capacity,
+            values: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Add a sample, overwriting the oldest once full
+    pub fn push(&mut self, value: f32) {
+        if (self.values.len() as u32) < self.capacity {
+            self.values.push(value);
+        } else {
+            self.values[self.next as usize] = value;
+        }
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Samples from oldest to newest
+    pub fn ordered(&self) -> Vec<f32> {
+        if (self.values.len() as u32) < self.capacity {
+            return self.values.clone();
+        }
+        let split = self.next as usize;
+        self.values[split..]
+            .iter()
+            .chain(&self.values[..split])
+            .copied()
+            .collect()
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().sum::<f32>() / self.values.len() as f32
+    }
+
+    pub fn max(&self) -> f32 {
+        self.values.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Nearest-rank percentile (0.0 - 100.0)
+    pub fn percentile(&self, percentile: f32) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.values.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * sorted.len() as f32).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+}
+
+impl FrameStats {
+    pub fn new(target_frame_ms: f32) -> Self {
+        assert!(
+            target_frame_ms.is_finite() && target_frame_ms > 0.0,
+            "Frame budget must be positive and finite"
+        );
+        Self {
+            frame_times_ms: RollingWindow::new(FRAME_WINDOW_SIZE),
+            target_frame_ms,
+            total_frames: 0,
+            dropped_frames: 0,
+            rejected_samples: 0,
+        }
+    }
+
+    /// Record one raw frame time; non-finite or non-positive samples are rejected
+    pub fn record_frame_time(&mut self, frame_ms: f32) -> bool {
+        if !frame_ms.is_finite() || frame_ms <= 0.0 {
+            self.rejected_samples += 1;
+            return false;
+        }
+        self.frame_times_ms.push(frame_ms);
+        self.total_frames += 1;
+
+        // A frame spanning N budgets means N - 1 vsyncs were missed
+        let budgets = (frame_ms / self.target_frame_ms).round() as u64;
+        self.dropped_frames = self.dropped_frames.saturating_add(budgets.saturating_sub(1));
+        true
+    }
+}
+
+impl PerformanceTracker {
+    pub fn new() -> Self {
+        Self {
+            frames: FrameStats::new(DEFAULT_TARGET_FRAME_MS),
+            reported_fps: reported_fps_window(),
+            compile_times_ms: RollingWindow::new(COMPILE_HISTORY_SIZE),
+            last_compile_ms: None,
+            gpu_memory_last_mb: 0.0,
+            gpu_memory_peak_mb: 0.0,
+        }
+    }
+
+    /// Record a client's own fps average; it never enters the frame-time window
+    pub fn record_reported_fps(&mut self, fps: f32) {
+        if fps.is_finite() && fps > 0.0 {
+            self.reported_fps.push(fps);
+        }
+    }
+
+    pub fn record_compile_time(&mut self, compile_ms: f32) {
+        if compile_ms.is_finite() && compile_ms >= 0.0 {
+            self.compile_times_ms.push(compile_ms);
+            self.last_compile_ms = Some(compile_ms);
+        }
+    }
+
+    pub fn record_gpu_memory(&mut self, gpu_memory_mb: f32) {
+        if gpu_memory_mb.is_finite() && gpu_memory_mb >= 0.0 {
+            self.gpu_memory_last_mb = gpu_memory_mb;
+            self.gpu_memory_peak_mb = self.gpu_memory_peak_mb.max(gpu_memory_mb);
+        }
+    }
+
+    pub fn summary(&self) -> PerformanceSummary {
+        let frames = &self.frames;
+        let window = &frames.frame_times_ms;
+        let mean_frame_ms = window.mean();
+        let expected_frames = frames.total_frames + frames.dropped_frames;
+
+        PerformanceSummary {
+            sample_count: window.len() as u32,
+            total_frames: frames.total_frames,
+            mean_frame_ms,
+            mean_fps: if mean_frame_ms > 0.0 {
+                1000.0 / mean_frame_ms
+            } else {
+                0.0
+            },
+            p50_frame_ms: window.percentile(50.0),
+            p95_frame_ms: window.percentile(95.0),
+            p99_frame_ms: window.percentile(99.0),
+            worst_frame_ms: window.max(),
+            mean_reported_fps: self.reported_fps.mean(),
+            dropped_frames: frames.dropped_frames,
+            dropped_ratio: if expected_frames > 0 {
+                frames.dropped_frames as f32 / expected_frames as f32
+            } else {
+                0.0
+            },
+            mean_compile_ms: self.compile_times_ms.mean(),
+            max_compile_ms: self.compile_times_ms.max(),
+            last_compile_ms: self.last_compile_ms,
+            gpu_memory_last_mb: self.gpu_memory_last_mb,
+            gpu_memory_peak_mb: self.gpu_memory_peak_mb,
+        }
+    }
+}
+
+impl SessionPerformance {
+    pub fn new() -> Self {
+        Self {
+            session: PerformanceTracker::new(),
+            shaders: Vec::new(),
+        }
+    }
+
+    /// Tracker for a shader, evicting the oldest shader once the limit is reached
+    pub fn shader_mut(&mut self, shader_id: &str) -> &mut PerformanceTracker {
+        let index = match self.shaders.iter().position(|s| s.shader_id == shader_id) {
+            Some(index) => index,
+            None => {
+                if self.shaders.len() >= MAX_TRACKED_SHADERS {
+                    self.shaders.remove(0);
+                }
+                self.shaders.push(ShaderPerformance {
+                    shader_id: shader_id.to_string(),
+                    tracker: PerformanceTracker::new(),
+                });
+                self.shaders.len() - 1
+            }
+        };
+        &mut self.shaders[index].tracker
+    }
+
+    /// Record raw frame times for the session and the active shader
+    pub fn record_frame_times(&mut self, shader_id: &str, frame_times_ms: &[f32]) {
+        for frame_ms in frame_times_ms {
+            self.session.frames.record_frame_time(*frame_ms);
+        }
+        let shader = self.shader_mut(shader_id);
+        for frame_ms in frame_times_ms {
+            shader.frames.record_frame_time(*frame_ms);
+        }
+    }
+
+    pub fn record_reported_fps(&mut self, shader_id: &str, fps: f32) {
+        self.session.record_reported_fps(fps);
+        self.shader_mut(shader_id).record_reported_fps(fps);
+    }
+
+    pub fn record_compile_time(&mut self, shader_id: &str, compile_ms: f32) {
+        self.session.record_compile_time(compile_ms);
+        self.shader_mut(shader_id).record_compile_time(compile_ms);
+    }
+
+    pub fn record_gpu_memory(&mut self, shader_id: &str, gpu_memory_mb: f32) {
+        self.session.record_gpu_memory(gpu_memory_mb);
+        self.shader_mut(shader_id).record_gpu_memory(gpu_memory_mb);
+    }
+
+    pub fn summary(&self) -> PerformanceSummary {
+        self.session.summary()
+    }
+
+    pub fn shader_summary(&self, shader_id: &str) -> Option<PerformanceSummary> {
+        self.shaders
+            .iter()
+            .find(|s| s.shader_id == shader_id)
+            .map(|s| s.tracker.summary())
+    }
+}
+
+fn reported_fps_window() -> RollingWindow {
+    RollingWindow::new(REPORTED_FPS_HISTORY_SIZE)
+}
+
+impl Default for PerformanceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for SessionPerformance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_window_is_bounded() {
+        let mut window = RollingWindow::new(3);
+        for value in [1.0, 2.0, 3.0, 4.0, 5.0] {
+            window.push(value);
+        }
+        assert_eq!(window.len(), 3);
+        assert_eq!(window.ordered(), vec![3.0, 4.0, 5.0]);
+        assert_eq!(window.mean(), 4.0);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut window = RollingWindow::new(100);
+        for i in 1..=100 {
+            window.push(i as f32);
+        }
+        assert_eq!(window.percentile(50.0), 50.0);
+        assert_eq!(window.percentile(95.0), 95.0);
+        assert_eq!(window.percentile(99.0), 99.0);
+        assert_eq!(window.percentile(100.0), 100.0);
+    }
+
+    #[test]
+    fn test_dropped_frames_and_rejected_samples() {
+        let mut stats = FrameStats::new(DEFAULT_TARGET_FRAME_MS);
+        assert!(stats.record_frame_time(16.7));
+        assert!(stats.record_frame_time(33.4));
+        assert!(stats.record_frame_time(50.0));
+        assert!(!stats.record_frame_time(f32::NAN));
+        assert!(!stats.record_frame_time(-1.0));
+
+        assert_eq!(stats.total_frames, 3);
+        assert_eq!(stats.dropped_frames, 3);
+        assert_eq!(stats.rejected_samples, 2);
+
+        // A budget restored from storage can't overflow the drop count
+        stats.target_frame_ms = 0.0;
+        stats.record_frame_time(16.7);
+        stats.record_frame_time(16.7);
+        assert_eq!(stats.dropped_frames, u64::MAX);
+    }
+
+    #[test]
+    fn test_inconsistent_windows_are_rejected() {
+        use near_sdk::serde_json;
+        for json in [
+            r#"{"capacity":0,"values":[],"next":0}"#,
+            r#"{"capacity":2,"values":[1.0,2.0],"next":2}"#,
+            r#"{"capacity":2,"values":[1.0],"next":0}"#,
+            r#"{"capacity":1,"values":[1.0,2.0],"next":0}"#,
+        ] {
+            assert!(serde_json::from_str::<RollingWindow>(json).is_err(), "{}", json);
+        }
+        let window: RollingWindow =
+            serde_json::from_str(r#"{"capacity":2,"values":[1.0],"next":1}"#).unwrap();
+        assert_eq!(window.ordered(), vec![1.0]);
+
+        let parts = (0u32, Vec::<f32>::new(), 0u32);
+        let bytes = borsh::to_vec(&parts).unwrap();
+        assert!(borsh::from_slice::<RollingWindow>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_session_summary_from_raw_samples() {
+        let mut performance = SessionPerformance::new();
+        let mut frames = vec![16.0; 98];
+        frames.extend([40.0, 100.0]);
+        performance.record_frame_times("main", &frames);
+        performance.record_compile_time("main", 12.0);
+        performance.record_compile_time("main", 20.0);
+        performance.record_gpu_memory("main", 64.0);
+        performance.record_gpu_memory("main", 48.0);
+
+        let summary = performance.summary();
+        assert_eq!(summary.sample_count, 100);
+        assert_eq!(summary.p50_frame_ms, 16.0);
+        assert_eq!(summary.p99_frame_ms, 40.0);
+        assert_eq!(summary.worst_frame_ms, 100.0);
+        assert!((summary.mean_frame_ms - 17.08).abs() < 0.01);
+        assert_eq!(summary.dropped_frames, 1 + 5);
+        assert_eq!(summary.mean_compile_ms, 16.0);
+        assert_eq!(summary.last_compile_ms, Some(20.0));
+        assert_eq!(summary.gpu_memory_last_mb, 48.0);
+        assert_eq!(summary.gpu_memory_peak_mb, 64.0);
+    }
+
+    #[test]
+    fn test_per_shader_tracking_is_bounded() {
+        let mut performance = SessionPerformance::new();
+        performance.record_frame_times("a", &[16.0]);
+        performance.record_frame_times("b", &[33.0, 33.0]);
+        assert_eq!(performance.shader_summary("a").unwrap().total_frames, 1);
+        assert_eq!(performance.shader_summary("b").unwrap().total_frames, 2);
+        assert_eq!(performance.summary().total_frames, 3);
+
+        for i in 0..MAX_TRACKED_SHADERS {
+            performance.record_frame_times(&format!("extra{}", i), &[16.0]);
+        }
+        assert_eq!(performance.shaders.len(), MAX_TRACKED_SHADERS);
+        assert!(performance.shader_summary("a").is_none());
+    }
+}
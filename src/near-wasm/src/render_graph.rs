@@ -0,0 +1,393 @@
+//! Render Graph - Multi-pass shader pipelines for WGSL sessions
+//!
+//! Chains WGSL shader nodes through named intermediate textures so sessions
+//! can express feedback buffers, blur, bloom and post-processing passes
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+use std::collections::HashMap;
+
+use crate::wgsl_studio::WGSLShader;
+
+/// Version tag written into serialized graphs
+pub const RENDER_GRAPH_FORMAT_VERSION: u32 = 1;
+
+/// Multi-pass render graph for a WGSL session
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RenderGraph {
+    pub format_version: u32,
+    pub nodes: Vec<RenderNode>,
+    pub textures: Vec<RenderTexture>,
+    pub output_node: Option<String>,
+}
+
+/// Single shader pass in the graph
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RenderNode {
+    pub node_id: String,
+    pub shader: WGSLShader,
+    pub inputs: Vec<TextureInput>,
+    pub output_texture: String,
+}
+
+/// Texture sampled by a node
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TextureInput {
+    pub texture: String,
+    pub binding: u32,
+    /// Sample the texture as written in the previous frame
+    pub feedback: bool,
+}
+
+/// Named intermediate texture
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RenderTexture {
+    pub name: String,
+    pub format: TextureFormat,
+    /// Size relative to the session resolution (0.5 = half resolution)
+    pub scale: f32,
+}
+
+/// Texture storage formats
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TextureFormat {
+    Rgba8Unorm,
+    Rgba16Float,
+    Rgba32Float,
+}
+
+impl RenderGraph {
+    /// Create an empty render graph
+    pub fn new() -> Self {
+        Self {
+            format_version: RENDER_GRAPH_FORMAT_VERSION,
+            nodes: Vec::new(),
+            textures: Vec::new(),
+            output_node: None,
+        }
+    }
+
+    /// Create a single-pass graph rendering one shader to the screen
+    pub fn single_pass(shader: WGSLShader) -> Self {
+        let mut graph = Self::new();
+        graph.add_texture("screen".to_string(), TextureFormat::Rgba8Unorm, 1.0);
+        graph.add_node(RenderNode {
+            node_id: "main".to_string(),
+            shader,
+            inputs: Vec::new(),
+            output_texture: "screen".to_string(),
+        });
+        graph.output_node = Some("main".to_string());
+        graph
+    }
+
+    /// Declare an intermediate texture
+    pub fn add_texture(&mut self, name: String, format: TextureFormat, scale: f32) {
+        self.textures.retain(|t| t.name != name);
+        self.textures.push(RenderTexture {
+            name,
+            format,
+            scale,
+        });
+    }
+
+    /// Add or replace a shader node
+    pub fn add_node(&mut self, node: RenderNode) {
+        self.nodes.retain(|n| n.node_id != node.node_id);
+        self.nodes.push(node);
+    }
+
+    /// Remove a node and clear the output if it pointed to it
+    pub fn remove_node(&mut self, node_id: &str) {
+        self.nodes.retain(|n| n.node_id != node_id);
+        if self.output_node.as_deref() == Some(node_id) {
+            self.output_node = None;
+        }
+    }
+
+    /// Replace the code of every node running `shader`, matched by id
+    pub fn refresh_shader(&mut self, shader: &WGSLShader) {
+        for node in &mut self.nodes {
+            if node.shader.shader_id == shader.shader_id {
+                node.shader = shader.clone();
+            }
+        }
+    }
+
+    /// Find the node writing a texture
+    pub fn producer_of(&self, texture: &str) -> Option<&RenderNode> {
+        self.nodes.iter().find(|n| n.output_texture == texture)
+    }
+
+    /// Validate the graph and return node ids in execution order
+    ///
+    /// Feedback inputs read the previous frame and are excluded from
+    /// ordering, so they may close loops; any other cycle is rejected.
+    pub fn validate(&self) -> Result<Vec<String>, String> {
+        if self.nodes.is_empty() {
+            return Err("Render graph has no nodes".to_string());
+        }
+
+        for texture in &self.textures {
+            if !texture.scale.is_finite() || texture.scale <= 0.0 {
+                return Err(format!(
+                    "Texture {} has invalid scale {}",
+                    texture.name, texture.scale
+                ));
+            }
+        }
+
+        let mut index_by_id: HashMap<&str, usize> = HashMap::new();
+        let mut producer_by_texture: HashMap<&str, usize> = HashMap::new();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index_by_id.insert(node.node_id.as_str(), index).is_some() {
+                return Err(format!("Duplicate node id: {}", node.node_id));
+            }
+            for (i, input) in node.inputs.iter().enumerate() {
+                if node.inputs[..i].iter().any(|o| o.binding == input.binding) {
+                    return Err(format!(
+                        "Node {} uses binding {} twice",
+                        node.node_id, input.binding
+                    ));
+                }
+            }
+            if !self.textures.iter().any(|t| t.name == node.output_texture) {
+                return Err(format!(
+                    "Node {} writes undeclared texture {}",
+                    node.node_id, node.output_texture
+                ));
+            }
+            if let Some(other) = producer_by_texture.insert(node.output_texture.as_str(), index) {
+                return Err(format!(
+                    "Texture {} is written by both {} and {}",
+                    node.output_texture, self.nodes[other].node_id, node.node_id
+                ));
+            }
+        }
+
+        if let Some(output) = &self.output_node {
+            if !index_by_id.contains_key(output.as_str()) {
+                return Err(format!("Output node {} does not exist", output));
+            }
+        }
+
+        // Build dependency edges from non-feedback inputs
+        let mut in_degree = vec![0u32; self.nodes.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            for input in &node.inputs {
+                let producer =
+                    producer_by_texture
+                        .get(input.texture.as_str())
+                        .ok_or_else(|| {
+                            format!(
+                                "Node {} reads texture {} which no node writes",
+                                node.node_id, input.texture
+                            )
+                        })?;
+
+                if input.feedback {
+                    continue;
+                }
+                if *producer == index {
+                    return Err(format!(
+                        "Node {} reads its own output without a feedback edge",
+                        node.node_id
+                    ));
+                }
+                dependents[*producer].push(index);
+                in_degree[index] += 1;
+            }
+        }
+
+        // Kahn's algorithm, seeded in declaration order for stable results
+        let mut ready: Vec<usize> = (0..self.nodes.len())
+            .filter(|i| in_degree[*i] == 0)
+            .collect();
+        ready.reverse();
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(index) = ready.pop() {
+            order.push(self.nodes[index].node_id.clone());
+            for dependent in dependents[index].iter().rev() {
+                in_degree[*dependent] -= 1;
+                if in_degree[*dependent] == 0 {
+                    ready.push(*dependent);
+                }
+            }
+        }
+
+        if order.len() != self.nodes.len() {
+            let cyclic: Vec<String> = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| in_degree[*i] > 0)
+                .map(|(_, n)| n.node_id.clone())
+                .collect();
+            return Err(format!(
+                "Render graph has a cycle through: {}",
+                cyclic.join(", ")
+            ));
+        }
+
+        Ok(order)
+    }
+
+    /// Shaders in execution order
+    pub fn execution_passes(&self) -> Result<Vec<&RenderNode>, String> {
+        let order = self.validate()?;
+        Ok(order
+            .iter()
+            .filter_map(|id| self.nodes.iter().find(|n| &n.node_id == id))
+            .collect())
+    }
+
+    /// Textures that must be double-buffered because they are read as feedback
+    pub fn feedback_textures(&self) -> Vec<String> {
+        let mut textures: Vec<String> = self
+            .nodes
+            .iter()
+            .flat_map(|n| n.inputs.iter())
+            .filter(|i| i.feedback)
+            .map(|i| i.texture.clone())
+            .collect();
+        textures.sort();
+        textures.dedup();
+        textures
+    }
+
+    /// Serialize the graph to its JSON interchange format
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Render graph serialization failed")
+    }
+
+    /// Parse and validate a graph from JSON
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let graph: Self =
+            serde_json::from_str(json).map_err(|e| format!("Invalid render graph: {}", e))?;
+        if graph.format_version > RENDER_GRAPH_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported render graph version {}",
+                graph.format_version
+            ));
+        }
+        graph.validate()?;
+        Ok(graph)
+    }
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, inputs: Vec<(&str, bool)>, output: &str) -> RenderNode {
+        RenderNode {
+            node_id: id.to_string(),
+            shader: WGSLShader::new(id.to_string(), id.to_string()),
+            inputs: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(i, (texture, feedback))| TextureInput {
+                    texture: texture.to_string(),
+                    binding: i as u32,
+                    feedback,
+                })
+                .collect(),
+            output_texture: output.to_string(),
+        }
+    }
+
+    fn bloom_graph() -> RenderGraph {
+        let mut graph = RenderGraph::new();
+        for name in ["scene", "bright", "blur", "screen"] {
+            graph.add_texture(name.to_string(), TextureFormat::Rgba16Float, 1.0);
+        }
+        graph.add_node(node(
+            "composite",
+            vec![("scene", false), ("blur", false)],
+            "screen",
+        ));
+        graph.add_node(node("blur", vec![("bright", false)], "blur"));
+        graph.add_node(node("scene", vec![], "scene"));
+        graph.add_node(node("threshold", vec![("scene", false)], "bright"));
+        graph.output_node = Some("composite".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let order = bloom_graph().validate().unwrap();
+        let pos = |id: &str| order.iter().position(|n| n == id).unwrap();
+        assert!(pos("scene") < pos("threshold"));
+        assert!(pos("threshold") < pos("blur"));
+        assert!(pos("blur") < pos("composite"));
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut graph = bloom_graph();
+        graph.add_node(node("scene", vec![("screen", false)], "scene"));
+        let err = graph.validate().unwrap_err();
+        assert!(err.contains("cycle"));
+    }
+
+    #[test]
+    fn test_feedback_edge_allowed() {
+        let mut graph = bloom_graph();
+        graph.add_node(node("scene", vec![("screen", true)], "scene"));
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.feedback_textures(), vec!["screen".to_string()]);
+
+        graph.add_node(node("scene", vec![("scene", true)], "scene"));
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn test_missing_texture_rejected() {
+        let mut graph = bloom_graph();
+        graph.add_node(node("blur", vec![("missing", false)], "blur"));
+        assert!(graph.validate().unwrap_err().contains("missing"));
+    }
+
+    #[test]
+    fn test_bindings_and_scales_validated() {
+        let mut graph = bloom_graph();
+        let mut composite = node(
+            "composite",
+            vec![("scene", false), ("blur", false)],
+            "screen",
+        );
+        composite.inputs[1].binding = 0;
+        graph.add_node(composite);
+        assert!(graph.validate().unwrap_err().contains("binding 0 twice"));
+
+        for scale in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let mut graph = bloom_graph();
+            graph.add_texture("blur".to_string(), TextureFormat::Rgba16Float, scale);
+            assert!(graph.validate().unwrap_err().contains("invalid scale"));
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let graph = bloom_graph();
+        let restored = RenderGraph::from_json(&graph.to_json()).unwrap();
+        assert_eq!(restored.nodes.len(), 4);
+        assert_eq!(restored.output_node, Some("composite".to_string()));
+    }
+}
@@ -0,0 +1,522 @@
+//! Session Replay - Headless recording and deterministic replay
+//!
+//! Captures every edit, uniform change and parameter tweak of a WGSL or
+//! fractal session as a timestamped timeline. Replaying a timeline from its
+//! initial snapshot reconstructs the exact session state at any instant, so
+//! performances can be archived, minted and reproduced.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, serde_json};
+
+use crate::fractal_studio::{EmotionalVector, FractalParams, FractalSession};
+use crate::render_graph::RenderGraph;
+use crate::wgsl_studio::{PerformanceMetrics, UniformParam, WGSLSession};
+
+/// Version tag written into archived timelines
+///
+/// Version 2 added `FrameTimes` and made `MetricsUpdate` feed the session's
+/// performance tracker instead of overwriting its metrics.
+pub const TIMELINE_FORMAT_VERSION: u32 = 2;
+
+/// Session state that can be recorded and replayed
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SessionState {
+    Wgsl(WGSLSession),
+    Fractal(FractalSession),
+}
+
+/// Recorded change to a session
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TimelineAction {
+    ShaderEdit {
+        fragment_code: String,
+        description: String,
+    },
+    UniformChange(UniformParam),
+    SetTime(f32),
+    SetResolution(f32, f32),
+    SetMouse(f32, f32),
+    SetRenderGraph(Option<RenderGraph>),
+    MetricsUpdate(PerformanceMetrics),
+    FrameTimes(Vec<f32>),
+    SetFractalParams(FractalParams),
+    AddKeyframe {
+        params: FractalParams,
+        emotional_state: Option<EmotionalVector>,
+    },
+    RecordPerformance {
+        fps: f32,
+        zoom_velocity: f64,
+        parameter_changes: Vec<String>,
+    },
+}
+
+/// Timestamped timeline entry
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimelineEvent {
+    pub timestamp: u64,
+    pub action: TimelineAction,
+}
+
+/// Complete recording of a session
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SessionTimeline {
+    pub format_version: u32,
+    pub session_id: String,
+    pub recorded_by: near_sdk::AccountId,
+    pub started_at: u64,
+    pub initial_state: SessionState,
+    pub events: Vec<TimelineEvent>,
+}
+
+/// Incremental replay over a timeline
+pub struct ReplayCursor<'a> {
+    timeline: &'a SessionTimeline,
+    state: SessionState,
+    next_event: usize,
+}
+
+impl SessionState {
+    pub fn session_id(&self) -> &str {
+        match self {
+            SessionState::Wgsl(session) => &session.session_id,
+            SessionState::Fractal(session) => &session.session_id,
+        }
+    }
+
+    /// Apply an action as it happened at `timestamp`
+    pub fn apply(&mut self, timestamp: u64, action: &TimelineAction) -> Result<(), String> {
+        self.apply_versioned(TIMELINE_FORMAT_VERSION, timestamp, action)
+    }
+
+    /// Apply an action with the semantics of a timeline format version
+    ///
+    /// Live recording and replay both go through this function, which is what
+    /// makes replay deterministic.
+    pub fn apply_versioned(
+        &mut self,
+        format_version: u32,
+        timestamp: u64,
+        action: &TimelineAction,
+    ) -> Result<(), String> {
+        match (self, action) {
+            (
+                SessionState::Wgsl(session),
+                TimelineAction::ShaderEdit {
+                    fragment_code,
+                    description,
+                },
+            ) => {
+                session.record_edit_at(fragment_code.clone(), description.clone(), timestamp);
+            }
+            (SessionState::Wgsl(session), TimelineAction::UniformChange(uniform)) => {
+                session.set_uniform(uniform.clone());
+            }
+            (SessionState::Wgsl(session), TimelineAction::SetTime(time)) => {
+                session.params.time = *time;
+            }
+            (SessionState::Wgsl(session), TimelineAction::SetResolution(width, height)) => {
+                session.params.resolution = (*width, *height);
+            }
+            (SessionState::Wgsl(session), TimelineAction::SetMouse(x, y)) => {
+                session.params.mouse = (*x, *y);
+            }
+            (SessionState::Wgsl(session), TimelineAction::SetRenderGraph(graph)) => match graph {
+                Some(graph) => {
+                    session.set_render_graph(graph.clone())?;
+                }
+                None => session.render_graph = None,
+            },
+            (SessionState::Wgsl(session), TimelineAction::MetricsUpdate(metrics))
+                if format_version < 2 =>
+            {
+                session.performance_metrics = metrics.clone();
+            }
+            (_, TimelineAction::FrameTimes(_)) if format_version < 2 => {
+                return Err(format!(
+                    "Frame times are not supported in timeline version {}",
+                    format_version
+                ))
+            }
+            (SessionState::Wgsl(session), TimelineAction::MetricsUpdate(metrics)) => {
+                session.update_metrics(
+                    metrics.avg_fps,
+                    metrics.compile_time_ms,
+                    metrics.gpu_memory_mb,
+                );
+            }
+            (SessionState::Wgsl(session), TimelineAction::FrameTimes(frame_times_ms)) => {
+                session.record_frame_times(frame_times_ms);
+            }
+            (SessionState::Fractal(session), TimelineAction::FrameTimes(frame_times_ms)) => {
+                session.record_frame_times(frame_times_ms);
+            }
+            (SessionState::Fractal(session), TimelineAction::SetFractalParams(params)) => {
+                session.params = params.clone();
+            }
+            (
+                SessionState::Fractal(session),
+                TimelineAction::AddKeyframe {
+                    params,
+                    emotional_state,
+                },
+            ) => {
+                session.add_keyframe_at(params.clone(), emotional_state.clone(), timestamp);
+            }
+            (
+                SessionState::Fractal(session),
+                TimelineAction::RecordPerformance {
+                    fps,
+                    zoom_velocity,
+                    parameter_changes,
+                },
+            ) => {
+                session.record_performance_at(
+                    *fps,
+                    *zoom_velocity,
+                    parameter_changes.clone(),
+                    timestamp,
+                );
+            }
+            (state, _) => {
+                return Err(format!(
+                    "Action does not apply to session {}",
+                    state.session_id()
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the Borsh encoding, identifying the exact state
+    pub fn fingerprint(&self) -> Vec<u8> {
+        env::sha256(borsh::to_vec(self).expect("Session state serialization failed"))
+    }
+}
+
+impl SessionTimeline {
+    /// Start recording from the current state of a session
+    pub fn start(initial_state: SessionState) -> Self {
+        Self {
+            format_version: TIMELINE_FORMAT_VERSION,
+            session_id: initial_state.session_id().to_string(),
+            recorded_by: env::predecessor_account_id(),
+            started_at: env::block_timestamp(),
+            initial_state,
+            events: Vec::new(),
+        }
+    }
+
+    /// Apply an action to the live state and append it to the timeline
+    pub fn record(
+        &mut self,
+        live: &mut SessionState,
+        action: TimelineAction,
+    ) -> Result<(), String> {
+        self.record_at(live, env::block_timestamp(), action)
+    }
+
+    /// Apply and record an action with a client-supplied timestamp
+    pub fn record_at(
+        &mut self,
+        live: &mut SessionState,
+        timestamp: u64,
+        action: TimelineAction,
+    ) -> Result<(), String> {
+        if live.session_id() != self.session_id {
+            return Err(format!(
+                "Timeline belongs to session {}, not {}",
+                self.session_id,
+                live.session_id()
+            ));
+        }
+        let last = self.events.last().map_or(self.started_at, |e| e.timestamp);
+        if timestamp < last {
+            return Err(format!(
+                "Timestamp {} is earlier than the previous event at {}",
+                timestamp, last
+            ));
+        }
+
+        live.apply_versioned(self.format_version, timestamp, &action)?;
+        self.events.push(TimelineEvent { timestamp, action });
+        Ok(())
+    }
+
+    /// Timestamp of the last recorded event
+    pub fn ended_at(&self) -> u64 {
+        self.events.last().map_or(self.started_at, |e| e.timestamp)
+    }
+
+    /// Reconstruct the session as it was at `timestamp`
+    pub fn state_at(&self, timestamp: u64) -> Result<SessionState, String> {
+        let mut cursor = self.cursor();
+        cursor.seek(timestamp)?;
+        Ok(cursor.into_state())
+    }
+
+    /// Reconstruct the final session state
+    pub fn final_state(&self) -> Result<SessionState, String> {
+        self.state_at(u64::MAX)
+    }
+
+    /// Begin an incremental replay from the initial snapshot
+    pub fn cursor(&self) -> ReplayCursor<'_> {
+        ReplayCursor {
+            timeline: self,
+            state: self.initial_state.clone(),
+            next_event: 0,
+        }
+    }
+
+    /// Fingerprint of the final state, suitable for minting an archived performance
+    pub fn final_fingerprint(&self) -> Result<Vec<u8>, String> {
+        Ok(self.final_state()?.fingerprint())
+    }
+
+    /// Serialize the timeline to its JSON archive format
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Timeline serialization failed")
+    }
+
+    /// Load and validate an archived timeline
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let timeline: Self =
+            serde_json::from_str(json).map_err(|e| format!("Invalid timeline: {}", e))?;
+        if timeline.format_version > TIMELINE_FORMAT_VERSION {
+            return Err(format!(
+                "Unsupported timeline version {}",
+                timeline.format_version
+            ));
+        }
+        if timeline
+            .events
+            .windows(2)
+            .any(|pair| pair[1].timestamp < pair[0].timestamp)
+        {
+            return Err("Timeline events are out of order".to_string());
+        }
+        Ok(timeline)
+    }
+}
+
+impl<'a> ReplayCursor<'a> {
+    /// Replay every event up to and including `timestamp`
+    ///
+    /// Seeking backwards restarts from the initial snapshot.
+    pub fn seek(&mut self, timestamp: u64) -> Result<(), String> {
+        if self.next_event > 0 && self.timeline.events[self.next_event - 1].timestamp > timestamp {
+            self.state = self.timeline.initial_state.clone();
+            self.next_event = 0;
+        }
+        while let Some(event) = self.timeline.events.get(self.next_event) {
+            if event.timestamp > timestamp {
+                break;
+            }
+            self.state.apply_versioned(
+                self.timeline.format_version,
+                event.timestamp,
+                &event.action,
+            )?;
+            self.next_event += 1;
+        }
+        Ok(())
+    }
+
+    /// Apply the next event, returning false at the end of the timeline
+    pub fn step(&mut self) -> Result<bool, String> {
+        match self.timeline.events.get(self.next_event) {
+            Some(event) => {
+                self.state.apply_versioned(
+                    self.timeline.format_version,
+                    event.timestamp,
+                    &event.action,
+                )?;
+                self.next_event += 1;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> SessionState {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgsl_studio::{UniformType, WGSLShader};
+
+    fn wgsl_state() -> SessionState {
+        let shader = WGSLShader::new("shader".to_string(), "Shader".to_string());
+        SessionState::Wgsl(WGSLSession::new("live".to_string(), shader))
+    }
+
+    fn uniform(name: &str, value: f32) -> TimelineAction {
+        TimelineAction::UniformChange(UniformParam {
+            name: name.to_string(),
+            value_type: UniformType::Float,
+            value: vec![value],
+        })
+    }
+
+    fn recorded_session() -> (SessionTimeline, SessionState) {
+        let mut live = wgsl_state();
+        let mut timeline = SessionTimeline::start(live.clone());
+        let actions = vec![
+            (100, uniform("zoom", 1.0)),
+            (
+                200,
+                TimelineAction::ShaderEdit {
+                    fragment_code: "// v2".to_string(),
+                    description: "second draft".to_string(),
+                },
+            ),
+            (300, uniform("zoom", 2.5)),
+            (300, TimelineAction::SetMouse(0.25, 0.75)),
+            (400, TimelineAction::SetTime(12.0)),
+        ];
+        for (timestamp, action) in actions {
+            timeline.record_at(&mut live, timestamp, action).unwrap();
+        }
+        (timeline, live)
+    }
+
+    fn wgsl(state: &SessionState) -> &WGSLSession {
+        match state {
+            SessionState::Wgsl(session) => session,
+            _ => panic!("expected a WGSL session"),
+        }
+    }
+
+    #[test]
+    fn test_replay_matches_live_state() {
+        let (timeline, live) = recorded_session();
+        let replayed = timeline.final_state().unwrap();
+        assert_eq!(replayed.fingerprint(), live.fingerprint());
+        assert_eq!(timeline.ended_at(), 400);
+    }
+
+    #[test]
+    fn test_state_at_instant() {
+        let (timeline, _) = recorded_session();
+
+        let early = timeline.state_at(150).unwrap();
+        assert_eq!(wgsl(&early).params.custom_uniforms[0].value, vec![1.0]);
+        assert!(wgsl(&early).edit_history.is_empty());
+
+        let middle = timeline.state_at(300).unwrap();
+        let session = wgsl(&middle);
+        assert_eq!(session.params.custom_uniforms.len(), 1);
+        assert_eq!(session.params.custom_uniforms[0].value, vec![2.5]);
+        assert_eq!(session.shader.fragment_code, "// v2");
+        assert_eq!(session.edit_history[0].timestamp, 200);
+        assert_eq!(session.params.mouse, (0.25, 0.75));
+        assert_eq!(session.params.time, 0.0);
+    }
+
+    #[test]
+    fn test_cursor_seeks_backwards() {
+        let (timeline, _) = recorded_session();
+        let mut cursor = timeline.cursor();
+        cursor.seek(400).unwrap();
+        assert_eq!(wgsl(cursor.state()).params.time, 12.0);
+        cursor.seek(100).unwrap();
+        assert_eq!(
+            wgsl(cursor.state()).params.custom_uniforms[0].value,
+            vec![1.0]
+        );
+        assert!(cursor.step().unwrap());
+        assert_eq!(wgsl(cursor.state()).shader.fragment_code, "// v2");
+    }
+
+    #[test]
+    fn test_rejects_out_of_order_and_mismatched_actions() {
+        let (mut timeline, mut live) = recorded_session();
+        assert!(timeline
+            .record_at(&mut live, 50, TimelineAction::SetTime(1.0))
+            .is_err());
+        assert!(timeline
+            .record_at(
+                &mut live,
+                500,
+                TimelineAction::SetFractalParams(FractalParams::default())
+            )
+            .is_err());
+        assert_eq!(timeline.events.len(), 5);
+    }
+
+    #[test]
+    fn test_version_one_metrics_replay_as_overwrites() {
+        let (mut timeline, _) = recorded_session();
+        timeline.format_version = 1;
+        timeline.events.push(TimelineEvent {
+            timestamp: 500,
+            action: TimelineAction::MetricsUpdate(PerformanceMetrics {
+                avg_fps: 42.0,
+                compile_time_ms: 3.0,
+                gpu_memory_mb: 64.0,
+            }),
+        });
+        let archived = SessionTimeline::from_json(&timeline.to_json()).unwrap();
+        let replayed = archived.final_state().unwrap();
+        let session = wgsl(&replayed);
+        assert_eq!(session.performance_metrics.avg_fps, 42.0);
+        assert_eq!(session.performance_summary().mean_reported_fps, 0.0);
+
+        timeline.events.push(TimelineEvent {
+            timestamp: 600,
+            action: TimelineAction::FrameTimes(vec![16.0]),
+        });
+        assert!(timeline.final_state().is_err());
+    }
+
+    #[test]
+    fn test_fractal_session_replay() {
+        let mut live = SessionState::Fractal(FractalSession::new(
+            "vj".to_string(),
+            FractalParams::mandelbrot(),
+        ));
+        let mut timeline = SessionTimeline::start(live.clone());
+        timeline
+            .record_at(
+                &mut live,
+                10,
+                TimelineAction::SetFractalParams(FractalParams::julia(-0.7, 0.27)),
+            )
+            .unwrap();
+        timeline
+            .record_at(
+                &mut live,
+                20,
+                TimelineAction::RecordPerformance {
+                    fps: 58.0,
+                    zoom_velocity: 0.1,
+                    parameter_changes: vec!["zoom".to_string()],
+                },
+            )
+            .unwrap();
+
+        let archived = SessionTimeline::from_json(&timeline.to_json()).unwrap();
+        let replayed = archived.final_state().unwrap();
+        assert_eq!(replayed.fingerprint(), live.fingerprint());
+        match replayed {
+            SessionState::Fractal(session) => {
+                assert_eq!(session.performance_data[0].timestamp, 20);
+                assert_eq!(session.params.julia_c_real, Some(-0.7));
+            }
+            _ => panic!("expected a fractal session"),
+        }
+    }
+}
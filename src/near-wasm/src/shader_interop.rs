@@ -0,0 +1,1410 @@
+//! Shader Interop - Shadertoy and ISF conversion for WGSL Studio
+//!
+//! Translates GLSL `mainImage` shaders from Shadertoy and Interactive Shader
+//! Format (ISF) files into WGSL shaders with live parameters, and exports
+//! WGSL shaders back to ISF. Every conversion produces a report listing the
+//! uniforms that were mapped and the features that could not be translated.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, json, Value};
+
+use crate::wgsl_studio::{ShaderParams, UniformParam, UniformType, WGSLShader};
+
+const UNBALANCED: &str = "Unbalanced brackets in shader source";
+
+/// Source format of a conversion
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ShaderSourceFormat {
+    Shadertoy,
+    Isf,
+    Wgsl,
+}
+
+/// Summary of what a conversion preserved and what it dropped
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConversionReport {
+    pub source_format: ShaderSourceFormat,
+    pub mapped_uniforms: Vec<String>,
+    pub unsupported: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Result of importing a foreign shader
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ShaderConversion {
+    pub shader: WGSLShader,
+    pub params: ShaderParams,
+    pub report: ConversionReport,
+}
+
+impl ConversionReport {
+    fn new(source_format: ShaderSourceFormat) -> Self {
+        Self {
+            source_format,
+            mapped_uniforms: Vec::new(),
+            unsupported: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// True when nothing had to be dropped during conversion
+    pub fn is_lossless(&self) -> bool {
+        self.unsupported.is_empty()
+    }
+
+    fn map(&mut self, from: &str, to: &str) {
+        let entry = format!("{} -> {}", from, to);
+        if !self.mapped_uniforms.contains(&entry) {
+            self.mapped_uniforms.push(entry);
+        }
+    }
+
+    fn unsupported(&mut self, feature: String) {
+        if !self.unsupported.contains(&feature) {
+            self.unsupported.push(feature);
+        }
+    }
+
+    fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// Import a Shadertoy `mainImage` shader
+pub fn import_shadertoy(
+    shader_id: String,
+    name: String,
+    source: &str,
+) -> Result<ShaderConversion, String> {
+    let mut report = ConversionReport::new(ShaderSourceFormat::Shadertoy);
+    let mut params = ShaderParams::default();
+
+    let renames: &[(&str, &str, Option<UniformType>)] = &[
+        ("iTime", "time", None),
+        ("iGlobalTime", "time", None),
+        ("iResolution", "vec3<f32>(resolution, 1.0)", None),
+        ("iMouse", "vec4<f32>(mouse, 0.0, 0.0)", None),
+        ("iTimeDelta", "time_delta", Some(UniformType::Float)),
+        ("iFrame", "i32(frame)", Some(UniformType::Float)),
+    ];
+
+    let tokens = tokenize(source);
+    check_brackets(&tokens)?;
+    for (glsl, wgsl, custom) in renames {
+        if !uses_identifier(&tokens, glsl) {
+            continue;
+        }
+        let uniform = uniform_name(wgsl);
+        report.map(glsl, uniform);
+        if let Some(value_type) = custom {
+            push_uniform(&mut params, uniform, value_type.clone(), vec![0.0]);
+        }
+    }
+    if uses_identifier(&tokens, "iMouse") {
+        report.warn("iMouse click position (zw) is always zero".to_string());
+    }
+    for unsupported in [
+        "iChannel0",
+        "iChannel1",
+        "iChannel2",
+        "iChannel3",
+        "iChannelTime",
+        "iChannelResolution",
+        "iDate",
+        "iSampleRate",
+    ] {
+        if uses_identifier(&tokens, unsupported) {
+            report.unsupported(format!("Shadertoy input {}", unsupported));
+        }
+    }
+
+    let rename_pairs: Vec<(&str, &str)> = renames.iter().map(|(g, w, _)| (*g, *w)).collect();
+    let entry = EntryPoint {
+        glsl_name: "mainImage",
+        fixed_output: None,
+    };
+    let body = translate_glsl(&tokens, &rename_pairs, &entry, &mut report)?;
+
+    Ok(build_conversion(shader_id, name, params, body, report))
+}
+
+/// Import an ISF shader with its JSON header
+pub fn import_isf(
+    shader_id: String,
+    name: String,
+    source: &str,
+) -> Result<ShaderConversion, String> {
+    let mut report = ConversionReport::new(ShaderSourceFormat::Isf);
+    let mut params = ShaderParams::default();
+
+    let trimmed = source.trim_start();
+    if !trimmed.starts_with("/*") {
+        return Err("ISF shader must start with a JSON header comment".to_string());
+    }
+    let header_end = trimmed[2..]
+        .find("*/")
+        .map(|i| i + 2)
+        .ok_or("Unterminated ISF header")?;
+    let header: Value = serde_json::from_str(&trimmed[2..header_end])
+        .map_err(|e| format!("Invalid ISF header: {}", e))?;
+    let glsl = &trimmed[header_end + 2..];
+
+    if let Some(inputs) = header.get("INPUTS").and_then(|i| i.as_array()) {
+        for input in inputs {
+            map_isf_input(input, &mut params, &mut report);
+        }
+    }
+    if header
+        .get("PASSES")
+        .and_then(|p| p.as_array())
+        .is_some_and(|p| !p.is_empty())
+    {
+        report.unsupported("ISF PASSES (rebuild as a render graph)".to_string());
+    }
+    if header.get("IMPORTED").is_some() {
+        report.unsupported("ISF IMPORTED images".to_string());
+    }
+
+    let mut renames: Vec<(&str, &str)> = vec![
+        ("TIME", "time"),
+        ("RENDERSIZE", "resolution"),
+        ("isf_FragNormCoord", "(fragCoord / resolution)"),
+        ("gl_FragCoord", "vec4<f32>(fragCoord, 0.0, 1.0)"),
+        ("gl_FragColor", "frag_color"),
+    ];
+    let tokens = tokenize(glsl);
+    check_brackets(&tokens)?;
+    for (isf, wgsl) in renames.clone() {
+        if isf.starts_with("gl_") || isf == "isf_FragNormCoord" || !uses_identifier(&tokens, isf) {
+            continue;
+        }
+        report.map(isf, wgsl);
+    }
+    if uses_identifier(&tokens, "TIMEDELTA") {
+        renames.push(("TIMEDELTA", "time_delta"));
+        report.map("TIMEDELTA", "time_delta");
+        push_uniform(&mut params, "time_delta", UniformType::Float, vec![0.0]);
+    }
+    if uses_identifier(&tokens, "FRAMEINDEX") {
+        renames.push(("FRAMEINDEX", "i32(frame)"));
+        report.map("FRAMEINDEX", "frame");
+        push_uniform(&mut params, "frame", UniformType::Float, vec![0.0]);
+    }
+    for unsupported in [
+        "DATE",
+        "IMG_PIXEL",
+        "IMG_NORM_PIXEL",
+        "IMG_THIS_PIXEL",
+        "IMG_THIS_NORM_PIXEL",
+        "IMG_SIZE",
+        "PASSINDEX",
+    ] {
+        if uses_identifier(&tokens, unsupported) {
+            report.unsupported(format!("ISF builtin {}", unsupported));
+        }
+    }
+
+    let entry = EntryPoint {
+        glsl_name: "main",
+        fixed_output: Some("frag_color"),
+    };
+    let body = translate_glsl(&tokens, &renames, &entry, &mut report)?;
+
+    let mut conversion = build_conversion(shader_id, name, params, body, report);
+    if let Some(description) = header.get("DESCRIPTION").and_then(|d| d.as_str()) {
+        conversion
+            .report
+            .warn(format!("ISF description not stored: {}", description));
+    }
+    Ok(conversion)
+}
+
+/// Export a WGSL shader and its parameters as an ISF document
+pub fn export_isf(
+    shader: &WGSLShader,
+    params: &ShaderParams,
+) -> Result<(String, ConversionReport), String> {
+    let mut report = ConversionReport::new(ShaderSourceFormat::Wgsl);
+    let tokens = tokenize(&shader.fragment_code);
+    check_brackets(&tokens)?;
+
+    let mut inputs = Vec::new();
+    let mut renames: Vec<(String, String)> = vec![
+        ("time".to_string(), "TIME".to_string()),
+        ("resolution".to_string(), "RENDERSIZE".to_string()),
+        ("time_delta".to_string(), "TIMEDELTA".to_string()),
+        ("frame".to_string(), "float(FRAMEINDEX)".to_string()),
+    ];
+    for (wgsl, isf) in &renames {
+        if uses_uniform(&tokens, wgsl) {
+            report.map(wgsl, uniform_name(isf));
+        }
+    }
+
+    if uses_uniform(&tokens, "mouse") {
+        inputs.push(json!({
+            "NAME": "mouse",
+            "TYPE": "point2D",
+            "DEFAULT": [params.mouse.0, params.mouse.1],
+        }));
+        report.map("mouse", "mouse (point2D input)");
+    }
+
+    for uniform in &params.custom_uniforms {
+        if uniform.name == "time_delta" || uniform.name == "frame" {
+            continue;
+        }
+        let (isf_type, default) = match uniform.value_type {
+            UniformType::Float => (
+                "float",
+                json!(uniform.value.first().copied().unwrap_or(0.0)),
+            ),
+            UniformType::Vec2 => ("point2D", json!(padded(&uniform.value, 2))),
+            UniformType::Vec4 => ("color", json!(padded(&uniform.value, 4))),
+            UniformType::Vec3 | UniformType::Mat4 => {
+                report.unsupported(format!("uniform {} has no ISF input type", uniform.name));
+                continue;
+            }
+        };
+        inputs.push(json!({ "NAME": uniform.name, "TYPE": isf_type, "DEFAULT": default }));
+        report.map(
+            &uniform.name,
+            &format!("{} ({} input)", uniform.name, isf_type),
+        );
+    }
+
+    let header = json!({
+        "ISFVSN": "2",
+        "DESCRIPTION": shader.name,
+        "CREDIT": shader.creator.to_string(),
+        "CATEGORIES": ["NEAR Creative Engine"],
+        "INPUTS": inputs,
+    });
+
+    renames.push(("atan2".to_string(), "atan".to_string()));
+    renames.push(("inverseSqrt".to_string(), "inversesqrt".to_string()));
+    renames.push(("glsl_mod".to_string(), "mod".to_string()));
+    let body = translate_wgsl(&tokens, &renames, &mut report)?;
+
+    let document = format!(
+        "/*{}*/\n\n{}",
+        serde_json::to_string_pretty(&header).expect("ISF header serialization failed"),
+        body.trim()
+    );
+    Ok((document, report))
+}
+
+/// Function treated as the fragment entry point during GLSL translation
+struct EntryPoint {
+    glsl_name: &'static str,
+    /// Output variable for entry points that write a global (ISF `gl_FragColor`)
+    fixed_output: Option<&'static str>,
+}
+
+fn build_conversion(
+    shader_id: String,
+    name: String,
+    params: ShaderParams,
+    body: String,
+    report: ConversionReport,
+) -> ShaderConversion {
+    let mut shader = WGSLShader::new(shader_id, name);
+    let mut code = uniform_declarations(&params);
+    if body.contains("glsl_mod(") {
+        code.push_str(
+            "\nfn glsl_mod(x: f32, y: f32) -> f32 {\n    return x - y * floor(x / y);\n}\n",
+        );
+    }
+    code.push('\n');
+    code.push_str(body.trim());
+    code.push_str(
+        r#"
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return mainImage(vec2<f32>(pos.x, resolution.y - pos.y));
+}
+"#,
+    );
+    shader.fragment_code = code;
+
+    let mut report = report;
+    if body.contains("glsl_mod(") {
+        report.warn("mod() is translated for scalar arguments only".to_string());
+    }
+    ShaderConversion {
+        shader,
+        params,
+        report,
+    }
+}
+
+fn uniform_declarations(params: &ShaderParams) -> String {
+    let mut code = String::from(
+        "@group(0) @binding(0) var<uniform> time: f32;\n\
+         @group(0) @binding(1) var<uniform> resolution: vec2<f32>;\n\
+         @group(0) @binding(2) var<uniform> mouse: vec2<f32>;\n",
+    );
+    for (index, uniform) in params.custom_uniforms.iter().enumerate() {
+        let wgsl_type = match uniform.value_type {
+            UniformType::Float => "f32",
+            UniformType::Vec2 => "vec2<f32>",
+            UniformType::Vec3 => "vec3<f32>",
+            UniformType::Vec4 => "vec4<f32>",
+            UniformType::Mat4 => "mat4x4<f32>",
+        };
+        code.push_str(&format!(
+            "@group(0) @binding({}) var<uniform> {}: {};\n",
+            index + 3,
+            uniform.name,
+            wgsl_type
+        ));
+    }
+    code
+}
+
+fn map_isf_input(input: &Value, params: &mut ShaderParams, report: &mut ConversionReport) {
+    let name = match input.get("NAME").and_then(|n| n.as_str()) {
+        Some(name) => name,
+        None => {
+            report.unsupported("ISF input without NAME".to_string());
+            return;
+        }
+    };
+    let isf_type = input.get("TYPE").and_then(|t| t.as_str()).unwrap_or("");
+    let default = input.get("DEFAULT");
+
+    let (value_type, value) = match isf_type {
+        "float" | "long" => (UniformType::Float, vec![json_f32(default).unwrap_or(0.0)]),
+        "bool" | "event" => {
+            report.warn(format!(
+                "{} input {} is exposed as a float uniform",
+                isf_type, name
+            ));
+            (UniformType::Float, vec![json_f32(default).unwrap_or(0.0)])
+        }
+        "point2D" => (UniformType::Vec2, padded(&json_f32_array(default), 2)),
+        "color" => (UniformType::Vec4, padded(&json_f32_array(default), 4)),
+        other => {
+            report.unsupported(format!("ISF input {} of type {}", name, other));
+            return;
+        }
+    };
+
+    if input.get("MIN").is_some() || input.get("MAX").is_some() {
+        report.warn(format!("MIN/MAX range of {} is not preserved", name));
+    }
+    report.map(name, name);
+    push_uniform(params, name, value_type, value);
+}
+
+fn push_uniform(params: &mut ShaderParams, name: &str, value_type: UniformType, value: Vec<f32>) {
+    if params.custom_uniforms.iter().any(|u| u.name == name) {
+        return;
+    }
+    params.custom_uniforms.push(UniformParam {
+        name: name.to_string(),
+        value_type,
+        value,
+    });
+}
+
+fn json_f32(value: Option<&Value>) -> Option<f32> {
+    match value? {
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        other => other.as_f64().map(|v| v as f32),
+    }
+}
+
+fn json_f32_array(value: Option<&Value>) -> Vec<f32> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_f64())
+                .map(|v| v as f32)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn padded(values: &[f32], len: usize) -> Vec<f32> {
+    let mut values = values.to_vec();
+    values.resize(len, 0.0);
+    values
+}
+
+/// Plain identifier that a rename expression refers to
+fn uniform_name(expression: &str) -> &str {
+    expression
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|part| !part.is_empty() && part.chars().next().is_some_and(|c| !c.is_numeric()))
+        .find(|part| !matches!(*part, "vec3" | "vec4" | "f32" | "i32" | "float"))
+        .unwrap_or(expression)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Punct(char),
+    Space(String),
+    Comment(String),
+    Directive(String),
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c == '#' && line_start {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            tokens.push(Token::Directive(chars[start..i].iter().collect()));
+            continue;
+        }
+        if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                if chars[i] == '\n' {
+                    line_start = true;
+                }
+                i += 1;
+            }
+            tokens.push(Token::Space(chars[start..i].iter().collect()));
+            continue;
+        }
+        line_start = false;
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            tokens.push(Token::Comment(chars[start..i].iter().collect()));
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i = (i + 2).min(chars.len());
+            tokens.push(Token::Comment(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()))
+        {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric()
+                    || chars[i] == '.'
+                    || ((chars[i] == '-' || chars[i] == '+') && matches!(chars[i - 1], 'e' | 'E')))
+            {
+                i += 1;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else {
+            i += 1;
+            tokens.push(Token::Punct(c));
+        }
+    }
+    tokens
+}
+
+fn uses_identifier(tokens: &[Token], name: &str) -> bool {
+    tokens
+        .iter()
+        .any(|t| matches!(t, Token::Ident(id) if id == name))
+}
+
+/// True when a WGSL uniform is referenced beyond its own declaration
+fn uses_uniform(tokens: &[Token], name: &str) -> bool {
+    tokens.iter().enumerate().any(|(i, t)| {
+        if !matches!(t, Token::Ident(id) if id == name) {
+            return false;
+        }
+        let previous = (0..i)
+            .rev()
+            .find(|p| !matches!(tokens[*p], Token::Space(_) | Token::Comment(_)));
+        let declared = previous.is_some_and(|p| tokens[p] == Token::Punct('>'))
+            && (0..i)
+                .rev()
+                .take(4)
+                .any(|p| tokens[p] == Token::Ident("uniform".to_string()));
+        !declared
+    })
+}
+
+fn next_significant(tokens: &[Token], from: usize) -> Option<usize> {
+    (from..tokens.len()).find(|i| !matches!(tokens[*i], Token::Space(_) | Token::Comment(_)))
+}
+
+fn is_punct(tokens: &[Token], index: Option<usize>, c: char) -> bool {
+    matches!(index.map(|i| &tokens[i]), Some(Token::Punct(p)) if *p == c)
+}
+
+fn ident_at(tokens: &[Token], index: Option<usize>) -> Option<&str> {
+    match index.map(|i| &tokens[i]) {
+        Some(Token::Ident(id)) => Some(id.as_str()),
+        _ => None,
+    }
+}
+
+/// Check every `(`, `[` and `{` is closed in order and nothing else is
+fn check_brackets(tokens: &[Token]) -> Result<(), String> {
+    let mut open: Vec<char> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Punct(c @ ('(' | '[' | '{')) => open.push(*c),
+            Token::Punct(c @ (')' | ']' | '}')) => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if open.pop() != Some(expected) {
+                    return Err(UNBALANCED.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    if open.is_empty() {
+        Ok(())
+    } else {
+        Err(UNBALANCED.to_string())
+    }
+}
+
+/// Index of the bracket closing the one at `open`, if the source has one
+fn matching_close(
+    tokens: &[Token],
+    open: usize,
+    open_char: char,
+    close_char: char,
+) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct(c) if *c == open_char => depth += 1,
+            Token::Punct(c) if *c == close_char => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Index of the `;` ending the statement at `start`, if it comes before the
+/// enclosing block closes
+fn statement_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Punct(';') if depth == 0 => return Some(i),
+            Token::Punct('(' | '[' | '{') => depth += 1,
+            Token::Punct(')' | ']' | '}') => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn glsl_type(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "void" => "",
+        "float" => "f32",
+        "int" => "i32",
+        "uint" => "u32",
+        "bool" => "bool",
+        "vec2" => "vec2<f32>",
+        "vec3" => "vec3<f32>",
+        "vec4" => "vec4<f32>",
+        "ivec2" => "vec2<i32>",
+        "ivec3" => "vec3<i32>",
+        "ivec4" => "vec4<i32>",
+        "uvec2" => "vec2<u32>",
+        "uvec3" => "vec3<u32>",
+        "uvec4" => "vec4<u32>",
+        "bvec2" => "vec2<bool>",
+        "bvec3" => "vec3<bool>",
+        "bvec4" => "vec4<bool>",
+        "mat2" => "mat2x2<f32>",
+        "mat3" => "mat3x3<f32>",
+        "mat4" => "mat4x4<f32>",
+        _ => return None,
+    })
+}
+
+fn is_qualifier(name: &str) -> bool {
+    matches!(
+        name,
+        "in" | "out" | "inout" | "const" | "highp" | "mediump" | "lowp"
+    )
+}
+
+/// Parameter list of a GLSL function as (qualifiers, type, name)
+fn glsl_params(tokens: &[Token], open: usize, close: usize) -> Vec<(Vec<String>, String, String)> {
+    let mut params = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    for token in &tokens[open + 1..=close] {
+        match token {
+            Token::Ident(id) => words.push(id.clone()),
+            Token::Punct(',') | Token::Punct(')') => {
+                if words.len() >= 2 {
+                    let name = words.pop().unwrap();
+                    let ty = words.pop().unwrap();
+                    let qualifiers = words.iter().filter(|w| is_qualifier(w)).cloned().collect();
+                    params.push((qualifiers, ty, name));
+                }
+                words.clear();
+            }
+            _ => {}
+        }
+    }
+    params
+}
+
+/// Translate GLSL source tokens into WGSL
+fn translate_glsl(
+    tokens: &[Token],
+    renames: &[(&str, &str)],
+    entry: &EntryPoint,
+    report: &mut ConversionReport,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut brace_depth = 0usize;
+    let mut paren_depth = 0usize;
+    let mut statement_start = true;
+    let mut for_header = false;
+    let mut control_parens: Vec<usize> = Vec::new();
+    let mut braceless_bodies: Vec<usize> = Vec::new();
+    let mut declaration: Option<(String, String, usize)> = None;
+    let mut pending_output: Option<String> = None;
+    let mut entry_scope: Option<(usize, String)> = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Space(s) | Token::Comment(s) => out.push_str(s),
+            Token::Directive(line) => {
+                report.unsupported(format!("preprocessor directive `{}`", line.trim()));
+                out.push_str("// ");
+                out.push_str(line);
+            }
+            Token::Number(n) => {
+                out.push_str(n);
+                statement_start = false;
+            }
+            Token::Ident(name) => {
+                if statement_start && name == "precision" {
+                    while i < tokens.len() && tokens[i] != Token::Punct(';') {
+                        i += 1;
+                    }
+                    i += 1;
+                    continue;
+                }
+
+                if statement_start && (name == "const" || glsl_type(name).is_some()) {
+                    let is_const = name == "const";
+                    let type_index = if is_const {
+                        next_significant(tokens, i + 1)
+                    } else {
+                        Some(i)
+                    };
+                    let wgsl_type = ident_at(tokens, type_index).and_then(glsl_type);
+                    let name_index = type_index.and_then(|t| next_significant(tokens, t + 1));
+                    let after_name = name_index.and_then(|n| next_significant(tokens, n + 1));
+
+                    if let (Some(wgsl_type), Some(var_name)) =
+                        (wgsl_type, ident_at(tokens, name_index))
+                    {
+                        if is_punct(tokens, after_name, '(') && brace_depth == 0 {
+                            let open = after_name.unwrap();
+                            let close = matching_close(tokens, open, '(', ')').ok_or(UNBALANCED)?;
+                            if is_punct(tokens, next_significant(tokens, close + 1), ';') {
+                                report.warn(format!("prototype for {} dropped", var_name));
+                                i = next_significant(tokens, close + 1).unwrap() + 1;
+                                continue;
+                            }
+                            let params = glsl_params(tokens, open, close);
+                            out.push_str(&function_header(
+                                var_name,
+                                wgsl_type,
+                                &params,
+                                entry,
+                                report,
+                                &mut pending_output,
+                            ));
+                            i = close + 1;
+                            statement_start = false;
+                            continue;
+                        }
+                        if is_punct(tokens, after_name, '[') {
+                            report.unsupported(format!("array declaration {}", var_name));
+                        } else if !is_punct(tokens, after_name, '(') {
+                            let keyword = match (is_const, brace_depth) {
+                                (true, 0) => "const",
+                                (true, _) => "let",
+                                (false, 0) => "var<private>",
+                                (false, _) => "var",
+                            };
+                            out.push_str(&format!(
+                                "{} {}: {}",
+                                keyword,
+                                rename(var_name, renames),
+                                wgsl_type
+                            ));
+                            declaration =
+                                Some((keyword.to_string(), wgsl_type.to_string(), paren_depth));
+                            i = name_index.unwrap() + 1;
+                            statement_start = false;
+                            continue;
+                        }
+                    }
+                }
+
+                statement_start = false;
+                if name == "for" {
+                    for_header = true;
+                }
+                if matches!(name.as_str(), "if" | "for" | "while") {
+                    control_parens.push(paren_depth + 1);
+                }
+                if name == "else" {
+                    let next = next_significant(tokens, i + 1);
+                    if !is_punct(tokens, next, '{') && ident_at(tokens, next) != Some("if") {
+                        // WGSL requires braces around every body
+                        out.push_str("else {");
+                        braceless_bodies.push(paren_depth);
+                        statement_start = true;
+                        i += 1;
+                        continue;
+                    }
+                }
+                if name == "return" {
+                    if let Some((_, output)) = &entry_scope {
+                        if is_punct(tokens, next_significant(tokens, i + 1), ';') {
+                            out.push_str(&format!("return {}", output));
+                            i += 1;
+                            continue;
+                        }
+                    }
+                }
+
+                let translated = match name.as_str() {
+                    "atan" => {
+                        let open = next_significant(tokens, i + 1);
+                        if is_punct(tokens, open, '(')
+                            && top_level_commas(tokens, open.unwrap())? == 1
+                        {
+                            "atan2".to_string()
+                        } else {
+                            "atan".to_string()
+                        }
+                    }
+                    "inversesqrt" => "inverseSqrt".to_string(),
+                    "mod" => "glsl_mod".to_string(),
+                    "texture" | "texture2D" | "textureLod" | "texelFetch" => {
+                        report.unsupported(format!("texture sampling via {}()", name));
+                        name.clone()
+                    }
+                    other => match glsl_type(other) {
+                        Some(wgsl) if !wgsl.is_empty() => wgsl.to_string(),
+                        _ => rename(other, renames),
+                    },
+                };
+                out.push_str(&translated);
+            }
+            Token::Punct(c) => {
+                match c {
+                    '(' => {
+                        paren_depth += 1;
+                        statement_start = for_header;
+                        for_header = false;
+                    }
+                    ')' => {
+                        if control_parens.last() == Some(&paren_depth) {
+                            control_parens.pop();
+                            if !is_punct(tokens, next_significant(tokens, i + 1), '{') {
+                                out.push_str(") {");
+                                braceless_bodies.push(paren_depth - 1);
+                                paren_depth -= 1;
+                                statement_start = true;
+                                i += 1;
+                                continue;
+                            }
+                        }
+                        paren_depth = paren_depth.saturating_sub(1);
+                    }
+                    '{' => {
+                        brace_depth += 1;
+                        statement_start = true;
+                        out.push('{');
+                        if let Some(output) = pending_output.take() {
+                            out.push_str(&format!("\n    var {}: vec4<f32>;", output));
+                            entry_scope = Some((brace_depth, output));
+                        }
+                        i += 1;
+                        continue;
+                    }
+                    '}' => {
+                        if let Some((depth, output)) = &entry_scope {
+                            if *depth == brace_depth {
+                                out.push_str(&format!("    return {};\n", output));
+                                entry_scope = None;
+                            }
+                        }
+                        brace_depth = brace_depth.saturating_sub(1);
+                        statement_start = true;
+                    }
+                    ';' => {
+                        statement_start = true;
+                        declaration = None;
+                        out.push(';');
+                        while braceless_bodies.last() == Some(&paren_depth) {
+                            braceless_bodies.pop();
+                            out.push_str(" }");
+                        }
+                        i += 1;
+                        continue;
+                    }
+                    ',' => {
+                        if let Some((keyword, wgsl_type, depth)) = &declaration {
+                            let next = next_significant(tokens, i + 1);
+                            if *depth == paren_depth {
+                                if let Some(next_name) = ident_at(tokens, next) {
+                                    out.push_str(&format!(
+                                        "; {} {}: {}",
+                                        keyword,
+                                        rename(next_name, renames),
+                                        wgsl_type
+                                    ));
+                                    i = next.unwrap() + 1;
+                                    continue;
+                                }
+                            }
+                        }
+                    }
+                    '?' => {
+                        report.unsupported("ternary operator (rewrite with select())".to_string())
+                    }
+                    _ => {}
+                }
+                out.push(*c);
+            }
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+fn function_header(
+    name: &str,
+    return_type: &str,
+    params: &[(Vec<String>, String, String)],
+    entry: &EntryPoint,
+    report: &mut ConversionReport,
+    pending_output: &mut Option<String>,
+) -> String {
+    if name == entry.glsl_name {
+        let coord = params
+            .iter()
+            .find(|(q, _, _)| !q.iter().any(|w| w == "out"))
+            .map(|(_, _, n)| n.clone())
+            .unwrap_or_else(|| "fragCoord".to_string());
+        let output = entry
+            .fixed_output
+            .map(|o| o.to_string())
+            .or_else(|| {
+                params
+                    .iter()
+                    .find(|(q, _, _)| q.iter().any(|w| w == "out"))
+                    .map(|(_, _, n)| n.clone())
+            })
+            .unwrap_or_else(|| "fragColor".to_string());
+        *pending_output = Some(output);
+        return format!("fn mainImage({}: vec2<f32>) -> vec4<f32>", coord);
+    }
+
+    let mut wgsl_params = Vec::new();
+    for (qualifiers, ty, param) in params {
+        if qualifiers.iter().any(|q| q == "out" || q == "inout") {
+            report.unsupported(format!("out parameter {} in {}()", param, name));
+        }
+        let wgsl_type = glsl_type(ty).unwrap_or_else(|| {
+            report.unsupported(format!("parameter type {} in {}()", ty, name));
+            "f32"
+        });
+        wgsl_params.push(format!("{}: {}", param, wgsl_type));
+    }
+    if return_type.is_empty() {
+        format!("fn {}({})", name, wgsl_params.join(", "))
+    } else {
+        format!("fn {}({}) -> {}", name, wgsl_params.join(", "), return_type)
+    }
+}
+
+fn top_level_commas(tokens: &[Token], open: usize) -> Result<usize, String> {
+    let close = matching_close(tokens, open, '(', ')').ok_or(UNBALANCED)?;
+    let mut depth = 0;
+    let mut commas = 0;
+    for token in &tokens[open + 1..close] {
+        match token {
+            Token::Punct('(') | Token::Punct('[') => depth += 1,
+            Token::Punct(')') | Token::Punct(']') => depth -= 1,
+            Token::Punct(',') if depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+    Ok(commas)
+}
+
+fn rename<S: AsRef<str>>(name: &str, renames: &[(S, S)]) -> String {
+    renames
+        .iter()
+        .find(|(from, _)| from.as_ref() == name)
+        .map(|(_, to)| to.as_ref().to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Consume a WGSL type starting at `index`, returning the GLSL name and the last token used
+fn wgsl_type(
+    tokens: &[Token],
+    index: usize,
+    report: &mut ConversionReport,
+) -> Result<(String, usize), String> {
+    let name = match &tokens[index] {
+        Token::Ident(id) => id.as_str(),
+        _ => return Ok((String::new(), index)),
+    };
+    let generic = next_significant(tokens, index + 1).filter(|g| is_punct(tokens, Some(*g), '<'));
+    let (element, end) = match generic {
+        Some(open) => {
+            let close = matching_close(tokens, open, '<', '>').ok_or(UNBALANCED)?;
+            let element = ident_at(tokens, next_significant(tokens, open + 1)).unwrap_or("f32");
+            (element, close)
+        }
+        None => ("f32", index),
+    };
+    let prefix = match element {
+        "i32" => "i",
+        "u32" => "u",
+        "bool" => "b",
+        _ => "",
+    };
+
+    let glsl = match name {
+        "f32" => "float".to_string(),
+        "i32" => "int".to_string(),
+        "u32" => "uint".to_string(),
+        "bool" => "bool".to_string(),
+        "vec2" | "vec3" | "vec4" => format!("{}{}", prefix, name),
+        "vec2f" | "vec3f" | "vec4f" => name.trim_end_matches('f').to_string(),
+        "mat2x2" | "mat3x3" | "mat4x4" => format!("mat{}", &name[3..4]),
+        "array" | "ptr" | "texture_2d" | "sampler" => {
+            report.unsupported(format!("WGSL type {}", name));
+            name.to_string()
+        }
+        other if other.starts_with("mat") => other.to_string(),
+        other => other.to_string(),
+    };
+    Ok((glsl, end))
+}
+
+/// Translate WGSL source tokens back into ISF-flavoured GLSL
+fn translate_wgsl(
+    tokens: &[Token],
+    renames: &[(String, String)],
+    report: &mut ConversionReport,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut entry_scope: Option<usize> = None;
+    let mut local_renames: Vec<(String, String)> = renames.to_vec();
+    let mut brace_depth = 0usize;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Space(s) | Token::Comment(s) | Token::Directive(s) => out.push_str(s),
+            Token::Number(n) => out.push_str(n.trim_end_matches(['f', 'i', 'u'])),
+            Token::Punct('@') => {
+                let attribute = ident_at(tokens, next_significant(tokens, i + 1)).unwrap_or("");
+                if attribute == "group" {
+                    while i < tokens.len() && tokens[i] != Token::Punct(';') {
+                        i += 1;
+                    }
+                    // Skip the newline after the removed declaration
+                    if matches!(tokens.get(i + 1), Some(Token::Space(_))) {
+                        i += 1;
+                    }
+                } else if attribute == "fragment" {
+                    let fn_index = (i..tokens.len())
+                        .find(|t| tokens[*t] == Token::Ident("fn".to_string()))
+                        .unwrap_or(i);
+                    let open = (fn_index..tokens.len())
+                        .find(|t| tokens[*t] == Token::Punct('('))
+                        .unwrap_or(fn_index);
+                    let close = matching_close(tokens, open, '(', ')').ok_or(UNBALANCED)?;
+                    let coord = (open..close)
+                        .rev()
+                        .find(|t| tokens[*t] == Token::Punct(':'))
+                        .and_then(|colon| {
+                            (open..colon).rev().find_map(|b| match &tokens[b] {
+                                Token::Ident(id) => Some(id.clone()),
+                                _ => None,
+                            })
+                        })
+                        .unwrap_or_else(|| "pos".to_string());
+                    local_renames.push((
+                        coord,
+                        "vec4(gl_FragCoord.x, RENDERSIZE.y - gl_FragCoord.y, gl_FragCoord.zw)"
+                            .to_string(),
+                    ));
+                    let body_open = (close..tokens.len())
+                        .find(|t| tokens[*t] == Token::Punct('{'))
+                        .unwrap_or(close);
+                    out.push_str("void main() {");
+                    brace_depth += 1;
+                    entry_scope = Some(brace_depth);
+                    i = body_open + 1;
+                    continue;
+                } else {
+                    report.unsupported(format!("WGSL attribute @{}", attribute));
+                }
+            }
+            Token::Ident(name) if name == "fn" => {
+                let name_index = next_significant(tokens, i + 1);
+                let fn_name = ident_at(tokens, name_index).unwrap_or("").to_string();
+                let open = name_index
+                    .and_then(|n| next_significant(tokens, n + 1))
+                    .unwrap_or(i);
+                let close = matching_close(tokens, open, '(', ')').ok_or(UNBALANCED)?;
+                let body_open = (close..tokens.len())
+                    .find(|t| tokens[*t] == Token::Punct('{'))
+                    .unwrap_or(close);
+
+                if fn_name == "glsl_mod" {
+                    i = matching_close(tokens, body_open, '{', '}').ok_or(UNBALANCED)? + 1;
+                    continue;
+                }
+
+                let mut params = Vec::new();
+                let mut cursor = open + 1;
+                while cursor < close {
+                    if let (Token::Ident(param), Some(colon)) =
+                        (&tokens[cursor], next_significant(tokens, cursor + 1))
+                    {
+                        if is_punct(tokens, Some(colon), ':') {
+                            let type_index = next_significant(tokens, colon + 1).unwrap_or(colon);
+                            let (ty, end) = wgsl_type(tokens, type_index, report)?;
+                            params.push(format!("{} {}", ty, param));
+                            cursor = end + 1;
+                            continue;
+                        }
+                    }
+                    cursor += 1;
+                }
+
+                let return_type = match next_significant(tokens, close + 1) {
+                    Some(arrow) if is_punct(tokens, Some(arrow), '-') => {
+                        let type_index = next_significant(tokens, arrow + 2).unwrap_or(arrow);
+                        wgsl_type(tokens, type_index, report)?.0
+                    }
+                    _ => "void".to_string(),
+                };
+                out.push_str(&format!(
+                    "{} {}({}) {{",
+                    return_type,
+                    fn_name,
+                    params.join(", ")
+                ));
+                brace_depth += 1;
+                i = body_open + 1;
+                continue;
+            }
+            Token::Ident(name) if name == "var" || name == "let" || name == "const" => {
+                let mut name_index = next_significant(tokens, i + 1);
+                if is_punct(tokens, name_index, '<') {
+                    name_index = next_significant(
+                        tokens,
+                        matching_close(tokens, name_index.unwrap(), '<', '>').ok_or(UNBALANCED)?
+                            + 1,
+                    );
+                }
+                let var_name = ident_at(tokens, name_index).unwrap_or("").to_string();
+                let after = name_index.and_then(|n| next_significant(tokens, n + 1));
+                let prefix = if name == "var" { "" } else { "const " };
+                if is_punct(tokens, after, ':') {
+                    let type_index = next_significant(tokens, after.unwrap() + 1).unwrap_or(i);
+                    let (ty, end) = wgsl_type(tokens, type_index, report)?;
+                    out.push_str(&format!("{}{} {}", prefix, ty, var_name));
+                    i = end + 1;
+                } else {
+                    report.unsupported(format!(
+                        "inferred type for {} (declared as float)",
+                        var_name
+                    ));
+                    out.push_str(&format!("{}float {}", prefix, var_name));
+                    i = name_index.map_or(i + 1, |n| n + 1);
+                }
+                continue;
+            }
+            Token::Ident(name) if name == "return" && entry_scope.is_some() => {
+                let end = statement_end(tokens, i)
+                    .ok_or("`return` without `;` in the fragment entry point")?;
+                let expression = translate_wgsl(&tokens[i + 1..end], &local_renames, report)?;
+                out.push_str(&format!("gl_FragColor = {}; return", expression.trim()));
+                i = end;
+                continue;
+            }
+            Token::Ident(name) if name == "loop" || name == "select" || name == "bitcast" => {
+                report.unsupported(format!("WGSL builtin {}", name));
+                out.push_str(name);
+            }
+            Token::Ident(name) => {
+                let next = next_significant(tokens, i + 1);
+                let is_type_constructor = is_punct(tokens, next, '<')
+                    && (name.starts_with("vec") || name.starts_with("mat") || name == "array");
+                if is_type_constructor
+                    || matches!(
+                        name.as_str(),
+                        "f32" | "i32" | "u32" | "vec2f" | "vec3f" | "vec4f"
+                    )
+                {
+                    let (ty, end) = wgsl_type(tokens, i, report)?;
+                    out.push_str(&ty);
+                    i = end + 1;
+                    continue;
+                }
+                out.push_str(&rename(name, &local_renames));
+            }
+            Token::Punct(c) => {
+                if *c == '{' {
+                    brace_depth += 1;
+                } else if *c == '}' {
+                    if entry_scope == Some(brace_depth) {
+                        entry_scope = None;
+                    }
+                    brace_depth = brace_depth.saturating_sub(1);
+                }
+                out.push(*c);
+            }
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADERTOY: &str = r#"
+// Simple gradient
+float wave(float x, float speed) {
+    return 0.5 + 0.5 * sin(x + iTime * speed);
+}
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    float r = wave(uv.x, 1.0), g = wave(uv.y, 2.0);
+    if (r > 0.9) return;
+    for (int i = 0; i < 3; i++) {
+        r = mod(r + 0.1, 1.0);
+    }
+    vec3 tex = texture(iChannel0, uv).rgb;
+    fragColor = vec4(r, g, tex.b, 1.0);
+}
+"#;
+
+    const ISF: &str = r#"/*{
+    "DESCRIPTION": "Pulse",
+    "INPUTS": [
+        { "NAME": "speed", "TYPE": "float", "DEFAULT": 2.0, "MIN": 0.0, "MAX": 10.0 },
+        { "NAME": "tint", "TYPE": "color", "DEFAULT": [1.0, 0.5, 0.25, 1.0] },
+        { "NAME": "inputImage", "TYPE": "image" }
+    ]
+}*/
+
+void main() {
+    vec2 uv = isf_FragNormCoord;
+    float pulse = 0.5 + 0.5 * sin(TIME * speed);
+    gl_FragColor = vec4(tint.rgb * pulse * uv.x, 1.0);
+}
+"#;
+
+    #[test]
+    fn test_import_shadertoy() {
+        let conversion =
+            import_shadertoy("st".to_string(), "Gradient".to_string(), SHADERTOY).unwrap();
+        let code = &conversion.shader.fragment_code;
+
+        assert!(code.contains("fn wave(x: f32, speed: f32) -> f32"));
+        assert!(code.contains("fn mainImage(fragCoord: vec2<f32>) -> vec4<f32>"));
+        assert!(code.contains("var uv: vec2<f32> = fragCoord / vec3<f32>(resolution, 1.0).xy"));
+        assert!(code.contains("var r: f32 = wave(uv.x, 1.0); var g: f32"));
+        assert!(code.contains("for (var i: i32 = 0;"));
+        assert!(code.contains("if (r > 0.9) { return fragColor; }"));
+        assert!(code.contains("return fragColor;"));
+        assert!(code.contains("fn glsl_mod"));
+        assert!(code.contains("@fragment"));
+        assert!(!code.contains("iTime"));
+
+        let report = &conversion.report;
+        assert!(report
+            .mapped_uniforms
+            .contains(&"iTime -> time".to_string()));
+        assert!(report
+            .mapped_uniforms
+            .contains(&"iResolution -> resolution".to_string()));
+        assert!(report.unsupported.iter().any(|u| u.contains("iChannel0")));
+        assert!(report.unsupported.iter().any(|u| u.contains("texture(")));
+        assert!(!report.is_lossless());
+    }
+
+    #[test]
+    fn test_shadertoy_frame_uniforms() {
+        let source =
+            "void mainImage(out vec4 c, in vec2 p) { c = vec4(float(iFrame) * iTimeDelta); }";
+        let conversion = import_shadertoy("st".to_string(), "Frames".to_string(), source).unwrap();
+        let names: Vec<&str> = conversion
+            .params
+            .custom_uniforms
+            .iter()
+            .map(|u| u.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["time_delta", "frame"]);
+        assert!(conversion
+            .shader
+            .fragment_code
+            .contains("var<uniform> frame: f32"));
+        assert!(conversion.report.is_lossless());
+    }
+
+    #[test]
+    fn test_import_isf_inputs() {
+        let conversion = import_isf("isf".to_string(), "Pulse".to_string(), ISF).unwrap();
+        let uniforms = &conversion.params.custom_uniforms;
+
+        assert_eq!(uniforms.len(), 2);
+        assert_eq!(uniforms[0].name, "speed");
+        assert!(matches!(uniforms[0].value_type, UniformType::Float));
+        assert_eq!(uniforms[0].value, vec![2.0]);
+        assert!(matches!(uniforms[1].value_type, UniformType::Vec4));
+        assert_eq!(uniforms[1].value, vec![1.0, 0.5, 0.25, 1.0]);
+
+        let code = &conversion.shader.fragment_code;
+        assert!(code.contains("var<uniform> speed: f32"));
+        assert!(code.contains("sin(time * speed)"));
+        assert!(code.contains("return frag_color;"));
+        assert!(conversion
+            .report
+            .unsupported
+            .iter()
+            .any(|u| u.contains("inputImage")));
+        assert!(conversion
+            .report
+            .warnings
+            .iter()
+            .any(|w| w.contains("MIN/MAX")));
+    }
+
+    #[test]
+    fn test_isf_requires_header() {
+        assert!(import_isf("isf".to_string(), "Bad".to_string(), "void main() {}").is_err());
+        let unterminated = import_isf("isf".to_string(), "Bad".to_string(), "/*/ void main() {}");
+        assert_eq!(
+            unterminated.err().as_deref(),
+            Some("Unterminated ISF header")
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_brackets_are_errors() {
+        let truncated = "void mainImage(out vec4 c, in vec2 p) { c = vec4(atan(";
+        let conversion = import_shadertoy("st".to_string(), "Cut".to_string(), truncated);
+        assert_eq!(conversion.err().as_deref(), Some(UNBALANCED));
+
+        let mut shader = WGSLShader::new("cut".to_string(), "Cut".to_string());
+        shader.fragment_code = "fn wave(x: f32 -> f32 { return x; }".to_string();
+        assert!(export_isf(&shader, &ShaderParams::default()).is_err());
+
+        for stray in [
+            "}}}}",
+            ")))",
+            "void mainImage(out vec4 c, in vec2 p) { c = vec4(1.0)); }",
+        ] {
+            let conversion = import_shadertoy("st".to_string(), "Stray".to_string(), stray);
+            assert_eq!(conversion.err().as_deref(), Some(UNBALANCED));
+        }
+        shader.fragment_code = ")))".to_string();
+        assert_eq!(
+            export_isf(&shader, &ShaderParams::default())
+                .err()
+                .as_deref(),
+            Some(UNBALANCED)
+        );
+    }
+
+    #[test]
+    fn test_return_without_semicolon_is_an_error() {
+        let mut shader = WGSLShader::new("ret".to_string(), "Ret".to_string());
+        shader.fragment_code =
+            "@fragment fn f() -> vec4<f32> { return vec4<f32>(1.0) }".to_string();
+        assert_eq!(
+            export_isf(&shader, &ShaderParams::default())
+                .err()
+                .as_deref(),
+            Some("`return` without `;` in the fragment entry point")
+        );
+    }
+
+    #[test]
+    fn test_export_isf_round_trip() {
+        let conversion = import_isf("isf".to_string(), "Pulse".to_string(), ISF).unwrap();
+        let (document, report) = export_isf(&conversion.shader, &conversion.params).unwrap();
+
+        assert!(document.starts_with("/*{"));
+        assert!(document.contains("\"NAME\": \"speed\""));
+        assert!(document.contains("\"TYPE\": \"color\""));
+        assert!(document.contains("vec4 mainImage(vec2 fragCoord)"));
+        assert!(document.contains("void main() {"));
+        assert!(document.contains("gl_FragColor = mainImage("));
+        assert!(document.contains("sin(TIME * speed)"));
+        assert!(!document.contains("@group"));
+
+        let reimported = import_isf("again".to_string(), "Pulse".to_string(), &document).unwrap();
+        assert_eq!(
+            reimported.params.custom_uniforms.len(),
+            conversion.params.custom_uniforms.len()
+        );
+        assert!(report.unsupported.is_empty());
+    }
+
+    #[test]
+    fn test_export_template() {
+        let shader = WGSLShader::new("audio".to_string(), "Audio".to_string());
+        let (document, _) = export_isf(&shader, &ShaderParams::default()).unwrap();
+        assert!(document.contains("void main() {"));
+        assert!(document.contains("gl_FragColor = vec4"));
+        assert!(document.contains("RENDERSIZE"));
+        assert!(!document.contains("point2D"));
+    }
+}
@@ -0,0 +1,471 @@
+//! Signal models - Raw biometric features to valence/arousal/dominance
+//!
+//! Each model declares the schema of the `raw_vector` it consumes and maps
+//! it to a VAD estimate with confidence. Linear models can be loaded from
+//! JSON weights.
+
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+
+/// Confidence lost for each feature that had to be clamped into range
+const OUT_OF_RANGE_PENALTY: f32 = 0.15;
+
+/// One entry of a model's input schema
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeatureSpec {
+    pub name: String,
+    pub unit: String,
+    /// Plausible range; values outside are clamped and lower confidence
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Declared layout of a raw feature vector
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignalSchema {
+    pub features: Vec<FeatureSpec>,
+}
+
+/// VAD estimate produced by a signal model
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VadEstimate {
+    pub valence: f32,
+    pub arousal: f32,
+    pub dominance: f32,
+    pub confidence: f32,
+}
+
+/// Maps a raw feature vector with a declared schema to VAD
+pub trait SignalModel {
+    fn name(&self) -> &str;
+
+    fn schema(&self) -> &SignalSchema;
+
+    /// Estimate VAD from features already checked against the schema
+    fn estimate_features(&self, features: &[f32]) -> VadEstimate;
+
+    /// Check `raw` against the schema, then estimate VAD
+    fn estimate(&self, raw: &[f32]) -> Result<VadEstimate, String> {
+        let (features, clamped) = self.schema().conform(raw)?;
+        let mut estimate = self.estimate_features(&features);
+        estimate.confidence *= (1.0 - OUT_OF_RANGE_PENALTY * clamped as f32).max(0.0);
+        Ok(estimate.clamped())
+    }
+}
+
+impl FeatureSpec {
+    pub fn new(name: &str, unit: &str, min: f32, max: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            min,
+            max,
+        }
+    }
+}
+
+impl SignalSchema {
+    pub fn new(features: Vec<FeatureSpec>) -> Self {
+        Self { features }
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Index of a named feature
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.features.iter().position(|f| f.name == name)
+    }
+
+    /// Validate length and finiteness, clamping values into their declared
+    /// ranges; returns the features and how many were clamped
+    pub fn conform(&self, raw: &[f32]) -> Result<(Vec<f32>, usize), String> {
+        if raw.len() != self.features.len() {
+            return Err(format!(
+                "Expected {} features, got {}",
+                self.features.len(),
+                raw.len()
+            ));
+        }
+
+        let mut clamped = 0;
+        let features = raw
+            .iter()
+            .zip(&self.features)
+            .map(|(value, spec)| {
+                if !value.is_finite() {
+                    return Err(format!("Feature {} is not finite", spec.name));
+                }
+                let bounded = value.clamp(spec.min, spec.max);
+                if bounded != *value {
+                    clamped += 1;
+                }
+                Ok(bounded)
+            })
+            .collect::<Result<Vec<f32>, String>>()?;
+        Ok((features, clamped))
+    }
+}
+
+impl VadEstimate {
+    fn clamped(self) -> Self {
+        Self {
+            valence: self.valence.clamp(-1.0, 1.0),
+            arousal: self.arousal.clamp(0.0, 1.0),
+            dominance: self.dominance.clamp(0.0, 1.0),
+            confidence: self.confidence.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Frontal EEG band powers (left/right alpha, beta, theta)
+///
+/// Valence follows frontal alpha asymmetry (relatively more right alpha means
+/// more left activity and approach motivation), arousal the beta share of
+/// total power, and dominance the left-right beta balance.
+pub struct EegBandPowerModel {
+    schema: SignalSchema,
+}
+
+impl EegBandPowerModel {
+    pub fn new() -> Self {
+        let band = |name: &str| FeatureSpec::new(name, "uV^2", 0.01, 1000.0);
+        Self {
+            schema: SignalSchema::new(vec![
+                band("alpha_left"),
+                band("alpha_right"),
+                band("beta_left"),
+                band("beta_right"),
+                band("theta_left"),
+                band("theta_right"),
+            ]),
+        }
+    }
+}
+
+impl Default for EegBandPowerModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignalModel for EegBandPowerModel {
+    fn name(&self) -> &str {
+        "eeg_band_power"
+    }
+
+    fn schema(&self) -> &SignalSchema {
+        &self.schema
+    }
+
+    fn estimate_features(&self, f: &[f32]) -> VadEstimate {
+        let (alpha_l, alpha_r, beta_l, beta_r, theta_l, theta_r) =
+            (f[0], f[1], f[2], f[3], f[4], f[5]);
+        let alpha = alpha_l + alpha_r;
+        let beta = beta_l + beta_r;
+        let theta = theta_l + theta_r;
+
+        let alpha_asymmetry = alpha_r.ln() - alpha_l.ln();
+        let beta_asymmetry = beta_l.ln() - beta_r.ln();
+        let engagement = beta / (alpha + theta);
+
+        // Asymmetry agreement between bands raises confidence
+        let consistency = 1.0 - ((alpha_asymmetry.tanh() - beta_asymmetry.tanh()).abs() / 2.0);
+
+        VadEstimate {
+            valence: alpha_asymmetry.tanh(),
+            arousal: logistic(engagement.ln() * 2.0),
+            dominance: logistic(beta_asymmetry * 2.0),
+            confidence: 0.5 + 0.4 * consistency,
+        }
+    }
+}
+
+/// Heart rate and heart-rate variability
+///
+/// Arousal rises with heart rate and falls with vagal tone (RMSSD); higher
+/// HRV is weakly associated with positive valence and a high LF/HF ratio
+/// with sympathetic stress.
+pub struct HrvModel {
+    schema: SignalSchema,
+}
+
+impl HrvModel {
+    pub fn new() -> Self {
+        Self {
+            schema: SignalSchema::new(vec![
+                FeatureSpec::new("heart_rate", "bpm", 30.0, 220.0),
+                FeatureSpec::new("rmssd", "ms", 1.0, 300.0),
+                FeatureSpec::new("lf_hf_ratio", "ratio", 0.05, 20.0),
+            ]),
+        }
+    }
+}
+
+impl Default for HrvModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignalModel for HrvModel {
+    fn name(&self) -> &str {
+        "hrv"
+    }
+
+    fn schema(&self) -> &SignalSchema {
+        &self.schema
+    }
+
+    fn estimate_features(&self, f: &[f32]) -> VadEstimate {
+        let (heart_rate, rmssd, lf_hf) = (f[0], f[1], f[2]);
+        let tone = (rmssd / 40.0).ln();
+        let stress = lf_hf.ln();
+
+        VadEstimate {
+            valence: (0.6 * tone - 0.3 * stress).tanh() * 0.6,
+            arousal: logistic((heart_rate - 80.0) / 15.0 - 0.5 * tone),
+            dominance: logistic(0.8 * tone - 0.4 * stress),
+            // HRV says more about arousal than valence or dominance
+            confidence: 0.55,
+        }
+    }
+}
+
+/// Galvanic skin response
+///
+/// Skin conductance level and phasic response rate track sympathetic
+/// arousal only; valence and dominance stay neutral.
+pub struct GsrModel {
+    schema: SignalSchema,
+}
+
+impl GsrModel {
+    pub fn new() -> Self {
+        Self {
+            schema: SignalSchema::new(vec![
+                FeatureSpec::new("skin_conductance_level", "uS", 0.05, 60.0),
+                FeatureSpec::new("scr_per_minute", "1/min", 0.0, 60.0),
+            ]),
+        }
+    }
+}
+
+impl Default for GsrModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignalModel for GsrModel {
+    fn name(&self) -> &str {
+        "gsr"
+    }
+
+    fn schema(&self) -> &SignalSchema {
+        &self.schema
+    }
+
+    fn estimate_features(&self, f: &[f32]) -> VadEstimate {
+        let (level, responses) = (f[0], f[1]);
+        VadEstimate {
+            valence: 0.0,
+            arousal: logistic((level / 5.0).ln() + (responses - 4.0) / 4.0),
+            dominance: 0.5,
+            confidence: 0.4,
+        }
+    }
+}
+
+/// Weights and bias for one output dimension
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LinearOutput {
+    pub bias: f32,
+    pub weights: Vec<f32>,
+}
+
+/// Linear model with weights loaded from JSON
+///
+/// Valence is `tanh` of its linear output; arousal and dominance use the
+/// logistic function, so weights are fitted in logit space.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LinearSignalModel {
+    pub name: String,
+    pub schema: SignalSchema,
+    pub valence: LinearOutput,
+    pub arousal: LinearOutput,
+    pub dominance: LinearOutput,
+    pub confidence: f32,
+}
+
+impl LinearSignalModel {
+    /// Parse and validate a model from its JSON weights
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let model: Self =
+            serde_json::from_str(json).map_err(|e| format!("Invalid signal model: {}", e))?;
+        model.validate()?;
+        Ok(model)
+    }
+
+    /// Load a model from a JSON weights file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: &std::path::Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schema.is_empty() {
+            return Err("Signal model has no features".to_string());
+        }
+        for spec in &self.schema.features {
+            if !spec.min.is_finite() || !spec.max.is_finite() || spec.min >= spec.max {
+                return Err(format!("Feature {} has an empty range", spec.name));
+            }
+        }
+        for (label, output) in [
+            ("valence", &self.valence),
+            ("arousal", &self.arousal),
+            ("dominance", &self.dominance),
+        ] {
+            if output.weights.len() != self.schema.len() {
+                return Err(format!(
+                    "{} has {} weights for {} features",
+                    label,
+                    output.weights.len(),
+                    self.schema.len()
+                ));
+            }
+            if !output.bias.is_finite() || output.weights.iter().any(|w| !w.is_finite()) {
+                return Err(format!("{} has non-finite weights", label));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.confidence) {
+            return Err("Confidence must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl LinearOutput {
+    fn apply(&self, features: &[f32]) -> f32 {
+        self.bias
+            + self
+                .weights
+                .iter()
+                .zip(features)
+                .map(|(w, x)| w * x)
+                .sum::<f32>()
+    }
+}
+
+impl SignalModel for LinearSignalModel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn schema(&self) -> &SignalSchema {
+        &self.schema
+    }
+
+    fn estimate_features(&self, features: &[f32]) -> VadEstimate {
+        VadEstimate {
+            valence: self.valence.apply(features).tanh(),
+            arousal: logistic(self.arousal.apply(features)),
+            dominance: logistic(self.dominance.apply(features)),
+            confidence: self.confidence,
+        }
+    }
+}
+
+fn logistic(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eeg_alpha_asymmetry_drives_valence() {
+        let model = EegBandPowerModel::new();
+        // More right alpha: relatively more left-frontal activity
+        let approach = model.estimate(&[8.0, 14.0, 5.0, 5.0, 6.0, 6.0]).unwrap();
+        let withdraw = model.estimate(&[14.0, 8.0, 5.0, 5.0, 6.0, 6.0]).unwrap();
+        assert!(approach.valence > 0.2);
+        assert!(withdraw.valence < -0.2);
+
+        let calm = model.estimate(&[20.0, 20.0, 4.0, 4.0, 10.0, 10.0]).unwrap();
+        let engaged = model.estimate(&[5.0, 5.0, 20.0, 20.0, 4.0, 4.0]).unwrap();
+        assert!(engaged.arousal > calm.arousal);
+    }
+
+    #[test]
+    fn test_hrv_and_gsr_arousal() {
+        let hrv = HrvModel::new();
+        let resting = hrv.estimate(&[60.0, 60.0, 1.0]).unwrap();
+        let stressed = hrv.estimate(&[115.0, 15.0, 4.0]).unwrap();
+        assert!(stressed.arousal > 0.8 && resting.arousal < 0.3);
+        assert!(stressed.valence < resting.valence);
+
+        let gsr = GsrModel::new();
+        let low = gsr.estimate(&[1.0, 1.0]).unwrap();
+        let high = gsr.estimate(&[20.0, 12.0]).unwrap();
+        assert!(high.arousal > low.arousal);
+        assert_eq!(high.valence, 0.0);
+    }
+
+    #[test]
+    fn test_schema_enforced() {
+        let model = HrvModel::new();
+        assert!(model
+            .estimate(&[70.0, 40.0])
+            .unwrap_err()
+            .contains("Expected 3"));
+        assert!(model.estimate(&[f32::NAN, 40.0, 1.0]).is_err());
+
+        let in_range = model.estimate(&[70.0, 40.0, 1.0]).unwrap();
+        let clamped = model.estimate(&[400.0, 40.0, 1.0]).unwrap();
+        assert!(clamped.confidence < in_range.confidence);
+    }
+
+    #[test]
+    fn test_linear_model_from_json() {
+        let json = r#"{
+            "name": "wearable_v1",
+            "schema": { "features": [
+                { "name": "hr_z", "unit": "z", "min": -5.0, "max": 5.0 },
+                { "name": "eda_z", "unit": "z", "min": -5.0, "max": 5.0 }
+            ]},
+            "valence": { "bias": 0.0, "weights": [-0.2, -0.3] },
+            "arousal": { "bias": 0.0, "weights": [1.0, 1.5] },
+            "dominance": { "bias": 0.0, "weights": [0.0, 0.0] },
+            "confidence": 0.7
+        }"#;
+        let model = LinearSignalModel::from_json(json).unwrap();
+        assert_eq!(model.name(), "wearable_v1");
+
+        let estimate = model.estimate(&[1.0, 1.0]).unwrap();
+        assert!(estimate.arousal > 0.9);
+        assert!(estimate.valence < 0.0);
+        assert_eq!(estimate.dominance, 0.5);
+        assert_eq!(estimate.confidence, 0.7);
+
+        let bad = json.replace("[1.0, 1.5]", "[1.0]");
+        assert!(LinearSignalModel::from_json(&bad)
+            .unwrap_err()
+            .contains("arousal"));
+    }
+}
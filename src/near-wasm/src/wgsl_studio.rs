@@ -6,6 +6,9 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env};
 
+use crate::performance_stats::{PerformanceSummary, SessionPerformance};
+use crate::render_graph::RenderGraph;
+
 /// WGSL shader program
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -58,6 +61,9 @@ pub struct WGSLSession {
     pub params: ShaderParams,
     pub edit_history: Vec<ShaderEdit>,
     pub performance_metrics: PerformanceMetrics,
+    pub render_graph: Option<RenderGraph>,
+    #[serde(default)]
+    pub performance: SessionPerformance,
 }
 
 /// Shader edit for version tracking
@@ -69,7 +75,7 @@ pub struct ShaderEdit {
     pub description: String,
 }
 
-/// Performance metrics for shader
+/// Latest performance metrics, derived from the session's sample history
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PerformanceMetrics {
@@ -194,25 +200,94 @@ impl WGSLSession {
             params: ShaderParams::default(),
             edit_history: Vec::new(),
             performance_metrics: PerformanceMetrics::default(),
+            render_graph: None,
+            performance: SessionPerformance::new(),
         }
     }
 
+    /// Attach a multi-pass render graph after validating it
+    pub fn set_render_graph(&mut self, mut graph: RenderGraph) -> Result<Vec<String>, String> {
+        let order = graph.validate()?;
+        graph.refresh_shader(&self.shader);
+        self.render_graph = Some(graph);
+        Ok(order)
+    }
+
+    /// Render graph for the session, falling back to a single pass of the main shader
+    pub fn effective_render_graph(&self) -> RenderGraph {
+        self.render_graph
+            .clone()
+            .unwrap_or_else(|| RenderGraph::single_pass(self.shader.clone()))
+    }
+
     /// Record a shader edit
     pub fn record_edit(&mut self, fragment_code: String, description: String) {
+        self.record_edit_at(fragment_code, description, env::block_timestamp());
+    }
+
+    /// Record a shader edit with an explicit timestamp (used by replay)
+    pub fn record_edit_at(&mut self, fragment_code: String, description: String, timestamp: u64) {
         self.edit_history.push(ShaderEdit {
-            timestamp: env::block_timestamp(),
+            timestamp,
             fragment_code: fragment_code.clone(),
             description,
         });
         self.shader.fragment_code = fragment_code;
+        // Graph nodes hold copies of the session shader; keep them current
+        if let Some(graph) = &mut self.render_graph {
+            graph.refresh_shader(&self.shader);
+        }
     }
 
-    /// Update performance metrics
+    /// Set a custom uniform, adding it if missing
+    pub fn set_uniform(&mut self, uniform: UniformParam) {
+        match self.params.custom_uniforms.iter_mut().find(|u| u.name == uniform.name) {
+            Some(existing) => *existing = uniform,
+            None => self.params.custom_uniforms.push(uniform),
+        }
+    }
+
+    /// Update performance metrics from a single client report
+    ///
+    /// The reported fps is an average, so it is kept apart from the raw
+    /// frame samples and only used for `avg_fps` until samples arrive.
     pub fn update_metrics(&mut self, fps: f32, compile_time: f32, gpu_memory: f32) {
+        let shader_id = self.shader.shader_id.clone();
+        self.performance.record_reported_fps(&shader_id, fps);
+        self.performance.record_compile_time(&shader_id, compile_time);
+        self.performance.record_gpu_memory(&shader_id, gpu_memory);
+        self.refresh_metrics();
+    }
+
+    /// Record raw frame times (milliseconds) for the active shader
+    pub fn record_frame_times(&mut self, frame_times_ms: &[f32]) {
+        let shader_id = self.shader.shader_id.clone();
+        self.performance.record_frame_times(&shader_id, frame_times_ms);
+        self.refresh_metrics();
+    }
+
+    /// Session-wide performance summary
+    pub fn performance_summary(&self) -> PerformanceSummary {
+        self.performance.summary()
+    }
+
+    /// Performance summary for one shader used in this session
+    pub fn shader_performance_summary(&self, shader_id: &str) -> Option<PerformanceSummary> {
+        self.performance.shader_summary(shader_id)
+    }
+
+    fn refresh_metrics(&mut self) {
+        let summary = self.performance.summary();
         self.performance_metrics = PerformanceMetrics {
-            avg_fps: fps,
-            compile_time_ms: compile_time,
-            gpu_memory_mb: gpu_memory,
+            avg_fps: if summary.sample_count > 0 {
+                summary.mean_fps
+            } else if summary.mean_reported_fps > 0.0 {
+                summary.mean_reported_fps
+            } else {
+                PerformanceMetrics::default().avg_fps
+            },
+            compile_time_ms: summary.last_compile_ms.unwrap_or(0.0),
+            gpu_memory_mb: summary.gpu_memory_last_mb,
         };
     }
 }
@@ -241,6 +316,7 @@ impl Default for PerformanceMetrics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render_graph::{RenderNode, TextureFormat};
 
     #[test]
     fn test_wgsl_shader_creation() {
@@ -259,4 +335,57 @@ mod tests {
         let audio = WGSLShader::audio_reactive_template();
         assert!(audio.contains("audio_bass"));
     }
+
+    #[test]
+    fn test_session_render_graph() {
+        let shader = WGSLShader::new("main".to_string(), "Main".to_string());
+        let mut session = WGSLSession::new("session".to_string(), shader);
+        assert!(session.render_graph.is_none());
+        assert_eq!(session.effective_render_graph().validate().unwrap(), vec!["main".to_string()]);
+
+        let graph = RenderGraph::new();
+        assert!(session.set_render_graph(graph).is_err());
+        assert!(session.render_graph.is_none());
+    }
+
+    #[test]
+    fn test_edits_reach_the_render_graph() {
+        let shader = WGSLShader::new("main".to_string(), "Main".to_string());
+        let mut session = WGSLSession::new("session".to_string(), shader.clone());
+        let mut graph = RenderGraph::single_pass(shader);
+        graph.add_texture("trail".to_string(), TextureFormat::Rgba16Float, 0.5);
+        graph.add_node(RenderNode {
+            node_id: "trail".to_string(),
+            shader: WGSLShader::new("trail".to_string(), "Trail".to_string()),
+            inputs: vec![],
+            output_texture: "trail".to_string(),
+        });
+        session.set_render_graph(graph).unwrap();
+
+        session.record_edit_at("// edited".to_string(), "Edit".to_string(), 1);
+        let graph = session.render_graph.as_ref().unwrap();
+        assert_eq!(graph.nodes[0].shader.fragment_code, "// edited");
+        assert_ne!(graph.nodes[1].shader.fragment_code, "// edited");
+    }
+
+    #[test]
+    fn test_metrics_derived_from_samples() {
+        let shader = WGSLShader::new("main".to_string(), "Main".to_string());
+        let mut session = WGSLSession::new("session".to_string(), shader);
+
+        session.update_metrics(30.0, 5.0, 32.0);
+        assert_eq!(session.performance_metrics.avg_fps, 30.0);
+
+        session.record_frame_times(&[20.0, 20.0, 20.0]);
+        session.update_metrics(1000.0, 5.0, 32.0);
+
+        // Client averages never enter the frame-time percentiles
+        let summary = session.performance_summary();
+        assert_eq!(summary.total_frames, 3);
+        assert_eq!(summary.p99_frame_ms, 20.0);
+        assert_eq!(summary.mean_reported_fps, 515.0);
+        assert!((session.performance_metrics.avg_fps - 50.0).abs() < 0.01);
+        assert_eq!(session.performance_metrics.compile_time_ms, 5.0);
+        assert_eq!(session.shader_performance_summary("main").unwrap().total_frames, 3);
+    }
 }